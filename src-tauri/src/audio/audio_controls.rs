@@ -4,12 +4,23 @@ use once_cell::sync::OnceCell;
 use std::sync::Mutex;
 
 use super::audio_handler::AudioHandler;
+use super::channel_map::ChannelMapSettings;
 use super::device::AudioDeviceOptions;
+use super::output::{OutputKind, OutputLevelInfo};
+use super::presets::PresetStore;
+use super::recorder::RecordingSettings;
+use crate::dsp::modules::carrier::CarrierSource;
+use crate::dsp::modules::modulation_matrix::{ModulationMatrixSettings, ModulationRoute};
 use crate::dsp::modules::utils::ParameterValue;
+use crate::dsp::modules::visualizer::audio_spectrum::SpectrogramSnapshot;
+use crate::dsp::modules::visualizer::settings::VisualizerSettings;
+use crate::music_bed::MusicBedManager;
+use crate::soundboard::SoundboardManager;
 
 pub struct AudioControls {
     audio_handler: AudioHandler,
     options: AudioDeviceOptions,
+    presets: PresetStore,
 }
 
 static AUDIO_CONTROLS: OnceCell<Mutex<AudioControls>> = OnceCell::new();
@@ -25,6 +36,7 @@ impl AudioControls {
         AudioControls {
             audio_handler,
             options,
+            presets: PresetStore::load(),
         }
     }
 
@@ -92,6 +104,19 @@ impl AudioControls {
         self.audio_handler.select_audio_devices(&self.options)
     }
 
+    // Channel mapping
+    pub fn set_channel_map(&mut self, settings: ChannelMapSettings) -> anyhow::Result<()> {
+        self.audio_handler.set_channel_map(settings)
+    }
+
+    pub fn get_channel_map(&self) -> ChannelMapSettings {
+        self.audio_handler.get_channel_map()
+    }
+
+    pub fn get_input_channel_count(&self) -> usize {
+        self.audio_handler.input_channel_count()
+    }
+
     // Switch audio engine modes
     pub fn start_audio_engine_loopback(&mut self) -> anyhow::Result<()> {
         self.audio_handler.start_audio_engine_loopback()
@@ -106,7 +131,15 @@ impl AudioControls {
     }
 
     pub fn stop_audio_engine_throughput(&mut self) -> anyhow::Result<()> {
-        self.audio_handler.stop_audio_engine_throughput()
+        self.audio_handler.stop_audio_engine_throughput()?;
+        // Engine restarts keep the soundboard and music bed playing, only a real stop silences them
+        if let Ok(mut soundboard) = SoundboardManager::get_instance().lock() {
+            soundboard.detach();
+        }
+        if let Ok(mut music_bed) = MusicBedManager::get_instance().lock() {
+            music_bed.detach();
+        }
+        Ok(())
     }
 
     // Audio engine status
@@ -127,6 +160,10 @@ impl AudioControls {
         self.audio_handler.disable_modulation()
     }
 
+    pub fn is_modulation_active(&self) -> bool {
+        self.audio_handler.is_modulation_active()
+    }
+
     pub fn append_effect(&mut self, effect_name: &str) -> anyhow::Result<()> {
         self.audio_handler.append_effect_to_modulation(effect_name)
     }
@@ -140,6 +177,11 @@ impl AudioControls {
         self.audio_handler.set_effect_parameter(effect_name, parameter)
     }
 
+    pub fn set_effect_parameter_normalized(&mut self, effect_name: &str, parameter_name: &str, normalized: f32) -> anyhow::Result<()> {
+        self.audio_handler
+            .set_effect_parameter_normalized(effect_name, parameter_name, normalized)
+    }
+
     pub fn get_parameters(&self, effect_name: &str) -> anyhow::Result<Vec<crate::dsp::modules::utils::EffectParameter>> {
         self.audio_handler.get_effect_parameters(effect_name)
     }
@@ -152,10 +194,137 @@ impl AudioControls {
         self.audio_handler.get_auto_tune_scale()
     }
 
+    pub fn set_vocoder_scale(&mut self, scale: crate::dsp::modules::effects::auto_tune::Scale) -> anyhow::Result<()> {
+        self.audio_handler.set_vocoder_scale(scale)
+    }
+
+    pub fn get_vocoder_scale(&self) -> Option<crate::dsp::modules::effects::auto_tune::Scale> {
+        self.audio_handler.get_vocoder_scale()
+    }
+
+    pub fn set_vocoder_carrier(&mut self, source: CarrierSource) -> anyhow::Result<()> {
+        self.audio_handler.set_vocoder_carrier(source)
+    }
+
+    pub fn get_vocoder_carrier(&self) -> Option<CarrierSource> {
+        self.audio_handler.get_vocoder_carrier()
+    }
+
     pub fn get_active_effects(&self) -> Vec<String> {
         self.audio_handler.get_active_effects()
     }
 
+    // Modulation matrix controls
+    pub fn set_modulation_matrix(&mut self, settings: ModulationMatrixSettings) -> anyhow::Result<()> {
+        self.audio_handler.set_modulation_matrix(settings)
+    }
+
+    pub fn get_modulation_matrix(&self) -> ModulationMatrixSettings {
+        self.audio_handler.get_modulation_matrix()
+    }
+
+    pub fn add_modulation_route(&mut self, route: ModulationRoute) -> anyhow::Result<()> {
+        self.audio_handler.add_modulation_route(route)
+    }
+
+    pub fn remove_modulation_route(&mut self, index: usize) -> anyhow::Result<()> {
+        self.audio_handler.remove_modulation_route(index)
+    }
+
+    // Preset controls
+    pub fn save_preset(&mut self, name: &str) -> anyhow::Result<()> {
+        let preset = self.audio_handler.capture_preset(name)?;
+        self.presets.insert(preset);
+        self.presets.set_current(Some(name.to_string()));
+        self.presets.save()
+    }
+
+    pub fn load_preset(&mut self, name: &str) -> anyhow::Result<()> {
+        let preset = self
+            .presets
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Preset '{}' not found", name))?;
+        self.audio_handler.apply_preset(&preset)?;
+        self.presets.set_current(Some(preset.name));
+        self.presets.save()
+    }
+
+    pub fn delete_preset(&mut self, name: &str) -> anyhow::Result<()> {
+        self.presets.remove(name)?;
+        self.presets.save()
+    }
+
+    pub fn next_preset(&mut self) -> anyhow::Result<()> {
+        match self.presets.neighbour(1) {
+            Some(name) => self.load_preset(&name),
+            None => Err(anyhow::anyhow!("No presets saved")),
+        }
+    }
+
+    pub fn previous_preset(&mut self) -> anyhow::Result<()> {
+        match self.presets.neighbour(-1) {
+            Some(name) => self.load_preset(&name),
+            None => Err(anyhow::anyhow!("No presets saved")),
+        }
+    }
+
+    pub fn get_presets(&self) -> Vec<String> {
+        self.presets.names()
+    }
+
+    pub fn get_current_preset(&self) -> Option<String> {
+        self.presets.current()
+    }
+
+    // Output gate (push-to-talk / push-to-mute)
+    pub fn set_output_gate(&self, open: bool) {
+        self.audio_handler.set_output_gate(open)
+    }
+
+    pub fn is_output_gate_open(&self) -> bool {
+        self.audio_handler.is_output_gate_open()
+    }
+
+    // Per-output gain and mute (monitor / virtual cable)
+    pub fn set_output_gain(&self, output: OutputKind, gain: f32) -> anyhow::Result<()> {
+        self.audio_handler.set_output_gain(output, gain)
+    }
+
+    pub fn set_output_muted(&self, output: OutputKind, muted: bool) {
+        self.audio_handler.set_output_muted(output, muted)
+    }
+
+    pub fn get_output_levels(&self) -> Vec<OutputLevelInfo> {
+        self.audio_handler.get_output_levels()
+    }
+
+    // Tuner controls
+    pub fn set_tuner_reference(&mut self, reference: f32) -> anyhow::Result<()> {
+        self.audio_handler.set_tuner_reference(reference)
+    }
+
+    pub fn get_tuner_reference(&self) -> f32 {
+        self.audio_handler.get_tuner_reference()
+    }
+
+    // Visualizer controls
+    pub fn set_visualizer_settings(&mut self, settings: VisualizerSettings) -> anyhow::Result<()> {
+        self.audio_handler.set_visualizer_settings(settings)
+    }
+
+    pub fn get_visualizer_settings(&self) -> VisualizerSettings {
+        self.audio_handler.get_visualizer_settings()
+    }
+
+    pub fn get_spectrogram(&self) -> anyhow::Result<SpectrogramSnapshot> {
+        self.audio_handler.get_spectrogram()
+    }
+
+    pub fn reset_meters(&self) -> anyhow::Result<()> {
+        self.audio_handler.reset_meters()
+    }
+
     // App handle
 
     pub fn set_app_handle(&mut self, handle: tauri::AppHandle) -> anyhow::Result<()> {
@@ -191,4 +360,25 @@ impl AudioControls {
     pub fn get_file_save_path(&self) -> Option<String> {
         self.audio_handler.get_file_save_path()
     }
+
+    pub fn set_recording_settings(&mut self, settings: RecordingSettings) -> anyhow::Result<()> {
+        self.audio_handler.set_recording_settings(settings)
+    }
+
+    pub fn get_recording_settings(&self) -> RecordingSettings {
+        self.audio_handler.get_recording_settings()
+    }
+
+    // Instant replay controls
+    pub fn set_replay_duration(&mut self, seconds: f32) -> anyhow::Result<()> {
+        self.audio_handler.set_replay_duration(seconds)
+    }
+
+    pub fn get_replay_duration(&self) -> f32 {
+        self.audio_handler.get_replay_duration()
+    }
+
+    pub fn save_replay(&self) -> anyhow::Result<String> {
+        self.audio_handler.save_replay()
+    }
 }
@@ -1,27 +1,59 @@
+use super::channel_map::ChannelMapSettings;
 use super::device::*;
 use super::engine::*;
+use super::output::{OutputKind, OutputLevel, OutputLevelInfo, OutputTarget, MAX_OUTPUT_GAIN};
+use super::presets::{Preset, PresetEffect};
+use super::recorder::RecordingSettings;
+use super::replay::{save_replay, ReplayBuffer, MAX_REPLAY_SECONDS, MIN_REPLAY_SECONDS};
 use crate::dsp::modulation_unit::ModulationUnit;
+use crate::dsp::modules::carrier::{create_carrier, CarrierSource};
+use crate::dsp::modules::effects::Scale;
+use crate::dsp::modules::modulation_matrix::{ModulationMatrixSettings, ModulationRoute};
 use crate::dsp::modules::utils::ParameterValue;
+use crate::dsp::modules::visualizer::audio_spectrum::SpectrogramSnapshot;
+use crate::dsp::modules::visualizer::settings::VisualizerSettings;
+use crate::dsp::modules::visualizer::tuner::{DEFAULT_A4_REFERENCE, MAX_A4_REFERENCE, MIN_A4_REFERENCE};
+use crate::music_bed::mixer::MusicBedMixer;
+use crate::music_bed::MusicBedManager;
+use crate::soundboard::mixer::SoundboardMixer;
+use crate::soundboard::SoundboardManager;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+// How often the measured clock drift of each output is sent to the UI
+const DRIFT_REPORT_INTERVAL: Duration = Duration::from_secs(1);
 
 pub struct AudioHandler {
     options: AudioDeviceOptions,
     audio_devices: AudioDeviceManager,
 
-    loopback_handle: Option<JoinHandle<()>>,
-    loopback_control: Option<Arc<Mutex<bool>>>, // true = run, false = stop
-    loopback_running: bool,
-
-    throughput_handle: Option<JoinHandle<()>>,
-    throughput_control: Option<Arc<Mutex<bool>>>, // true = run, false = stop
-    throughput_running: bool,
+    // One engine serves both outputs - a single input stream and DSP pass
+    engine_handle: Option<JoinHandle<()>>,
+    engine_control: Option<Arc<Mutex<bool>>>, // true = run, false = stop
+    loopback_running: bool,   // monitor output enabled
+    throughput_running: bool, // virtual cable output enabled
 
     modulation_unit: Option<Arc<Mutex<ModulationUnit>>>,
+    channel_map: ChannelMapSettings,
 
     recorder_active: bool, 
-    file_save_path: Option<String>,
+    recording_settings: RecordingSettings,
+
+    replay_seconds: f32, // 0 = instant replay off
+    replay_buffer: Option<Arc<Mutex<ReplayBuffer>>>, // kept across engine restarts
+
+    tuner_reference: f32,
+    visualizer_settings: VisualizerSettings,
+    modulation_matrix: ModulationMatrixSettings,
+    vocoder_carrier: CarrierSource, // reopened whenever the chain gets a new vocoder
+    vocoder_scale: Scale,
+
+    output_gate: Arc<AtomicBool>, // true = throughput output audible (push-to-talk / push-to-mute)
+    monitor_level: Arc<OutputLevel>,
+    virtual_cable_level: Arc<OutputLevel>,
 }
 
 impl AudioHandler {
@@ -31,18 +63,29 @@ impl AudioHandler {
             audio_devices: AudioDeviceManager::default(),
             options: options,
 
-            loopback_handle: None,
-            loopback_control: None,
+            engine_handle: None,
+            engine_control: None,
             loopback_running: false,
-
-            throughput_handle: None,
-            throughput_control: None,
             throughput_running: false,
 
-            modulation_unit: Some(Arc::new(Mutex::new(ModulationUnit::new(44100)))),
+            modulation_unit: Some(Arc::new(Mutex::new(ModulationUnit::new(44100, 1)))),
+            channel_map: ChannelMapSettings::default(),
 
             recorder_active: false,
-            file_save_path: None,
+            recording_settings: RecordingSettings::default(),
+
+            replay_seconds: 0.0,
+            replay_buffer: None,
+
+            tuner_reference: DEFAULT_A4_REFERENCE,
+            visualizer_settings: VisualizerSettings::default(),
+            modulation_matrix: ModulationMatrixSettings::default(),
+            vocoder_carrier: CarrierSource::default(),
+            vocoder_scale: Scale::CMajor,
+
+            output_gate: Arc::new(AtomicBool::new(true)),
+            monitor_level: Arc::new(OutputLevel::new()),
+            virtual_cable_level: Arc::new(OutputLevel::new()),
         }
     }
 
@@ -81,24 +124,14 @@ impl AudioHandler {
         &self.audio_devices
     }
 
-    fn restart(&mut self) -> anyhow::Result<()> {
-        if self.loopback_running {
-            self.stop_audio_engine_loopback()?;
-            self.start_audio_engine_loopback()?;
-        }
-
-        if self.throughput_running {
-            self.stop_audio_engine_throughput()?;
-            self.start_audio_engine_throughput()?;
-        }
-
-        Ok(())
-    }
-
     // Use this every time options are changed - make sure to call this after changing options
     pub fn select_audio_devices(&mut self, opt: &AudioDeviceOptions) -> anyhow::Result<()> {
         self.audio_devices.select_devices_from_options(opt)?;
         self.options = opt.clone();
+        // A device with fewer channels may not have the ones picked for the voice
+        if self.channel_map.validate(self.input_channel_count()).is_err() {
+            self.channel_map.voice_channels.clear();
+        }
         self.modulation_unit = Some(Arc::new(Mutex::new(ModulationUnit::new(
             self.audio_devices
                 .get_input_device()
@@ -106,13 +139,57 @@ impl AudioHandler {
                 .get_config()
                 .sample_rate
                 .0 as usize,
+            self.channel_map.layout.channels(),
         ))));
+        self.apply_tuner_reference();
+        self.apply_visualizer_settings();
+        self.apply_modulation_matrix();
+        self.apply_vocoder_settings();
         // Restart engine if it is running
         self.restart()?;
 
         Ok(())
     }
 
+    // Channel mapping - changing the layout rebuilds the chain for the new channel count
+    pub fn set_channel_map(&mut self, settings: ChannelMapSettings) -> anyhow::Result<()> {
+        settings.validate(self.input_channel_count())?;
+        let preset = self.capture_preset("")?;
+        let previous = std::mem::replace(&mut self.channel_map, settings);
+        self.apply_channel_map();
+        // Restores the effects and restarts the engine with the new mapping
+        if let Err(e) = self.apply_preset(&preset) {
+            // Back to the previous layout with the chain that was playing
+            self.channel_map = previous;
+            self.apply_channel_map();
+            if let Err(restore) = self.apply_preset(&preset) {
+                eprintln!("Failed to restore the previous channel map: {}", restore);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    // Rebuilds the (empty) chain for the mapped channel count
+    fn apply_channel_map(&mut self) {
+        if let Some(ref unit) = self.modulation_unit {
+            let mut unit = unit.lock().unwrap();
+            unit.set_channels(self.channel_map.layout.channels());
+        }
+        self.apply_modulation_matrix();
+    }
+
+    pub fn get_channel_map(&self) -> ChannelMapSettings {
+        self.channel_map.clone()
+    }
+
+    pub fn input_channel_count(&self) -> usize {
+        self.audio_devices
+            .get_input_device()
+            .map(|device| device.get_config().channels as usize)
+            .unwrap_or(1)
+    }
+
     // Modulation unit methods
     pub fn get_modulation_unit(&self) -> Option<Arc<Mutex<ModulationUnit>>> {
         self.modulation_unit.as_ref().map(Arc::clone)
@@ -143,23 +220,43 @@ impl AudioHandler {
             let mut unit = unit.lock().unwrap();
             unit.append_effect_from_name(effect_name)?;
         }
+        self.apply_vocoder_settings();
         self.restart()?;
 
         Ok(())
     }
 
-    pub fn set_effect_parameter(
+    // Applied live without an engine restart - the effect ramps to the new value itself.
+    // Called at controller rate too (MIDI, OSC, WebSocket)
+    pub fn set_effect_parameter(&mut self, effect_name: &str, parameter: ParameterValue) -> anyhow::Result<()> {
+        self.modulation_unit
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No modulation unit available"))?
+            .lock()
+            .unwrap()
+            .set_effect_parameter(effect_name, parameter)
+    }
+
+    // Controller value in 0..1, mapped onto the parameter's range
+    pub fn set_effect_parameter_normalized(
         &mut self,
         effect_name: &str,
-        parameter: ParameterValue,
+        parameter_name: &str,
+        normalized: f32,
     ) -> anyhow::Result<()> {
-        if let Some(ref unit) = self.modulation_unit {
-            let mut unit = unit.lock().unwrap();
-            unit.set_effect_parameter(effect_name, parameter)?;
-        }
-        self.restart()?;
-
-        Ok(())
+        let mut parameter = self
+            .get_effect_parameters(effect_name)?
+            .into_iter()
+            .find(|p| p.name == parameter_name)
+            .ok_or_else(|| anyhow::anyhow!("Effect '{}' has no parameter '{}'", effect_name, parameter_name))?;
+        parameter.set_normalized(normalized);
+        self.set_effect_parameter(
+            effect_name,
+            ParameterValue {
+                name: parameter.name,
+                value: parameter.value,
+            },
+        )
     }
 
     pub fn remove_effect_from_modulation(&mut self, effect_name: &str) -> anyhow::Result<()> {
@@ -194,6 +291,73 @@ impl AudioHandler {
         }
     }
 
+    // Kept here like the carrier, so device changes and preset loads do not reset it
+    pub fn set_vocoder_scale(&mut self, scale: Scale) -> anyhow::Result<()> {
+        self.modulation_unit
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No modulation unit available"))?
+            .lock()
+            .unwrap()
+            .set_vocoder_scale(scale)?;
+        self.vocoder_scale = scale;
+        Ok(())
+    }
+
+    pub fn get_vocoder_scale(&self) -> Option<crate::dsp::modules::effects::auto_tune::Scale> {
+        if let Some(ref unit) = self.modulation_unit {
+            let unit = unit.lock().unwrap();
+            unit.get_vocoder_scale()
+        } else {
+            None
+        }
+    }
+
+    // The carrier is opened before locking the unit - loading a file must not stall the audio callback.
+    // Kept here so it survives modulation unit re-creation and chain rebuilds
+    pub fn set_vocoder_carrier(&mut self, source: CarrierSource) -> anyhow::Result<()> {
+        let unit = self
+            .modulation_unit
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No modulation unit available"))?;
+        let sample_rate = unit.lock().unwrap().get_sample_rate();
+        let carrier = create_carrier(&source, sample_rate)?;
+        unit.lock().unwrap().set_vocoder_carrier(carrier)?;
+        self.vocoder_carrier = source;
+        Ok(())
+    }
+
+    // Gives a rebuilt or re-added vocoder the carrier and scale it had - the carrier is only reopened when it differs
+    fn apply_vocoder_settings(&mut self) {
+        let Some(ref unit) = self.modulation_unit else {
+            return;
+        };
+        let (sample_rate, current) = {
+            let mut unit = unit.lock().unwrap();
+            // Fails only when there is no vocoder in the chain
+            let _ = unit.set_vocoder_scale(self.vocoder_scale);
+            (unit.get_sample_rate(), unit.get_vocoder_carrier())
+        };
+        // `None` = no vocoder in the chain
+        if current.is_none() || current.as_ref() == Some(&self.vocoder_carrier) {
+            return;
+        }
+
+        let result = create_carrier(&self.vocoder_carrier, sample_rate)
+            .and_then(|carrier| unit.lock().unwrap().set_vocoder_carrier(carrier));
+        if let Err(e) = result {
+            eprintln!("Vocoder carrier could not be restored: {}", e);
+        }
+    }
+
+    pub fn get_vocoder_carrier(&self) -> Option<CarrierSource> {
+        if let Some(ref unit) = self.modulation_unit {
+            let unit = unit.lock().unwrap();
+            unit.get_vocoder_carrier()
+        } else {
+            None
+        }
+    }
+
     pub fn get_effect_parameters(
         &self,
         effect_name: &str,
@@ -215,7 +379,201 @@ impl AudioHandler {
         }
     }
 
+    // Tuner reference is kept here so it survives modulation unit re-creation
+    pub fn set_tuner_reference(&mut self, reference: f32) -> anyhow::Result<()> {
+        if !reference.is_finite() {
+            return Err(anyhow::anyhow!("Invalid tuner reference: {}", reference));
+        }
+        // Clamped the same way the tuner does, so the getter reports what is in use
+        // even without a modulation unit
+        self.tuner_reference = reference.clamp(MIN_A4_REFERENCE, MAX_A4_REFERENCE);
+        self.apply_tuner_reference();
+        Ok(())
+    }
+
+    pub fn get_tuner_reference(&self) -> f32 {
+        if let Some(ref unit) = self.modulation_unit {
+            let unit = unit.lock().unwrap();
+            unit.get_tuner_reference()
+        } else {
+            self.tuner_reference
+        }
+    }
+
+    fn apply_tuner_reference(&mut self) {
+        if let Some(ref unit) = self.modulation_unit {
+            let mut unit = unit.lock().unwrap();
+            unit.set_tuner_reference(self.tuner_reference);
+        }
+    }
+
+    // Visualizer settings survive modulation unit re-creation the same way
+    pub fn set_visualizer_settings(&mut self, settings: VisualizerSettings) -> anyhow::Result<()> {
+        if let Some(ref unit) = self.modulation_unit {
+            let mut unit = unit.lock().unwrap();
+            unit.set_visualizer_settings(settings.clone())?;
+        }
+        self.visualizer_settings = settings;
+        Ok(())
+    }
+
+    pub fn get_visualizer_settings(&self) -> VisualizerSettings {
+        self.visualizer_settings.clone()
+    }
+
+    pub fn get_spectrogram(&self) -> anyhow::Result<SpectrogramSnapshot> {
+        if let Some(ref unit) = self.modulation_unit {
+            let unit = unit.lock().unwrap();
+            Ok(unit.get_spectrogram())
+        } else {
+            Err(anyhow::anyhow!("No modulation unit available"))
+        }
+    }
+
+    pub fn reset_meters(&self) -> anyhow::Result<()> {
+        if let Some(ref unit) = self.modulation_unit {
+            let unit = unit.lock().unwrap();
+            unit.reset_meters();
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("No modulation unit available"))
+        }
+    }
+
+    fn apply_visualizer_settings(&mut self) {
+        if let Some(ref unit) = self.modulation_unit {
+            let mut unit = unit.lock().unwrap();
+            // The new device may have a lower Nyquist frequency - fall back to defaults then
+            if unit.set_visualizer_settings(self.visualizer_settings.clone()).is_err() {
+                self.visualizer_settings = unit.get_visualizer_settings();
+            }
+        }
+    }
+
+    // Modulation matrix - applied live, LFOs keep their phase across changes
+    pub fn set_modulation_matrix(&mut self, settings: ModulationMatrixSettings) -> anyhow::Result<()> {
+        if let Some(ref unit) = self.modulation_unit {
+            let mut unit = unit.lock().unwrap();
+            unit.set_modulation_matrix(settings.clone())?;
+        } else {
+            settings.validate()?;
+        }
+        self.modulation_matrix = settings;
+        Ok(())
+    }
+
+    pub fn get_modulation_matrix(&self) -> ModulationMatrixSettings {
+        self.modulation_matrix.clone()
+    }
+
+    pub fn add_modulation_route(&mut self, route: ModulationRoute) -> anyhow::Result<()> {
+        let mut settings = self.modulation_matrix.clone();
+        settings.routes.push(route);
+        self.set_modulation_matrix(settings)
+    }
+
+    pub fn remove_modulation_route(&mut self, index: usize) -> anyhow::Result<()> {
+        let mut settings = self.modulation_matrix.clone();
+        if index >= settings.routes.len() {
+            return Err(anyhow::anyhow!("Modulation route {} does not exist", index));
+        }
+        settings.routes.remove(index);
+        self.set_modulation_matrix(settings)
+    }
+
+    fn apply_modulation_matrix(&mut self) {
+        if let Some(ref unit) = self.modulation_unit {
+            let mut unit = unit.lock().unwrap();
+            // Already validated when it was set
+            let _ = unit.set_modulation_matrix(self.modulation_matrix.clone());
+        }
+    }
+
+    // Presets
+    pub fn capture_preset(&self, name: &str) -> anyhow::Result<Preset> {
+        let unit = self
+            .modulation_unit
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No modulation unit available"))?
+            .lock()
+            .unwrap();
+
+        let mut effects = Vec::new();
+        for effect_name in unit.get_active_effects() {
+            let parameters = unit
+                .get_effect_parameters(&effect_name)?
+                .into_iter()
+                .map(|parameter| ParameterValue {
+                    name: parameter.name,
+                    value: parameter.value,
+                })
+                .collect();
+            effects.push(PresetEffect {
+                name: effect_name,
+                parameters,
+            });
+        }
+
+        Ok(Preset {
+            name: name.to_string(),
+            effects,
+            auto_tune_scale: unit.get_auto_tune_scale(),
+        })
+    }
+
+    // Replaces the whole chain with the preset and restarts the engine once
+    pub fn apply_preset(&mut self, preset: &Preset) -> anyhow::Result<()> {
+        if let Some(ref unit) = self.modulation_unit {
+            let mut unit = unit.lock().unwrap();
+            // Everything is built first - a bad preset leaves the current chain playing
+            let mut effects = Vec::with_capacity(preset.effects.len());
+            for preset_effect in &preset.effects {
+                let mut effect = unit.create_effect(&preset_effect.name)?;
+                for parameter in &preset_effect.parameters {
+                    effect.set_parameter(parameter.clone())?;
+                }
+                effects.push(effect);
+            }
+            if let Some(scale) = preset.auto_tune_scale {
+                effects
+                    .iter_mut()
+                    .find(|effect| effect.name() == "autotune")
+                    .ok_or_else(|| anyhow::anyhow!("AutoTune effect not found in chain"))?
+                    .set_scale(scale)?;
+            }
+            unit.replace_effects(effects);
+        }
+        self.apply_vocoder_settings();
+        self.restart()?;
+
+        Ok(())
+    }
+
+    pub fn is_modulation_active(&self) -> bool {
+        if let Some(ref unit) = self.modulation_unit {
+            let unit = unit.lock().unwrap();
+            unit.is_active()
+        } else {
+            false
+        }
+    }
+
+    // Output gate - silences the throughput output without stopping the engine
+    pub fn set_output_gate(&self, open: bool) {
+        self.output_gate.store(open, Ordering::Relaxed);
+    }
+
+    pub fn is_output_gate_open(&self) -> bool {
+        self.output_gate.load(Ordering::Relaxed)
+    }
+
     pub fn start_recording(&mut self) -> anyhow::Result<()> {
+        // Catch a bad directory here - the engine thread can only report it as an event
+        self.recording_settings.validate()?;
+        let dir = self.recording_settings.directory();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| anyhow::anyhow!("Cannot use recording directory '{}': {}", dir.display(), e))?;
+
         self.recorder_active = true;
         self.restart()?;
         Ok(())
@@ -228,7 +586,7 @@ impl AudioHandler {
     }
 
     pub fn set_file_save_path(&mut self, path: Option<String>) -> anyhow::Result<()> {
-        self.file_save_path = path;
+        self.recording_settings.directory = path;
         Ok(())    
     }
 
@@ -237,62 +595,96 @@ impl AudioHandler {
     }
 
     pub fn get_file_save_path(&self) -> Option<String> {
-        self.file_save_path.clone()
+        self.recording_settings.directory.clone()
+    }
+
+    // Takes effect with the next recording
+    pub fn set_recording_settings(&mut self, settings: RecordingSettings) -> anyhow::Result<()> {
+        settings.validate()?;
+        self.recording_settings = settings;
+        Ok(())
+    }
+
+    pub fn get_recording_settings(&self) -> RecordingSettings {
+        self.recording_settings.clone()
+    }
+
+    // Instant replay is fed by the throughput engine - what the stream actually hears
+    pub fn set_replay_duration(&mut self, seconds: f32) -> anyhow::Result<()> {
+        if seconds != 0.0 && !(MIN_REPLAY_SECONDS..=MAX_REPLAY_SECONDS).contains(&seconds) {
+            return Err(anyhow::anyhow!(
+                "Replay duration must be 0 (off) or between {} and {} seconds",
+                MIN_REPLAY_SECONDS,
+                MAX_REPLAY_SECONDS
+            ));
+        }
+        self.replay_seconds = seconds;
+        match self.replay_buffer {
+            // Resized under its lock - the running engine keeps feeding it, no history is lost
+            Some(ref buffer) if seconds != 0.0 => buffer
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to acquire replay buffer lock: {}", e))?
+                .resize(seconds),
+            // Turning replay on or off attaches or detaches the engine's feed
+            _ => {
+                self.replay_buffer = None;
+                self.restart()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_replay_duration(&self) -> f32 {
+        self.replay_seconds
+    }
+
+    pub fn save_replay(&self) -> anyhow::Result<String> {
+        let buffer = self
+            .replay_buffer
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Instant replay is not running"))?;
+        let path = save_replay(buffer, &self.recording_settings)?;
+        Ok(path.to_string_lossy().into_owned())
     }
 
-    // Start and stop audio engine for loopback mode
+    // Reuses the buffer unless the input format changed
+    fn replay_buffer_for(&mut self, sample_rate: u32, channels: u16) -> Option<Arc<Mutex<ReplayBuffer>>> {
+        if self.replay_seconds == 0.0 {
+            return None;
+        }
+        let reusable = match self.replay_buffer {
+            Some(ref buffer) => buffer
+                .lock()
+                .map(|buffer| buffer.is_format(sample_rate, channels))
+                .unwrap_or(false),
+            None => false,
+        };
+        if !reusable {
+            self.replay_buffer = Some(Arc::new(Mutex::new(ReplayBuffer::new(
+                self.replay_seconds,
+                sample_rate,
+                channels,
+            ))));
+        }
+        self.replay_buffer.as_ref().map(Arc::clone)
+    }
+
+    // Loopback and throughput are outputs of one engine - enabling either restarts it with both
     pub fn start_audio_engine_loopback(&mut self) -> anyhow::Result<()> {
         if self.loopback_running {
             return Err(anyhow::anyhow!("Loopback audio engine is already running"));
         }
 
         // Verify we have required devices
-        let input_device = self
-            .audio_devices
+        self.audio_devices
             .get_input_device()
             .ok_or_else(|| anyhow::anyhow!("No input device available"))?;
-        let output_device = self
-            .audio_devices
+        self.audio_devices
             .get_output_device()
             .ok_or_else(|| anyhow::anyhow!("No output device available"))?;
 
-        // Clone devices and options for the thread
-        let input_device_clone = AudioDevice::new(
-            input_device.get_device().clone(),
-            input_device.get_config().clone(),
-        );
-        let output_device_clone = AudioDevice::new(
-            output_device.get_device().clone(),
-            output_device.get_config().clone(),
-        );
-        let options_clone = self.options.clone();
-
-        // Create control flag
-        let control = Arc::new(Mutex::new(true));
-        self.loopback_control = Some(Arc::clone(&control));
-
-        // Clone modulation unit if exists
-        let modulation_unit_clone = self.modulation_unit.as_ref().map(Arc::clone);
-        let recorder_active = self.recorder_active;
-        let file_save_path = self.file_save_path.clone();
-
-        // Spawn audio processing thread
-        let handle = thread::spawn(move || {
-            Self::audio_engine_thread(
-                input_device_clone,
-                output_device_clone,
-                options_clone,
-                control,
-                modulation_unit_clone,
-                recorder_active,
-                file_save_path
-            );
-        });
-
-        self.loopback_handle = Some(handle);
         self.loopback_running = true;
-
-        Ok(())
+        self.restart()
     }
 
     pub fn stop_audio_engine_loopback(&mut self) -> anyhow::Result<()> {
@@ -300,24 +692,8 @@ impl AudioHandler {
             return Err(anyhow::anyhow!("Loopback audio engine is not running"));
         }
 
-        // Signal the thread to stop
-        if let Some(ref control) = self.loopback_control {
-            if let Ok(mut should_run) = control.lock() {
-                *should_run = false;
-            }
-        }
-
-        // Wait for thread to finish
-        if let Some(handle) = self.loopback_handle.take() {
-            handle
-                .join()
-                .map_err(|_| anyhow::anyhow!("Failed to join audio thread"))?;
-        }
-
-        self.loopback_control = None;
         self.loopback_running = false;
-
-        Ok(())
+        self.restart()
     }
 
     pub fn start_audio_engine_throughput(&mut self) -> anyhow::Result<()> {
@@ -328,131 +704,235 @@ impl AudioHandler {
         }
 
         // Verify we have required devices
-        let input_device = self
-            .audio_devices
+        self.audio_devices
             .get_input_device()
             .ok_or_else(|| anyhow::anyhow!("No input device available"))?;
-        let output_device = self
-            .audio_devices
+        self.audio_devices
             .get_virtual_input()
             .ok_or_else(|| anyhow::anyhow!("No output device available"))?;
 
-        // Clone devices and options for the thread
-        let input_device_clone = AudioDevice::new(
-            input_device.get_device().clone(),
-            input_device.get_config().clone(),
-        );
-        let output_device_clone = AudioDevice::new(
-            output_device.get_device().clone(),
-            output_device.get_config().clone(),
-        );
+        self.throughput_running = true;
+        self.restart()
+    }
+
+    pub fn stop_audio_engine_throughput(&mut self) -> anyhow::Result<()> {
+        if !self.throughput_running {
+            return Err(anyhow::anyhow!("Throughput audio engine is not running"));
+        }
+
+        self.throughput_running = false;
+        self.restart()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.loopback_running || self.throughput_running
+    }
+
+    pub fn is_loopback_running(&self) -> bool {
+        self.loopback_running
+    }
+
+    pub fn is_throughput_running(&self) -> bool {
+        self.throughput_running
+    }
+
+    pub fn get_status(&self) -> String {
+        match (self.loopback_running, self.throughput_running) {
+            (true, true) => "both_running".to_string(),
+            (true, false) => "loopback_running".to_string(),
+            (false, true) => "throughput_running".to_string(),
+            (false, false) => "stopped".to_string(),
+        }
+    }
+
+    // Output levels - applied live by the output callbacks
+    pub fn set_output_gain(&self, output: OutputKind, gain: f32) -> anyhow::Result<()> {
+        if !(0.0..=MAX_OUTPUT_GAIN).contains(&gain) {
+            return Err(anyhow::anyhow!("Output gain must be between 0 and {}", MAX_OUTPUT_GAIN));
+        }
+        self.output_level(output).set_gain(gain);
+        Ok(())
+    }
+
+    pub fn set_output_muted(&self, output: OutputKind, muted: bool) {
+        self.output_level(output).set_muted(muted);
+    }
+
+    pub fn get_output_levels(&self) -> Vec<OutputLevelInfo> {
+        [OutputKind::Monitor, OutputKind::VirtualCable]
+            .into_iter()
+            .map(|output| {
+                let level = self.output_level(output);
+                OutputLevelInfo {
+                    output,
+                    gain: level.get_gain(),
+                    muted: level.is_muted(),
+                }
+            })
+            .collect()
+    }
+
+    fn output_level(&self, output: OutputKind) -> &Arc<OutputLevel> {
+        match output {
+            OutputKind::Monitor => &self.monitor_level,
+            OutputKind::VirtualCable => &self.virtual_cable_level,
+        }
+    }
+
+    // Stops the engine and starts it again with every enabled output
+    fn restart(&mut self) -> anyhow::Result<()> {
+        self.stop_engine()?;
+        if !self.is_running() {
+            return Ok(());
+        }
+
+        let input_device = self
+            .audio_devices
+            .get_input_device()
+            .ok_or_else(|| anyhow::anyhow!("No input device available"))?;
+        let input_device_clone = clone_device(input_device);
+
+        let mut outputs = Vec::new();
+        if self.loopback_running {
+            let output_device = self
+                .audio_devices
+                .get_output_device()
+                .ok_or_else(|| anyhow::anyhow!("No output device available"))?;
+            outputs.push(OutputTarget {
+                kind: OutputKind::Monitor,
+                device: clone_device(output_device),
+                level: Arc::clone(&self.monitor_level),
+                gate: None,
+            });
+        }
+        if self.throughput_running {
+            let output_device = self
+                .audio_devices
+                .get_virtual_input()
+                .ok_or_else(|| anyhow::anyhow!("No output device available"))?;
+            outputs.push(OutputTarget {
+                kind: OutputKind::VirtualCable,
+                device: clone_device(output_device),
+                level: Arc::clone(&self.virtual_cable_level),
+                gate: Some(Arc::clone(&self.output_gate)), // Only the virtual cable is gated
+            });
+        }
         let options_clone = self.options.clone();
+        let channel_map = self.channel_map.clone();
 
         // Create control flag
         let control = Arc::new(Mutex::new(true));
-        self.throughput_control = Some(Arc::clone(&control));
+        self.engine_control = Some(Arc::clone(&control));
 
         // Clone modulation unit if exists
         let modulation_unit_clone = self.modulation_unit.as_ref().map(Arc::clone);
-        let recorder_active = self.recorder_active;
-        let file_save_path = self.file_save_path.clone();
+        let recording_settings = if self.recorder_active {
+            Some(self.recording_settings.clone())
+        } else {
+            None
+        };
+
+        // Replay, soundboard and music bed belong to the stream, so they run with the virtual cable,
+        // in the format of the processed signal
+        let (replay_buffer, soundboard, music_bed) = if self.throughput_running {
+            let sample_rate = input_device_clone.get_config().sample_rate.0;
+            let channels = self.channel_map.layout.channels() as u16;
+            (
+                self.replay_buffer_for(sample_rate, channels),
+                SoundboardManager::get_instance()
+                    .lock()
+                    .ok()
+                    .map(|mut manager| manager.attach(sample_rate, channels)),
+                MusicBedManager::get_instance()
+                    .lock()
+                    .ok()
+                    .map(|mut manager| manager.attach(sample_rate, channels)),
+            )
+        } else {
+            (None, None, None)
+        };
 
         // Spawn audio processing thread
         let handle = thread::spawn(move || {
             Self::audio_engine_thread(
                 input_device_clone,
-                output_device_clone,
+                outputs,
+                channel_map,
                 options_clone,
                 control,
                 modulation_unit_clone,
-                recorder_active,
-                file_save_path, // Pass file save path for throughput mode
+                recording_settings,
+                replay_buffer,
+                soundboard,
+                music_bed,
             );
         });
-
-        self.throughput_handle = Some(handle);
-        self.throughput_running = true;
+        self.engine_handle = Some(handle);
 
         Ok(())
     }
 
-    pub fn stop_audio_engine_throughput(&mut self) -> anyhow::Result<()> {
-        if !self.throughput_running {
-            return Err(anyhow::anyhow!("Throughput audio engine is not running"));
-        }
-
+    fn stop_engine(&mut self) -> anyhow::Result<()> {
         // Signal the thread to stop
-        if let Some(ref control) = self.throughput_control {
-            if let Ok(mut should_run) = control.lock() {
-                *should_run = false;
-            }
+        if let Some(Ok(mut should_run)) = self.engine_control.take().as_ref().map(|control| control.lock()) {
+            *should_run = false;
         }
 
         // Wait for thread to finish
-        if let Some(handle) = self.throughput_handle.take() {
+        if let Some(handle) = self.engine_handle.take() {
             handle
                 .join()
                 .map_err(|_| anyhow::anyhow!("Failed to join audio thread"))?;
         }
 
-        self.throughput_control = None;
-        self.throughput_running = false;
-
         Ok(())
     }
 
-    pub fn is_running(&self) -> bool {
-        self.loopback_running || self.throughput_running
-    }
-
-    pub fn is_loopback_running(&self) -> bool {
-        self.loopback_running
-    }
-
-    pub fn is_throughput_running(&self) -> bool {
-        self.throughput_running
-    }
-
-    pub fn get_status(&self) -> String {
-        match (self.loopback_running, self.throughput_running) {
-            (true, true) => "both_running".to_string(),
-            (true, false) => "loopback_running".to_string(),
-            (false, true) => "throughput_running".to_string(),
-            (false, false) => "stopped".to_string(),
-        }
-    }
-
+    #[allow(clippy::too_many_arguments)]
     fn audio_engine_thread(
         input_device: AudioDevice,
-        output_device: AudioDevice,
+        outputs: Vec<OutputTarget>,
+        channel_map: ChannelMapSettings,
         options: AudioDeviceOptions,
         control: Arc<Mutex<bool>>,
         modulation_unit: Option<Arc<Mutex<ModulationUnit>>>,
-        recorder_active: bool,
-        file_save_path: Option<String>,
+        recording_settings: Option<RecordingSettings>,
+        replay_buffer: Option<Arc<Mutex<ReplayBuffer>>>,
+        soundboard: Option<Arc<Mutex<SoundboardMixer>>>,
+        music_bed: Option<Arc<Mutex<MusicBedMixer>>>,
     ) {
+        // Errors from this thread never reach a command, so they go to the UI as events
+        let app_handle = modulation_unit
+            .as_ref()
+            .and_then(|unit| unit.lock().ok().and_then(|unit| unit.get_app_handle()));
+
         // Create the audio engine
-        let audio_engine = match AudioEngine::new(
+        let mut audio_engine = match AudioEngine::new(
             &input_device,
-            &output_device,
+            &outputs,
+            &channel_map,
             &options,
             modulation_unit,
-            recorder_active,
+            recording_settings,
+            replay_buffer,
+            soundboard,
+            music_bed,
         ) {
             Ok(engine) => engine,
             Err(e) => {
-                eprintln!("Failed to create audio engine: {}", e);
+                Self::report_error(&app_handle, format!("Failed to create audio engine: {}", e));
                 return;
             }
         };
 
         // Start the engine
         if let Err(e) = audio_engine.start() {
-            eprintln!("Failed to start audio engine: {}", e);
+            Self::report_error(&app_handle, format!("Failed to start audio engine: {}", e));
             return;
         }
 
         // Keep the engine running until stop signal
+        let mut last_drift_report = Instant::now();
         loop {
             // Check if we should continue running
             let should_continue = match control.lock() {
@@ -464,20 +944,37 @@ impl AudioHandler {
                 break;
             }
 
+            if last_drift_report.elapsed() >= DRIFT_REPORT_INTERVAL {
+                last_drift_report = Instant::now();
+                if let Some(ref app_handle) = app_handle {
+                    let _ = app_handle.emit("audio-drift", &audio_engine.get_output_drift());
+                }
+            }
+
             // Sleep to prevent busy waiting
             thread::sleep(Duration::from_millis(10));
         }
 
         // Stop the engine
-        if let Err(e) = audio_engine.stop(file_save_path) {
-            eprintln!("Failed to stop audio engine: {}", e);
+        if let Err(e) = audio_engine.stop() {
+            Self::report_error(&app_handle, format!("Failed to stop audio engine: {}", e));
+        }
+    }
+
+    fn report_error(app_handle: &Option<tauri::AppHandle>, message: String) {
+        eprintln!("{}", message);
+        if let Some(app_handle) = app_handle {
+            let _ = app_handle.emit("audio-error", &message);
         }
     }
 }
 
 impl Drop for AudioHandler {
     fn drop(&mut self) {
-        let _ = self.stop_audio_engine_loopback();
-        let _ = self.stop_audio_engine_throughput();
+        let _ = self.stop_engine();
     }
 }
+
+fn clone_device(device: &AudioDevice) -> AudioDevice {
+    AudioDevice::new(device.get_device().clone(), device.get_config().clone())
+}
@@ -4,7 +4,12 @@
 
 use ringbuf::consumer::Consumer;
 use ringbuf::producer::Producer;
-use ringbuf::{HeapRb, traits::Split};
+use ringbuf::{HeapRb, traits::{Observer, Split}};
+
+use crate::dsp::modules::utils::mix_frame;
+
+// Input frames up to this many channels convert without growing the scratch frame
+const MAX_FRAME_CHANNELS: usize = 32;
 
 // AudioBuffer struct to hold the audio data buffer
 pub struct AudioBuffer {
@@ -13,23 +18,31 @@ pub struct AudioBuffer {
 
     // Consumer (used to read audio data)
     consumer: <HeapRb<f32> as Split>::Cons,
+
+    // One input frame while converting channels - kept so the output callback never allocates
+    frame: Vec<f32>,
 }
 
 impl AudioBuffer {
-    // Constructor Args: buffer_size - size of the buffer in samples
+    // Constructor Args: latency_samples - fill the ring is started at and held at
 
-    // Buffer size could be * 2 to secure that there is enough space for audio data with the cost of more latency
-    pub fn new(buffer_size: usize) -> Self {
-        let heap_rb = HeapRb::<f32>::new(buffer_size);
+    // The ring holds twice the latency, so a block written on top of the target fill
+    // (and any drift the controller has not caught yet) fits without dropping samples
+    pub fn new(latency_samples: usize) -> Self {
+        let heap_rb = HeapRb::<f32>::new((latency_samples * 2).max(1));
 
         let (mut producer, consumer) = heap_rb.split();
 
         // Initialize the producer with zeroes
-        for _ in 0..buffer_size {
+        for _ in 0..latency_samples {
             producer.try_push(0.0).unwrap();
         }
 
-        AudioBuffer { producer, consumer }
+        AudioBuffer {
+            producer,
+            consumer,
+            frame: Vec::with_capacity(MAX_FRAME_CHANNELS),
+        }
     }
 
     // Write audio data to the buffer (tries to write all data from slice) (could add output fell behind check)
@@ -47,23 +60,9 @@ impl AudioBuffer {
         Ok(())
     }
 
-    pub fn buffer_write_and_record(
-        &mut self,
-        data: &[f32],
-        recording_buffer: &mut Vec<f32>,
-    ) -> anyhow::Result<()> {
-        let mut output_fell_behind = false;
-        for &sample in data {
-            if self.producer.try_push(sample).is_err() {
-                output_fell_behind = true;
-                break;
-            }
-            recording_buffer.push(sample);
-        }
-        if output_fell_behind {
-            eprintln!("Output buffer fell behind, some samples were dropped.");
-        }
-        Ok(())
+    // Samples waiting to be read - the latency buffered right now
+    pub fn occupied_len(&self) -> usize {
+        self.consumer.occupied_len()
     }
 
     // Read audio data from the buffer (tries to read all data into slice)
@@ -76,7 +75,7 @@ impl AudioBuffer {
         let mut input_fell_behind = false;
 
         // Use external channel conversion function
-        convert_audio_channels(input_channels, output_channels, data, &mut || match self
+        convert_audio_channels(input_channels, output_channels, data, &mut self.frame, &mut || match self
             .consumer
             .try_pop()
         {
@@ -99,47 +98,33 @@ fn convert_audio_channels<F>(
     input_channels: usize,
     output_channels: usize,
     data: &mut [f32],
+    frame: &mut Vec<f32>,
     sample_provider: &mut F,
 ) -> anyhow::Result<()>
 where
     F: FnMut() -> f32,
 {
-    match (input_channels, output_channels) {
-        (1, 1) => {
-            // Mono to mono - direct copy
-            for sample in data {
-                *sample = sample_provider();
-            }
-        }
-        (1, 2) => {
-            // Mono to stereo - duplicate mono signal to both channels
-            for chunk in data.chunks_exact_mut(2) {
-                let mono_sample = sample_provider();
-                chunk[0] = mono_sample; // Left channel
-                chunk[1] = mono_sample; // Right channel
-            }
-        }
-        (2, 1) => {
-            // Stereo to mono - mix both channels
-            for sample in data {
-                let left = sample_provider();
-                let right = sample_provider();
-                *sample = (left + right) / 2.0; // Mix to mono
-            }
+    if input_channels == 0 || output_channels == 0 {
+        return Err(anyhow::anyhow!(
+            "Unsupported channel configuration: {} input channels, {} output channels",
+            input_channels,
+            output_channels
+        ));
+    }
+
+    if input_channels == output_channels {
+        // Same layout - direct copy
+        for sample in data {
+            *sample = sample_provider();
         }
-        (2, 2) => {
-            // Stereo to stereo - direct copy
-            for sample in data {
+    } else {
+        // Any other pair is up or downmixed frame by frame
+        frame.resize(input_channels, 0.0);
+        for chunk in data.chunks_exact_mut(output_channels) {
+            for sample in frame.iter_mut() {
                 *sample = sample_provider();
             }
-        }
-        _ => {
-            // Unsupported channel configuration
-            return Err(anyhow::anyhow!(
-                "Unsupported channel configuration: {} input channels, {} output channels",
-                input_channels,
-                output_channels
-            ));
+            mix_frame(frame, chunk);
         }
     }
 
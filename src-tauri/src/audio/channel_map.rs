@@ -0,0 +1,140 @@
+// Channel mapping - which input channels form the voice and how many channels the effects run on
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingLayout {
+    Mono,   // the voice channels are averaged into one
+    Stereo, // left and right run through the effects separately
+}
+
+impl ProcessingLayout {
+    pub fn channels(&self) -> usize {
+        match self {
+            ProcessingLayout::Mono => 1,
+            ProcessingLayout::Stereo => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelMapSettings {
+    // Zero-based input channels. Empty = the first channel for mono, the first two for stereo
+    pub voice_channels: Vec<usize>,
+    pub layout: ProcessingLayout,
+}
+
+impl Default for ChannelMapSettings {
+    fn default() -> Self {
+        Self {
+            voice_channels: Vec::new(),
+            layout: ProcessingLayout::Mono,
+        }
+    }
+}
+
+impl ChannelMapSettings {
+    pub fn validate(&self, input_channels: usize) -> anyhow::Result<()> {
+        if let Some(&channel) = self.voice_channels.iter().find(|&&channel| channel >= input_channels) {
+            return Err(anyhow::anyhow!(
+                "Input channel {} does not exist, the device has {} channels",
+                channel + 1,
+                input_channels
+            ));
+        }
+        if self.layout == ProcessingLayout::Stereo && self.voice_channels.len() > 2 {
+            return Err(anyhow::anyhow!("Stereo processing takes one or two input channels"));
+        }
+        Ok(())
+    }
+}
+
+// Builds the processing signal from interleaved input frames
+pub struct ChannelMapper {
+    sources: Vec<Vec<usize>>, // input channels averaged into each processing channel
+    input_channels: usize,
+}
+
+impl ChannelMapper {
+    pub fn new(settings: &ChannelMapSettings, input_channels: usize) -> anyhow::Result<Self> {
+        let input_channels = input_channels.max(1);
+        settings.validate(input_channels)?;
+
+        // Averaging every input would mix a silent or unrelated channel into the voice
+        let selected = if settings.voice_channels.is_empty() {
+            (0..input_channels.min(settings.layout.channels())).collect()
+        } else {
+            settings.voice_channels.clone()
+        };
+        let sources = match settings.layout {
+            ProcessingLayout::Mono => vec![selected],
+            // A single channel is centred, otherwise left and right
+            ProcessingLayout::Stereo => {
+                let left = selected[0];
+                let right = *selected.get(1).unwrap_or(&left);
+                vec![vec![left], vec![right]]
+            }
+        };
+
+        Ok(Self {
+            sources,
+            input_channels,
+        })
+    }
+
+    pub fn channels(&self) -> usize {
+        self.sources.len()
+    }
+
+    pub fn map(&self, input: &[f32]) -> Vec<f32> {
+        let mut output = Vec::with_capacity(input.len() / self.input_channels * self.sources.len());
+        for frame in input.chunks_exact(self.input_channels) {
+            for source in &self.sources {
+                let sum: f32 = source.iter().map(|&channel| frame[channel]).sum();
+                output.push(sum / source.len() as f32);
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voice_channels_are_picked_from_the_interface() {
+        // Two frames from a four-channel interface
+        let input = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
+
+        let settings = ChannelMapSettings {
+            voice_channels: vec![2],
+            layout: ProcessingLayout::Mono,
+        };
+        assert_eq!(ChannelMapper::new(&settings, 4).unwrap().map(&input), vec![0.3, 0.7]);
+
+        let settings = ChannelMapSettings {
+            voice_channels: vec![3, 1],
+            layout: ProcessingLayout::Stereo,
+        };
+        let mapper = ChannelMapper::new(&settings, 4).unwrap();
+        assert_eq!(mapper.channels(), 2);
+        assert_eq!(mapper.map(&input), vec![0.4, 0.2, 0.8, 0.6]);
+
+        // A mono mic in stereo processing lands in the centre
+        let mapper = ChannelMapper::new(&ChannelMapSettings { layout: ProcessingLayout::Stereo, ..Default::default() }, 1).unwrap();
+        assert_eq!(mapper.map(&[0.5, 0.25]), vec![0.5, 0.5, 0.25, 0.25]);
+
+        // Nothing picked on a multichannel interface - the voice is the first input, not the average
+        let mapper = ChannelMapper::new(&ChannelMapSettings::default(), 4).unwrap();
+        assert_eq!(mapper.map(&input), vec![0.1, 0.5]);
+
+        let missing = ChannelMapSettings {
+            voice_channels: vec![4],
+            layout: ProcessingLayout::Mono,
+        };
+        assert!(ChannelMapper::new(&missing, 4).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+// Clock drift compensation - two devices never run at exactly the same rate, so each output
+// ring would slowly fill up or run dry. A PI controller nudges the resampler ratio to hold
+// the ring at its target fill, and its integral term is the measured drift.
+
+// Largest ratio correction, +-2000 ppm is about 3.5 cents - far beyond real clock drift
+const MAX_ADJUST: f64 = 0.002;
+// Ratio correction per second of fill error
+const PROPORTIONAL_GAIN: f64 = 0.1;
+// Critically damped with the proportional gain - settles in about a minute without overshoot
+const INTEGRAL_GAIN: f64 = PROPORTIONAL_GAIN * PROPORTIONAL_GAIN / 4.0;
+// The fill jumps by a whole block on every callback, so it is averaged first
+const FILL_SMOOTHING_SECONDS: f64 = 0.5;
+
+pub struct DriftController {
+    target: f64,             // ring fill to hold, in samples
+    samples_per_second: f64, // rate the ring is drained at
+    fill: f64,               // smoothed fill, in samples
+    integral: f64,           // fill error integrated over time
+}
+
+impl DriftController {
+    pub fn new(target_samples: usize, sample_rate: u32, channels: usize) -> Self {
+        Self {
+            target: target_samples as f64,
+            samples_per_second: (sample_rate as f64 * channels.max(1) as f64).max(1.0),
+            fill: target_samples as f64,
+            integral: 0.0,
+        }
+    }
+
+    // Called once per block with the ring fill before the block is written.
+    // Returns the relative ratio correction - positive consumes input faster.
+    pub fn update(&mut self, fill_samples: usize, elapsed_seconds: f64) -> f64 {
+        let smoothing = 1.0 - (-elapsed_seconds / FILL_SMOOTHING_SECONDS).exp();
+        self.fill += (fill_samples as f64 - self.fill) * smoothing;
+
+        // Fill error as latency, so the gains do not depend on the rate or channel count
+        let error = (self.fill - self.target) / self.samples_per_second;
+        let integral_limit = MAX_ADJUST / INTEGRAL_GAIN;
+        self.integral = (self.integral + error * elapsed_seconds).clamp(-integral_limit, integral_limit);
+
+        (PROPORTIONAL_GAIN * error + INTEGRAL_GAIN * self.integral).clamp(-MAX_ADJUST, MAX_ADJUST)
+    }
+
+    // Output clock relative to the input clock - positive = output runs fast.
+    // Once settled the integral alone carries the whole correction.
+    pub fn drift_ppm(&self) -> f64 {
+        -INTEGRAL_GAIN * self.integral * 1e6
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settles_on_the_clock_difference() {
+        // 48 kHz stereo with a 20 ms ring, output clock 150 ppm fast
+        let block = 960.0;
+        let target = 1_920;
+        let drift = 150e-6;
+        let mut controller = DriftController::new(target, 48_000, 2);
+
+        let mut fill = target as f64;
+        let mut adjust = 0.0;
+        for _ in 0..30_000 {
+            // 5 minutes of 10 ms blocks
+            adjust = controller.update(fill as usize, 0.01);
+            fill += block / (1.0 + adjust) - block * (1.0 + drift);
+        }
+
+        assert!((controller.drift_ppm() - 150.0).abs() < 5.0, "drift {}", controller.drift_ppm());
+        assert!((fill - target as f64).abs() < block / 2.0, "fill {}", fill);
+        assert!((adjust + drift).abs() < 10e-6);
+    }
+}
@@ -3,34 +3,54 @@
 use crate::dsp::modulation_unit::ModulationUnit;
 use std::sync::{Arc, Mutex};
 
+use super::channel_map::ChannelMapSettings;
 use super::device::*;
+use super::output::{OutputDrift, OutputKind, OutputTarget};
+use super::recorder::RecordingSettings;
+use super::replay::ReplayBuffer;
+use crate::music_bed::mixer::MusicBedMixer;
+use crate::soundboard::mixer::SoundboardMixer;
 use super::stream::*;
 use super::utils::*;
 pub struct AudioEngine {
     streams: AudioStreams,
+    output_kinds: Vec<OutputKind>,
 }
 
 impl AudioEngine {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         input_device: &AudioDevice,
-        output_device: &AudioDevice,
+        outputs: &[OutputTarget],
+        channel_map: &ChannelMapSettings,
         opt: &AudioDeviceOptions,
         modulation_unit: Option<Arc<Mutex<ModulationUnit>>>,
-        active_recording: bool,
+        recording_settings: Option<RecordingSettings>,
+        replay_buffer: Option<Arc<Mutex<ReplayBuffer>>>,
+        soundboard: Option<Arc<Mutex<SoundboardMixer>>>,
+        music_bed: Option<Arc<Mutex<MusicBedMixer>>>,
     ) -> anyhow::Result<Self> {
-        // Verify sample rates match
-        verify_sample_rate(&input_device, &output_device)?;
-        // Create latency samples based on options
-        let latency_samples = create_latency_samples(&input_device, opt);
-        // Create audio streams with the specified buffer size
+        // Create latency samples based on options, one ring per output
+        let latency_samples: Vec<usize> = outputs
+            .iter()
+            .map(|output| create_latency_samples(channel_map.layout.channels(), &output.device, opt))
+            .collect();
+        // Create audio streams with the specified buffer sizes
         let streams = AudioStreams::new(
             &input_device,
-            &output_device,
-            latency_samples,
+            outputs,
+            channel_map,
+            &latency_samples,
             modulation_unit,
-            active_recording,
+            recording_settings,
+            replay_buffer,
+            soundboard,
+            music_bed,
         )?;
-        Ok(AudioEngine { streams })
+        Ok(AudioEngine {
+            streams,
+            output_kinds: outputs.iter().map(|output| output.kind).collect(),
+        })
     }
 
     pub fn start(&self) -> anyhow::Result<()> {
@@ -40,10 +60,18 @@ impl AudioEngine {
         Ok(())
     }
 
-    pub fn stop(&self, save_path: Option<String>) -> anyhow::Result<()> {
-        // Stop input and output streams
+    pub fn stop(&mut self) -> anyhow::Result<()> {
+        // Stop input and output streams, finishes the recording
         self.streams.stop_input_stream()?;
-        self.streams.stop_output_stream(save_path)?;
+        self.streams.stop_output_stream()?;
         Ok(())
     }
+
+    pub fn get_output_drift(&self) -> Vec<OutputDrift> {
+        self.output_kinds
+            .iter()
+            .zip(self.streams.get_drift_ppm())
+            .map(|(&output, drift_ppm)| OutputDrift { output, drift_ppm })
+            .collect()
+    }
 }
@@ -4,4 +4,10 @@ pub mod utils;
 pub mod stream;
 pub mod engine;
 pub mod audio_handler;
-pub mod audio_controls;
\ No newline at end of file
+pub mod audio_controls;
+pub mod presets;
+pub mod recorder;
+pub mod replay;
+pub mod output;
+pub mod drift;
+pub mod channel_map;
\ No newline at end of file
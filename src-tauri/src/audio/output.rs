@@ -0,0 +1,144 @@
+// Output fan-out - one processed signal feeds every enabled output, each with its own level
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+
+use super::device::AudioDevice;
+
+pub const MAX_OUTPUT_GAIN: f32 = 2.0;
+
+// Fade time for gain, mute and gate changes - avoids clicks
+const OUTPUT_FADE_MS: f32 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    Monitor,      // headphones - the loopback output device
+    VirtualCable, // the virtual input other apps record from
+}
+
+// Shared with the output callback, so gain and mute apply without an engine restart
+pub struct OutputLevel {
+    gain: AtomicU32, // f32 bits
+    muted: AtomicBool,
+}
+
+impl OutputLevel {
+    pub fn new() -> Self {
+        Self {
+            gain: AtomicU32::new(1.0f32.to_bits()),
+            muted: AtomicBool::new(false),
+        }
+    }
+
+    pub fn set_gain(&self, gain: f32) {
+        self.gain.store(gain.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn get_gain(&self) -> f32 {
+        f32::from_bits(self.gain.load(Ordering::Relaxed))
+    }
+
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, Ordering::Relaxed);
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted.load(Ordering::Relaxed)
+    }
+
+    // Gain the output should be at right now
+    pub fn target_gain(&self, gate: Option<&AtomicBool>) -> f32 {
+        let gate_open = gate.map(|gate| gate.load(Ordering::Relaxed)).unwrap_or(true);
+        if gate_open && !self.is_muted() {
+            self.get_gain()
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for OutputLevel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputLevelInfo {
+    pub output: OutputKind,
+    pub gain: f32,
+    pub muted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputDrift {
+    pub output: OutputKind,
+    pub drift_ppm: f32, // output clock relative to the input clock, positive = output runs fast
+}
+
+pub struct OutputTarget {
+    pub kind: OutputKind,
+    pub device: AudioDevice,
+    pub level: Arc<OutputLevel>,
+    pub gate: Option<Arc<AtomicBool>>, // push-to-talk / push-to-mute, None = always open
+}
+
+// Per-sample linear ramp towards the target gain
+pub struct OutputFader {
+    gain: f32,
+    step: f32,
+}
+
+impl OutputFader {
+    pub fn new(sample_rate: u32, channels: usize, initial_gain: f32) -> Self {
+        Self {
+            gain: initial_gain,
+            step: 1.0 / (OUTPUT_FADE_MS * 0.001 * sample_rate as f32 * channels as f32),
+        }
+    }
+
+    pub fn process(&mut self, data: &mut [f32], target: f32) {
+        if self.gain == target && target == 1.0 {
+            return;
+        }
+        for sample in data.iter_mut() {
+            if self.gain < target {
+                self.gain = (self.gain + self.step).min(target);
+            } else if self.gain > target {
+                self.gain = (self.gain - self.step).max(target);
+            }
+            *sample *= self.gain;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fader_ramps_to_mute_and_gain() {
+        let level = OutputLevel::new();
+        let mut fader = OutputFader::new(1000, 1, level.target_gain(None));
+
+        let mut data = vec![1.0; 10];
+        fader.process(&mut data, level.target_gain(None));
+        assert!(data.iter().all(|&s| s == 1.0));
+
+        // 5 ms at 1 kHz mono = 5 samples to fade out
+        level.set_muted(true);
+        let mut data = vec![1.0; 10];
+        fader.process(&mut data, level.target_gain(None));
+        assert!((data[0] - 0.8).abs() < 1e-6);
+        assert!(data[5..].iter().all(|&s| s == 0.0));
+
+        level.set_muted(false);
+        level.set_gain(0.5);
+        let gate = AtomicBool::new(true);
+        let mut data = vec![1.0; 10];
+        fader.process(&mut data, level.target_gain(Some(&gate)));
+        assert!((data[9] - 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,124 @@
+// Saved effect chains that can be switched from the UI or by hotkeys
+
+use serde::{Deserialize, Serialize};
+
+use crate::dsp::modules::effects::auto_tune::Scale;
+use crate::dsp::modules::utils::ParameterValue;
+use crate::persistence;
+
+const PRESETS_FILE: &str = "presets.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetEffect {
+    pub name: String,
+    pub parameters: Vec<ParameterValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub effects: Vec<PresetEffect>, // in chain order
+    pub auto_tune_scale: Option<Scale>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PresetStore {
+    presets: Vec<Preset>,
+    current: Option<String>,
+}
+
+impl PresetStore {
+    pub fn load() -> Self {
+        persistence::load(PRESETS_FILE)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        persistence::save(PRESETS_FILE, self)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.presets.iter().map(|preset| preset.name.clone()).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    // Replaces a preset with the same name or appends a new one
+    pub fn insert(&mut self, preset: Preset) {
+        match self.presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> anyhow::Result<()> {
+        let index = self
+            .presets
+            .iter()
+            .position(|preset| preset.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Preset '{}' not found", name))?;
+        self.presets.remove(index);
+        if self.current.as_deref() == Some(name) {
+            self.current = None;
+        }
+        Ok(())
+    }
+
+    pub fn current(&self) -> Option<String> {
+        self.current.clone()
+    }
+
+    pub fn set_current(&mut self, name: Option<String>) {
+        self.current = name;
+    }
+
+    // Name of the preset `step` positions away from the current one, wrapping around
+    pub fn neighbour(&self, step: isize) -> Option<String> {
+        if self.presets.is_empty() {
+            return None;
+        }
+        let count = self.presets.len() as isize;
+        let index = match self.current.as_deref().and_then(|name| {
+            self.presets.iter().position(|preset| preset.name == name)
+        }) {
+            Some(index) => (index as isize + step).rem_euclid(count),
+            // Nothing loaded yet - start from the first (or last) preset
+            None if step >= 0 => 0,
+            None => count - 1,
+        };
+        Some(self.presets[index as usize].name.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(name: &str) -> Preset {
+        Preset {
+            name: name.to_string(),
+            effects: vec![],
+            auto_tune_scale: None,
+        }
+    }
+
+    #[test]
+    fn neighbour_wraps_around() {
+        let mut store = PresetStore::default();
+        assert_eq!(store.neighbour(1), None);
+
+        store.insert(preset("robot"));
+        store.insert(preset("chipmunk"));
+        store.insert(preset("cave"));
+        assert_eq!(store.neighbour(1).as_deref(), Some("robot"));
+        assert_eq!(store.neighbour(-1).as_deref(), Some("cave"));
+
+        store.set_current(Some("cave".to_string()));
+        assert_eq!(store.neighbour(1).as_deref(), Some("robot"));
+        assert_eq!(store.neighbour(-1).as_deref(), Some("chipmunk"));
+
+        store.remove("cave").unwrap();
+        assert_eq!(store.current(), None);
+    }
+}
@@ -0,0 +1,309 @@
+// File encoders used by the recorder's writer thread
+
+use flacenc::component::{BitRepr, Stream, StreamInfo};
+use flacenc::error::{Verified, Verify};
+use flacenc::source::{Context, Fill, FrameBuf};
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::settings::{BitDepth, RecordingFormat, RecordingSettings};
+use crate::dsp::modules::utils::NoiseOscillator;
+
+// Samples per channel in one FLAC frame (the reference encoder's default)
+const FLAC_BLOCK_SIZE: usize = 4096;
+
+pub trait SampleEncoder: Send {
+    // Interleaved samples in -1.0 to 1.0
+    fn write(&mut self, samples: &[f32]) -> anyhow::Result<()>;
+
+    // Makes everything written so far readable from disk
+    fn flush(&mut self) -> anyhow::Result<()>;
+
+    fn finalize(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+pub fn create_encoder(
+    path: &Path,
+    settings: &RecordingSettings,
+    sample_rate: u32,
+    channels: u16,
+) -> anyhow::Result<Box<dyn SampleEncoder>> {
+    settings.validate()?;
+    let quantizer = match settings.bit_depth {
+        BitDepth::Float32 => None,
+        bit_depth => Some(Quantizer::new(
+            bit_depth.bits(),
+            settings.dither && bit_depth == BitDepth::Int16,
+        )),
+    };
+
+    let encoder: Box<dyn SampleEncoder> = match (settings.format, quantizer) {
+        (RecordingFormat::Wav, quantizer) => Box::new(WavEncoder::create(
+            path,
+            sample_rate,
+            channels,
+            settings.bit_depth,
+            quantizer,
+        )?),
+        (RecordingFormat::Flac, Some(quantizer)) => Box::new(FlacEncoder::create(
+            path,
+            sample_rate,
+            channels,
+            settings.bit_depth,
+            quantizer,
+        )?),
+        (RecordingFormat::Flac, None) => {
+            return Err(anyhow::anyhow!("FLAC recordings must be 16 or 24 bit"));
+        }
+    };
+    Ok(encoder)
+}
+
+fn create_error(path: &Path, e: impl std::fmt::Display) -> anyhow::Error {
+    anyhow::anyhow!("Failed to create recording '{}': {}", path.display(), e)
+}
+
+// Float to integer conversion, optionally with TPDF dither of +-1 LSB
+struct Quantizer {
+    max: f32,
+    dither: Option<NoiseOscillator>,
+}
+
+impl Quantizer {
+    fn new(bits: u16, dither: bool) -> Self {
+        Self {
+            max: ((1i32 << (bits - 1)) - 1) as f32,
+            dither: if dither { Some(NoiseOscillator::new(1.0)) } else { None },
+        }
+    }
+
+    fn quantize(&mut self, sample: f32) -> i32 {
+        // Sum of two uniform values has the triangular density TPDF needs
+        let dither = match self.dither {
+            Some(ref mut noise) => (noise.process() + noise.process()) * 0.5,
+            None => 0.0,
+        };
+        (sample.clamp(-1.0, 1.0) * self.max + dither)
+            .round()
+            .clamp(-self.max - 1.0, self.max) as i32
+    }
+}
+
+struct WavEncoder {
+    writer: hound::WavWriter<BufWriter<File>>,
+    quantizer: Option<Quantizer>, // None = 32-bit float
+}
+
+impl WavEncoder {
+    fn create(
+        path: &Path,
+        sample_rate: u32,
+        channels: u16,
+        bit_depth: BitDepth,
+        quantizer: Option<Quantizer>,
+    ) -> anyhow::Result<Self> {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: bit_depth.bits(),
+            sample_format: match bit_depth {
+                BitDepth::Float32 => hound::SampleFormat::Float,
+                _ => hound::SampleFormat::Int,
+            },
+        };
+        let writer = hound::WavWriter::create(path, spec).map_err(|e| create_error(path, e))?;
+        Ok(Self { writer, quantizer })
+    }
+}
+
+impl SampleEncoder for WavEncoder {
+    fn write(&mut self, samples: &[f32]) -> anyhow::Result<()> {
+        match self.quantizer {
+            Some(ref mut quantizer) => {
+                for &sample in samples {
+                    self.writer.write_sample(quantizer.quantize(sample))?;
+                }
+            }
+            None => {
+                for &sample in samples {
+                    self.writer.write_sample(sample)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Rewrites the header, so the file on disk is a valid WAV up to here
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn finalize(self: Box<Self>) -> anyhow::Result<()> {
+        self.writer.finalize()?;
+        Ok(())
+    }
+}
+
+// Encodes fixed-size frames as they fill up; STREAMINFO is rewritten in place on flush
+struct FlacEncoder {
+    file: BufWriter<File>,
+    config: Verified<flacenc::config::Encoder>,
+    stream_info: StreamInfo,
+    framebuf: FrameBuf,
+    context: Context,
+    pending: Vec<i32>, // interleaved samples of the frame being filled
+    channels: usize,
+    quantizer: Quantizer,
+}
+
+impl FlacEncoder {
+    fn create(
+        path: &Path,
+        sample_rate: u32,
+        channels: u16,
+        bit_depth: BitDepth,
+        quantizer: Quantizer,
+    ) -> anyhow::Result<Self> {
+        let channels = channels as usize;
+        let bits = bit_depth.bits() as usize;
+        let mut stream_info = StreamInfo::new(sample_rate as usize, channels, bits)
+            .map_err(|e| create_error(path, e))?;
+        stream_info
+            .set_block_sizes(FLAC_BLOCK_SIZE, FLAC_BLOCK_SIZE)
+            .map_err(|e| create_error(path, e))?;
+        let config = flacenc::config::Encoder::default()
+            .into_verified()
+            .map_err(|(_, e)| create_error(path, e))?;
+        let framebuf = FrameBuf::with_size(channels, FLAC_BLOCK_SIZE).map_err(|e| create_error(path, e))?;
+
+        let file = File::create(path).map_err(|e| create_error(path, e))?;
+        let mut encoder = Self {
+            file: BufWriter::new(file),
+            config,
+            stream_info,
+            framebuf,
+            context: Context::new(bits, channels),
+            pending: Vec::with_capacity(FLAC_BLOCK_SIZE * channels),
+            channels,
+            quantizer,
+        };
+        encoder.write_header()?;
+        Ok(encoder)
+    }
+
+    // The header has a fixed size, so it can be overwritten once more is known
+    fn write_header(&mut self) -> anyhow::Result<()> {
+        let mut sink = flacenc::bitsink::MemSink::<u8>::new();
+        Stream::with_stream_info(self.stream_info.clone())
+            .write(&mut sink)
+            .map_err(|e| anyhow::anyhow!("Failed to write FLAC header: {}", e))?;
+
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(sink.as_slice())?;
+        self.file.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    fn encode_pending(&mut self) -> anyhow::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        (&mut self.framebuf, &mut self.context)
+            .fill_interleaved(&self.pending)
+            .map_err(|e| anyhow::anyhow!("Failed to buffer FLAC frame: {}", e))?;
+        self.pending.clear();
+
+        let frame_number = self.context.current_frame_number().unwrap_or(0);
+        let frame = flacenc::encode_fixed_size_frame(&self.config, &self.framebuf, frame_number, &self.stream_info)
+            .map_err(|e| anyhow::anyhow!("Failed to encode FLAC frame: {}", e))?;
+        self.stream_info.update_frame_info(&frame);
+
+        let mut sink = flacenc::bitsink::MemSink::<u8>::with_capacity(frame.count_bits());
+        frame
+            .write(&mut sink)
+            .map_err(|e| anyhow::anyhow!("Failed to write FLAC frame: {}", e))?;
+        self.file.write_all(sink.as_slice())?;
+        Ok(())
+    }
+}
+
+impl SampleEncoder for FlacEncoder {
+    fn write(&mut self, samples: &[f32]) -> anyhow::Result<()> {
+        let frame_len = FLAC_BLOCK_SIZE * self.channels;
+        for &sample in samples {
+            self.pending.push(self.quantizer.quantize(sample));
+            if self.pending.len() == frame_len {
+                self.encode_pending()?;
+            }
+        }
+        Ok(())
+    }
+
+    // Only whole frames reach the disk - the partial one waits for more samples
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.write_header()?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn finalize(mut self: Box<Self>) -> anyhow::Result<()> {
+        // Drop a trailing partial frame so the last frame holds every channel
+        let whole = self.pending.len() - self.pending.len() % self.channels;
+        self.pending.truncate(whole);
+        self.encode_pending()?;
+
+        let digest = self.context.md5_digest();
+        self.stream_info.set_md5_digest(&digest);
+        self.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dither_stays_within_one_lsb() {
+        let mut quantizer = Quantizer::new(16, true);
+        let exact = 0.25 * quantizer.max;
+        let mut error = 0.0;
+        for _ in 0..10_000 {
+            let value = quantizer.quantize(0.25) as f32;
+            assert!((value - exact).abs() < 2.0);
+            error += value - exact;
+        }
+        // TPDF noise is zero-mean, so the average lands between the two steps
+        let bias = error / 10_000.0;
+        assert!(bias.abs() < 0.05, "dither bias {}", bias);
+
+        assert_eq!(Quantizer::new(24, false).quantize(2.0), (1 << 23) - 1);
+        assert_eq!(Quantizer::new(16, false).quantize(-1.0), -i16::MAX as i32);
+    }
+
+    #[test]
+    fn flac_header_counts_every_sample() {
+        let path = std::env::temp_dir().join("pitchslap_flac_encoder_test.flac");
+        let settings = RecordingSettings {
+            format: RecordingFormat::Flac,
+            bit_depth: BitDepth::Int24,
+            ..Default::default()
+        };
+        let mut encoder = create_encoder(&path, &settings, 48_000, 2).unwrap();
+        let samples: Vec<f32> = (0..10_000).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+        encoder.write(&samples).unwrap();
+        encoder.finalize().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..4], b"fLaC");
+        // STREAMINFO: 20 bits rate, 3 bits channels, 5 bits depth, 36 bits total samples
+        let packed = u64::from_be_bytes(bytes[18..26].try_into().unwrap());
+        assert_eq!(packed >> 44, 48_000);
+        assert_eq!(((packed >> 41) & 0x7) + 1, 2);
+        assert_eq!(((packed >> 36) & 0x1f) + 1, 24);
+        assert_eq!(packed & 0xf_ffff_ffff, 5_000);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
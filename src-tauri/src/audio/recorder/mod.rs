@@ -0,0 +1,331 @@
+// Streaming recorder - the audio callback pushes samples into a lock-free ring,
+// a writer thread drains it to disk so a take never has to fit in memory.
+
+pub mod encoder;
+pub mod settings;
+
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::HeapRb;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use encoder::{create_encoder, SampleEncoder};
+pub use settings::{BitDepth, RecordingFormat, RecordingSettings, RecordingTracks};
+
+// Seconds of audio the ring holds if the disk stalls
+const RING_SECONDS: usize = 2;
+// Header rewrite interval - a crash loses at most this much of the take
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// Audio callback side - never blocks, drops samples if the writer falls behind
+pub struct RecordingSink {
+    producer: <HeapRb<f32> as Split>::Prod,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl RecordingSink {
+    // Whole blocks or nothing - a partial block would shift every later frame across channels
+    pub fn push(&mut self, data: &[f32]) {
+        if self.vacant_len() >= data.len() {
+            self.producer.push_slice(data);
+        } else {
+            self.skip(data.len());
+        }
+    }
+
+    fn vacant_len(&self) -> usize {
+        self.producer.vacant_len()
+    }
+
+    fn skip(&self, count: usize) {
+        self.dropped.fetch_add(count, Ordering::Relaxed);
+    }
+}
+
+// Where the input callback sends each block - dry and processed are pushed together,
+// so every track of a take stays sample-aligned
+pub enum RecordingTaps {
+    Processed(RecordingSink),
+    Separate {
+        dry: RecordingSink,
+        processed: RecordingSink,
+    },
+    Multichannel {
+        sink: RecordingSink,
+        channels: usize,
+    },
+}
+
+impl RecordingTaps {
+    pub fn push(&mut self, dry: &[f32], processed: &[f32]) {
+        match self {
+            RecordingTaps::Processed(sink) => sink.push(processed),
+            RecordingTaps::Separate {
+                dry: dry_sink,
+                processed: processed_sink,
+            } => {
+                // A block is dropped from both files or neither, otherwise they drift apart
+                if dry_sink.vacant_len() >= dry.len() && processed_sink.vacant_len() >= processed.len() {
+                    dry_sink.push(dry);
+                    processed_sink.push(processed);
+                } else {
+                    dry_sink.skip(dry.len());
+                    processed_sink.skip(processed.len());
+                }
+            }
+            // Interleaved straight into the ring, frame by frame - the callback never allocates
+            RecordingTaps::Multichannel { sink, channels } => {
+                if sink.vacant_len() < dry.len() + processed.len() {
+                    sink.skip(dry.len() + processed.len());
+                    return;
+                }
+                for (dry_frame, processed_frame) in dry.chunks(*channels).zip(processed.chunks(*channels)) {
+                    sink.producer.push_slice(dry_frame);
+                    sink.producer.push_slice(processed_frame);
+                }
+            }
+        }
+    }
+}
+
+pub struct RecordingWriter {
+    path: PathBuf,
+    handle: Option<JoinHandle<anyhow::Result<()>>>,
+    control: Arc<Mutex<bool>>, // true = run, false = stop
+    dropped: Arc<AtomicUsize>,
+}
+
+// Creates the file right away, so a bad path fails before the stream starts
+pub fn start_recording(
+    path: &Path,
+    settings: &RecordingSettings,
+    sample_rate: u32,
+    channels: u16,
+) -> anyhow::Result<(RecordingSink, RecordingWriter)> {
+    let writer = create_encoder(path, settings, sample_rate, channels)?;
+
+    let capacity = (sample_rate as usize * channels as usize * RING_SECONDS).max(1);
+    let (producer, consumer) = HeapRb::<f32>::new(capacity).split();
+    let control = Arc::new(Mutex::new(true));
+    let dropped = Arc::new(AtomicUsize::new(0));
+
+    let thread_control = Arc::clone(&control);
+    let handle = thread::spawn(move || write_loop(writer, consumer, thread_control));
+
+    Ok((
+        RecordingSink {
+            producer,
+            dropped: Arc::clone(&dropped),
+        },
+        RecordingWriter {
+            path: path.to_path_buf(),
+            handle: Some(handle),
+            control,
+            dropped,
+        },
+    ))
+}
+
+// Opens every file of a take as picked by `settings.tracks`
+pub fn start_session(
+    settings: &RecordingSettings,
+    sample_rate: u32,
+    channels: u16,
+) -> anyhow::Result<(RecordingTaps, Vec<RecordingWriter>)> {
+    match settings.tracks {
+        RecordingTracks::Processed => {
+            let path = settings.next_path()?;
+            let (sink, writer) = start_recording(&path, settings, sample_rate, channels)?;
+            Ok((RecordingTaps::Processed(sink), vec![writer]))
+        }
+        RecordingTracks::Separate => {
+            let paths = settings.next_paths(&["_dry", "_processed"])?;
+            let (dry, dry_writer) = start_recording(&paths[0], settings, sample_rate, channels)?;
+            let (processed, processed_writer) = start_recording(&paths[1], settings, sample_rate, channels)?;
+            Ok((
+                RecordingTaps::Separate { dry, processed },
+                vec![dry_writer, processed_writer],
+            ))
+        }
+        RecordingTracks::Multichannel => {
+            let path = settings.next_path()?;
+            let (sink, writer) = start_recording(&path, settings, sample_rate, channels * 2)?;
+            Ok((
+                RecordingTaps::Multichannel {
+                    sink,
+                    channels: channels.max(1) as usize,
+                },
+                vec![writer],
+            ))
+        }
+    }
+}
+
+fn write_loop(
+    mut writer: Box<dyn SampleEncoder>,
+    mut consumer: <HeapRb<f32> as Split>::Cons,
+    control: Arc<Mutex<bool>>,
+) -> anyhow::Result<()> {
+    let mut chunk = vec![0.0f32; 4096];
+    let mut last_flush = Instant::now();
+
+    loop {
+        let running = control.lock().map(|run| *run).unwrap_or(false);
+
+        // Drain everything available - after a stop this also picks up the tail
+        drain(writer.as_mut(), &mut consumer, &mut chunk)?;
+
+        if !running {
+            break;
+        }
+
+        if last_flush.elapsed() >= FLUSH_INTERVAL {
+            writer.flush()?;
+            last_flush = Instant::now();
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    writer.finalize()?;
+    Ok(())
+}
+
+fn drain(
+    writer: &mut dyn SampleEncoder,
+    consumer: &mut <HeapRb<f32> as Split>::Cons,
+    chunk: &mut [f32],
+) -> anyhow::Result<()> {
+    while !consumer.is_empty() {
+        let count = consumer.pop_slice(chunk);
+        writer.write(&chunk[..count])?;
+    }
+    Ok(())
+}
+
+impl RecordingWriter {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Writes what is left in the ring and closes the file
+    pub fn finish(mut self) -> anyhow::Result<PathBuf> {
+        self.stop()?;
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        if dropped > 0 {
+            eprintln!("Recording fell behind, {} samples were dropped.", dropped);
+        }
+        Ok(self.path.clone())
+    }
+
+    fn stop(&mut self) -> anyhow::Result<()> {
+        if let Ok(mut run) = self.control.lock() {
+            *run = false;
+        }
+        match self.handle.take() {
+            Some(handle) => handle
+                .join()
+                .map_err(|_| anyhow::anyhow!("Recording writer thread panicked"))?,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for RecordingWriter {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_to_a_readable_file() {
+        let path = std::env::temp_dir().join("pitchslap_recorder_test.wav");
+        let settings = RecordingSettings {
+            dither: false,
+            ..Default::default()
+        };
+        let (mut sink, writer) = start_recording(&path, &settings, 8_000, 1).unwrap();
+
+        sink.push(&[0.5; 800]);
+        sink.push(&[-0.25; 200]);
+        // Joins the writer thread, so everything pushed is on disk afterwards
+        assert_eq!(writer.finish().unwrap(), path);
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        let samples: Vec<i16> = reader.samples::<i16>().map(Result::unwrap).collect();
+        assert_eq!(samples.len(), 1000);
+        assert_eq!(samples[0], (0.5 * i16::MAX as f32).round() as i16);
+        assert_eq!(samples[999], (-0.25 * i16::MAX as f32).round() as i16);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn flushed_take_is_readable_while_recording() {
+        // One pass of the writer loop, run here instead of on its thread
+        let path = std::env::temp_dir().join("pitchslap_recorder_flush_test.wav");
+        let mut writer = create_encoder(&path, &RecordingSettings::default(), 8_000, 1).unwrap();
+        let (mut producer, mut consumer) = HeapRb::<f32>::new(1_000).split();
+        let mut chunk = [0.0f32; 256];
+
+        producer.push_slice(&[0.5; 800]);
+        drain(writer.as_mut(), &mut consumer, &mut chunk).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(hound::WavReader::open(&path).unwrap().len(), 800);
+
+        writer.finalize().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn multichannel_take_puts_dry_before_processed() {
+        let dir = std::env::temp_dir().join("pitchslap_recorder_session_test");
+        let settings = RecordingSettings {
+            bit_depth: BitDepth::Float32,
+            tracks: RecordingTracks::Multichannel,
+            filename_template: "take".to_string(),
+            directory: Some(dir.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        let (mut taps, writers) = start_session(&settings, 8_000, 2).unwrap();
+
+        taps.push(&[0.1, 0.2, 0.3, 0.4], &[-0.1, -0.2, -0.3, -0.4]);
+        let paths: Vec<PathBuf> = writers.into_iter().map(|writer| writer.finish().unwrap()).collect();
+
+        let mut reader = hound::WavReader::open(&paths[0]).unwrap();
+        assert_eq!(reader.spec().channels, 4);
+        let samples: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+        assert_eq!(samples, vec![0.1, 0.2, -0.1, -0.2, 0.3, 0.4, -0.3, -0.4]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn full_ring_drops_whole_blocks() {
+        let (producer, mut consumer) = HeapRb::<f32>::new(6).split();
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut taps = RecordingTaps::Multichannel {
+            sink: RecordingSink {
+                producer,
+                dropped: Arc::clone(&dropped),
+            },
+            channels: 1,
+        };
+
+        taps.push(&[0.1, 0.2], &[-0.1, -0.2]);
+        // Two samples would still fit - the block is dropped whole so the channels stay in place
+        taps.push(&[0.3, 0.4], &[-0.3, -0.4]);
+        assert_eq!(dropped.load(Ordering::Relaxed), 4);
+
+        let mut ring = [0.0; 6];
+        assert_eq!(consumer.pop_slice(&mut ring), 4);
+        assert_eq!(ring[..4], [0.1, -0.1, 0.2, -0.2]);
+    }
+}
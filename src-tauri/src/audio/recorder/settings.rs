@@ -0,0 +1,196 @@
+// Recording options chosen by the user - file format, bit depth and where takes go
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const DEFAULT_FILENAME_TEMPLATE: &str = "recording_{date}_{time}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingFormat {
+    Wav,
+    Flac,
+}
+
+impl RecordingFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RecordingFormat::Wav => "wav",
+            RecordingFormat::Flac => "flac",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BitDepth {
+    Int16,
+    Int24,
+    Float32,
+}
+
+impl BitDepth {
+    pub fn bits(&self) -> u16 {
+        match self {
+            BitDepth::Int16 => 16,
+            BitDepth::Int24 => 24,
+            BitDepth::Float32 => 32,
+        }
+    }
+}
+
+// Which signals a take records - dry is the input before the modulation unit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingTracks {
+    Processed,
+    Separate,     // "_dry" and "_processed" files
+    Multichannel, // one file, dry channels first then processed channels
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingSettings {
+    pub format: RecordingFormat,
+    pub bit_depth: BitDepth,
+    pub dither: bool, // TPDF dither, only applied to 16-bit
+    pub tracks: RecordingTracks,
+    // "{date}" and "{time}" are replaced with the local start time of the take
+    pub filename_template: String,
+    pub directory: Option<String>, // None = system temp dir
+}
+
+impl Default for RecordingSettings {
+    fn default() -> Self {
+        Self {
+            format: RecordingFormat::Wav,
+            bit_depth: BitDepth::Int16,
+            dither: true,
+            tracks: RecordingTracks::Processed,
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+            directory: None,
+        }
+    }
+}
+
+impl RecordingSettings {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.format == RecordingFormat::Flac && self.bit_depth == BitDepth::Float32 {
+            return Err(anyhow::anyhow!("FLAC recordings must be 16 or 24 bit"));
+        }
+        let template = self.filename_template.trim();
+        if template.is_empty() {
+            return Err(anyhow::anyhow!("Filename template must not be empty"));
+        }
+        if template.contains(['/', '\\']) {
+            return Err(anyhow::anyhow!("Filename template must not contain path separators"));
+        }
+        Ok(())
+    }
+
+    pub fn directory(&self) -> PathBuf {
+        match self.directory {
+            Some(ref dir) => PathBuf::from(dir),
+            None => std::env::temp_dir(),
+        }
+    }
+
+    // Path for a take starting now - creates the directory, never overwrites an existing file
+    pub fn next_path(&self) -> anyhow::Result<PathBuf> {
+        Ok(self.next_paths(&[""])?.remove(0))
+    }
+
+    // One path per suffix, all sharing the first index at which none of them exists yet
+    pub fn next_paths(&self, suffixes: &[&str]) -> anyhow::Result<Vec<PathBuf>> {
+        self.validate()?;
+        let dir = self.directory();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| anyhow::anyhow!("Cannot use recording directory '{}': {}", dir.display(), e))?;
+
+        let stem = render_template(self.filename_template.trim(), &Local::now());
+        let extension = self.format.extension();
+        for index in 1.. {
+            let numbered = if index == 1 {
+                stem.clone()
+            } else {
+                format!("{}_{}", stem, index)
+            };
+            let paths: Vec<PathBuf> = suffixes
+                .iter()
+                .map(|suffix| dir.join(format!("{}{}.{}", numbered, suffix, extension)))
+                .collect();
+            if paths.iter().all(|path| !path.exists()) {
+                return Ok(paths);
+            }
+        }
+        unreachable!()
+    }
+}
+
+fn render_template(template: &str, time: &DateTime<Local>) -> String {
+    template
+        .replace("{date}", &time.format("%Y-%m-%d").to_string())
+        .replace("{time}", &time.format("%H-%M-%S").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn template_is_filled_with_the_start_time() {
+        let time = Local.with_ymd_and_hms(2024, 3, 7, 9, 5, 2).unwrap();
+        assert_eq!(
+            render_template(DEFAULT_FILENAME_TEMPLATE, &time),
+            "recording_2024-03-07_09-05-02"
+        );
+        assert_eq!(render_template("take", &time), "take");
+    }
+
+    #[test]
+    fn existing_takes_are_not_overwritten() {
+        let dir = std::env::temp_dir().join("pitchslap_recording_settings_test");
+        let settings = RecordingSettings {
+            format: RecordingFormat::Flac,
+            filename_template: "take".to_string(),
+            directory: Some(dir.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+
+        let first = settings.next_path().unwrap();
+        assert_eq!(first, dir.join("take.flac"));
+        std::fs::write(&first, b"").unwrap();
+        assert_eq!(settings.next_path().unwrap(), dir.join("take_2.flac"));
+
+        std::fs::write(dir.join("take_2_dry.flac"), b"").unwrap();
+        assert_eq!(
+            settings.next_paths(&["_dry", "_processed"]).unwrap(),
+            vec![dir.join("take_dry.flac"), dir.join("take_processed.flac")]
+        );
+        std::fs::write(dir.join("take_processed.flac"), b"").unwrap();
+        assert_eq!(
+            settings.next_paths(&["_dry", "_processed"]).unwrap()[0],
+            dir.join("take_3_dry.flac")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let float_flac = RecordingSettings {
+            format: RecordingFormat::Flac,
+            bit_depth: BitDepth::Float32,
+            ..Default::default()
+        };
+        assert!(float_flac.validate().is_err());
+
+        let nested = RecordingSettings {
+            filename_template: "../take".to_string(),
+            ..Default::default()
+        };
+        assert!(nested.next_path().is_err());
+    }
+}
@@ -0,0 +1,192 @@
+// Instant replay - a fixed-size ring with the last seconds of processed audio,
+// dumped to a file on demand. Separate from the recorder, which writes whole takes.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use super::recorder::encoder::create_encoder;
+use super::recorder::{RecordingFormat, RecordingSettings};
+
+pub const MIN_REPLAY_SECONDS: f32 = 5.0;
+pub const MAX_REPLAY_SECONDS: f32 = 300.0;
+const REPLAY_FILENAME_TEMPLATE: &str = "replay_{date}_{time}";
+
+pub struct ReplayBuffer {
+    samples: Vec<f32>, // interleaved, preallocated for the whole duration
+    position: usize,   // next write index, also the oldest sample once filled
+    filled: bool,
+    sample_rate: u32,
+    channels: u16,
+}
+
+impl ReplayBuffer {
+    pub fn new(seconds: f32, sample_rate: u32, channels: u16) -> Self {
+        let frames = (seconds * sample_rate as f32) as usize;
+        Self {
+            samples: vec![0.0; (frames * channels as usize).max(1)],
+            position: 0,
+            filled: false,
+            sample_rate,
+            channels,
+        }
+    }
+
+    pub fn push(&mut self, data: &[f32]) {
+        let len = self.samples.len();
+        // Only the newest `len` samples of an oversized block survive anyway
+        let data = &data[data.len().saturating_sub(len)..];
+
+        let first = data.len().min(len - self.position);
+        self.samples[self.position..self.position + first].copy_from_slice(&data[..first]);
+        let rest = data.len() - first;
+        self.samples[..rest].copy_from_slice(&data[first..]);
+
+        if self.position + data.len() >= len {
+            self.filled = true;
+        }
+        self.position = (self.position + data.len()) % len;
+    }
+
+    // Buffered audio, oldest first
+    pub fn snapshot(&self) -> Vec<f32> {
+        if self.filled {
+            let mut samples = self.samples[self.position..].to_vec();
+            samples.extend_from_slice(&self.samples[..self.position]);
+            samples
+        } else {
+            self.samples[..self.position].to_vec()
+        }
+    }
+
+    // New duration in the same format - the newest audio that fits is kept
+    pub fn resize(&mut self, seconds: f32) {
+        let history = self.snapshot();
+        let mut resized = Self::new(seconds, self.sample_rate, self.channels);
+        resized.push(&history);
+        *self = resized;
+    }
+
+    pub fn is_format(&self, sample_rate: u32, channels: u16) -> bool {
+        self.sample_rate == sample_rate && self.channels == channels
+    }
+}
+
+// Audio callback side - only ever try_locks. Blocks that arrive while a replay is being
+// copied out wait in the backlog and go in ahead of the next block, so the history has no hole
+pub struct ReplayFeed {
+    buffer: Arc<Mutex<ReplayBuffer>>,
+    backlog: Vec<f32>,
+    backlog_capacity: usize, // a second of audio, a longer copy loses the oldest
+}
+
+impl ReplayFeed {
+    pub fn new(buffer: Arc<Mutex<ReplayBuffer>>) -> Self {
+        let backlog_capacity = match buffer.lock() {
+            Ok(buffer) => (buffer.sample_rate as usize * buffer.channels as usize).max(1),
+            Err(_) => 1,
+        };
+        Self {
+            buffer,
+            backlog: Vec::with_capacity(backlog_capacity),
+            backlog_capacity,
+        }
+    }
+
+    pub fn push(&mut self, block: &[f32]) {
+        if let Ok(mut buffer) = self.buffer.try_lock() {
+            buffer.push(&self.backlog);
+            buffer.push(block);
+            self.backlog.clear();
+            return;
+        }
+
+        let capacity = self.backlog_capacity;
+        let block = &block[block.len().saturating_sub(capacity)..];
+        let excess = (self.backlog.len() + block.len()).saturating_sub(capacity);
+        self.backlog.drain(..excess);
+        self.backlog.extend_from_slice(block);
+    }
+}
+
+// Copies the ring under the lock and encodes outside it, so the audio callback is not held up
+pub fn save_replay(buffer: &Mutex<ReplayBuffer>, settings: &RecordingSettings) -> anyhow::Result<PathBuf> {
+    let (samples, sample_rate, channels) = {
+        let buffer = buffer
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire replay buffer lock: {}", e))?;
+        (buffer.snapshot(), buffer.sample_rate, buffer.channels)
+    };
+    if samples.is_empty() {
+        return Err(anyhow::anyhow!("Replay buffer is empty"));
+    }
+
+    let settings = RecordingSettings {
+        format: RecordingFormat::Wav,
+        filename_template: REPLAY_FILENAME_TEMPLATE.to_string(),
+        ..settings.clone()
+    };
+    let path = settings.next_path()?;
+    let mut encoder = create_encoder(&path, &settings, sample_rate, channels)?;
+    encoder.write(&samples)?;
+    encoder.finalize()?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_newest_samples_in_order() {
+        // 1 second at 4 Hz, stereo = 8 samples
+        let mut buffer = ReplayBuffer::new(1.0, 4, 2);
+        buffer.push(&[1.0, 2.0, 3.0]);
+        assert_eq!(buffer.snapshot(), vec![1.0, 2.0, 3.0]);
+
+        buffer.push(&[4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0]);
+        assert_eq!(buffer.snapshot(), vec![3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0]);
+
+        let oversized: Vec<f32> = (0..20).map(|i| i as f32).collect();
+        buffer.push(&oversized);
+        assert_eq!(buffer.snapshot(), oversized[12..].to_vec());
+    }
+
+    #[test]
+    fn resizing_keeps_the_newest_samples() {
+        // 2 seconds at 2 Hz mono
+        let mut buffer = ReplayBuffer::new(2.0, 2, 1);
+        buffer.push(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        buffer.resize(1.0);
+        assert_eq!(buffer.snapshot(), vec![4.0, 5.0]);
+
+        buffer.resize(3.0);
+        assert_eq!(buffer.snapshot(), vec![4.0, 5.0]);
+        buffer.push(&[6.0, 7.0, 8.0, 9.0, 10.0]);
+        assert_eq!(buffer.snapshot(), vec![5.0, 6.0, 7.0, 8.0, 9.0, 10.0]);
+    }
+
+    #[test]
+    fn blocks_wait_while_a_replay_is_copied_out() {
+        // 4 seconds at 2 Hz mono - the backlog holds one second, 2 samples
+        let buffer = Arc::new(Mutex::new(ReplayBuffer::new(4.0, 2, 1)));
+        let mut feed = ReplayFeed::new(Arc::clone(&buffer));
+        feed.push(&[1.0]);
+
+        let held = buffer.lock().unwrap();
+        feed.push(&[2.0]);
+        feed.push(&[3.0]);
+        drop(held);
+        feed.push(&[4.0]);
+        assert_eq!(buffer.lock().unwrap().snapshot(), vec![1.0, 2.0, 3.0, 4.0]);
+
+        // A longer copy keeps only the newest second
+        let held = buffer.lock().unwrap();
+        feed.push(&[5.0]);
+        feed.push(&[6.0]);
+        feed.push(&[7.0]);
+        drop(held);
+        feed.push(&[8.0]);
+        assert_eq!(buffer.lock().unwrap().snapshot(), vec![1.0, 2.0, 3.0, 4.0, 6.0, 7.0, 8.0]);
+    }
+}
@@ -2,108 +2,167 @@
 
 use cpal::Stream;
 use cpal::traits::{DeviceTrait, StreamTrait};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 
 use super::buffer::*;
+use super::channel_map::{ChannelMapSettings, ChannelMapper};
 use super::device::*;
-use super::utils::save_audio_buffer_to_file;
+use super::drift::DriftController;
+use super::output::{OutputFader, OutputTarget};
+use super::recorder::{start_session, RecordingSettings, RecordingWriter};
+use super::replay::{ReplayBuffer, ReplayFeed};
 
 use crate::dsp::modulation_unit::ModulationUnit;
+use crate::dsp::modules::utils::Resampler;
+use crate::music_bed::mixer::MusicBedMixer;
+use crate::soundboard::mixer::SoundboardMixer;
 
+// One input stream and one DSP pass, fanned out to a ring buffer per output
 pub struct AudioStreams {
-    audio_buffer: Arc<Mutex<AudioBuffer>>,
+    audio_buffers: Vec<Arc<Mutex<AudioBuffer>>>,
     input_stream: Stream,
-    output_stream: Stream,
+    output_streams: Vec<Stream>,
+    drift_ppm: Vec<Arc<AtomicU32>>, // f32 bits, written by the input callback
 
-    recording_buffer: Option<Arc<Mutex<Vec<f32>>>>,
-    record_sample_rate: u32,
-    record_channels: u16,
+    recording: Vec<RecordingWriter>,
 }
 
 impl AudioStreams {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         input_device: &AudioDevice,
-        output_device: &AudioDevice,
-        buffer_size: usize,
+        outputs: &[OutputTarget],
+        channel_map: &ChannelMapSettings,
+        latency_samples: &[usize],
         modulation_unit: Option<Arc<Mutex<ModulationUnit>>>,
-        active_recording: bool,
+        recording_settings: Option<RecordingSettings>,
+        replay_buffer: Option<Arc<Mutex<ReplayBuffer>>>,
+        soundboard: Option<Arc<Mutex<SoundboardMixer>>>,
+        music_bed: Option<Arc<Mutex<MusicBedMixer>>>,
     ) -> anyhow::Result<Self> {
-        let audio_buffer = Arc::new(Mutex::new(AudioBuffer::new(buffer_size)));
-
-        let buffer_input = Arc::clone(&audio_buffer);
-        let buffer_output = Arc::clone(&audio_buffer);
+        let audio_buffers: Vec<Arc<Mutex<AudioBuffer>>> = latency_samples
+            .iter()
+            .map(|&latency| Arc::new(Mutex::new(AudioBuffer::new(latency))))
+            .collect();
 
+        let input_rate = input_device.get_config().sample_rate.0;
         let input_channels = input_device.get_config().channels as usize;
-        let output_channels = output_device.get_config().channels as usize;
-
-        let record_sample_rate = input_device.get_config().sample_rate.0;
-        let record_channels = input_device.get_config().channels;
-        let recording_buffer = if active_recording {
-            Some(Arc::new(Mutex::new(Vec::new())))
-        } else {
-            None
+        // Everything after the mapper - effects, recording, rings - uses the processing channels
+        let mapper = ChannelMapper::new(channel_map, input_channels)?;
+        let channels = mapper.channels();
+        let drift_ppm: Vec<Arc<AtomicU32>> = outputs.iter().map(|_| Arc::new(AtomicU32::new(0))).collect();
+        // Every output goes through a resampler - it converts the rate and absorbs clock drift
+        let mut feeds: Vec<OutputFeed> = outputs
+            .iter()
+            .zip(&audio_buffers)
+            .zip(latency_samples.iter().zip(&drift_ppm))
+            .map(|((output, buffer), (&latency, drift_ppm))| {
+                let output_rate = output.device.get_config().sample_rate.0;
+                OutputFeed {
+                    buffer: Arc::clone(buffer),
+                    resampler: Resampler::new(input_rate, output_rate, channels),
+                    drift: DriftController::new(latency, output_rate, channels),
+                    drift_ppm: Arc::clone(drift_ppm),
+                }
+            })
+            .collect();
+
+        // Records the voice before and/or after the modulation unit, in the processing format
+        let (mut recording_taps, recording) = match recording_settings {
+            Some(settings) => {
+                let (taps, writers) = start_session(
+                    &settings,
+                    input_device.get_config().sample_rate.0,
+                    channels as u16,
+                )?;
+                (Some(taps), writers)
+            }
+            None => (None, Vec::new()),
         };
-        let recording_buffer_input = recording_buffer.as_ref().map(Arc::clone);
+
+        let mut replay_feed = replay_buffer.map(ReplayFeed::new);
 
         let input_stream = input_device.get_device().build_input_stream(
             input_device.get_config(),
             move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                if let Ok(mut buffer) = buffer_input.lock() {
-                    let processed = if let Some(ref modulation_unit) = modulation_unit {
-                        match modulation_unit.lock() {
-                            Ok(mut mod_unit) => mod_unit.process(data),
-                            Err(poisoned) => {
-                                eprintln!("Modulation unit lock poisoned: {}", poisoned);
-                                data.to_vec()
-                            }
-                        }
-                    } else {
-                        data.to_vec()
-                    };
-
-                    if active_recording {
-                        if let Some(recording_buffer_input) = &recording_buffer_input {
-                            if let Ok(mut recording) = recording_buffer_input.lock() {
-                                if let Err(e) =
-                                    buffer.buffer_write_and_record(&processed, &mut recording)
-                                {
-                                    eprintln!("Input callback error: {}", e);
-                                }
-                            }
-                        } else if let Err(e) = buffer.buffer_write(&processed) {
-                            eprintln!("Input callback error: {}", e);
-                        }
-                    } else {
-                        if let Err(e) = buffer.buffer_write(&processed) {
-                            eprintln!("Input callback error: {}", e);
-                        }
+                let voice = mapper.map(data);
+                // Held until the mix is done - the analysis meters the final signal
+                let mut mod_unit = match modulation_unit.as_ref().map(|unit| unit.lock()) {
+                    Some(Ok(mod_unit)) => Some(mod_unit),
+                    Some(Err(poisoned)) => {
+                        eprintln!("Modulation unit lock poisoned: {}", poisoned);
+                        None
                     }
+                    None => None,
+                };
+                let mut processed = match mod_unit {
+                    Some(ref mut mod_unit) => mod_unit.process(&voice),
+                    None => voice.clone(),
+                };
+
+                // The bed is ducked by the voice alone, so it goes in before the clips
+                if let Some(Ok(mut bed)) = music_bed.as_ref().map(|bed| bed.try_lock()) {
+                    bed.mix_into(&mut processed);
                 }
-            },
-            error_callback,
-            None,
-        )?;
+                // Clips go in after the modulation chain, so recordings and replays hear them too
+                if let Some(Ok(mut mixer)) = soundboard.as_ref().map(|mixer| mixer.try_lock()) {
+                    mixer.mix_into(&mut processed);
+                }
+                if let Some(ref mut mod_unit) = mod_unit {
+                    mod_unit.send_spectrum(&voice, &processed);
+                }
+                drop(mod_unit);
 
-        let output_stream = output_device.get_device().build_output_stream(
-            output_device.get_config(),
-            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                if let Ok(mut buffer) = buffer_output.lock() {
-                    if let Err(e) = buffer.buffer_read(data, input_channels, output_channels) {
-                        eprintln!("Output callback error: {}", e);
-                    }
+                if let Some(ref mut taps) = recording_taps {
+                    taps.push(&voice, &processed);
+                }
+                if let Some(ref mut replay) = replay_feed {
+                    replay.push(&processed);
+                }
+                let block_seconds = data.len() as f64 / input_channels.max(1) as f64 / input_rate as f64;
+                for feed in feeds.iter_mut() {
+                    feed.write(&processed, block_seconds);
                 }
             },
             error_callback,
             None,
         )?;
 
+        let mut output_streams = Vec::with_capacity(outputs.len());
+        for (output, buffer) in outputs.iter().zip(&audio_buffers) {
+            let buffer_output = Arc::clone(buffer);
+            let level = Arc::clone(&output.level);
+            let gate = output.gate.as_ref().map(Arc::clone);
+            let output_channels = output.device.get_config().channels as usize;
+            let mut fader = OutputFader::new(
+                output.device.get_config().sample_rate.0,
+                output_channels,
+                level.target_gain(gate.as_deref()),
+            );
+
+            let output_stream = output.device.get_device().build_output_stream(
+                output.device.get_config(),
+                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                    if let Ok(mut buffer) = buffer_output.lock() {
+                        if let Err(e) = buffer.buffer_read(data, channels, output_channels) {
+                            eprintln!("Output callback error: {}", e);
+                        }
+                    }
+                    fader.process(data, level.target_gain(gate.as_deref()));
+                },
+                error_callback,
+                None,
+            )?;
+            output_streams.push(output_stream);
+        }
+
         Ok(AudioStreams {
-            audio_buffer,
+            audio_buffers,
             input_stream,
-            output_stream,
-            recording_buffer,
-            record_sample_rate,
-            record_channels,
+            output_streams,
+            drift_ppm,
+            recording,
         })
     }
 
@@ -113,7 +172,9 @@ impl AudioStreams {
     }
 
     pub fn start_output_stream(&self) -> anyhow::Result<()> {
-        self.output_stream.play()?;
+        for output_stream in &self.output_streams {
+            output_stream.play()?;
+        }
         Ok(())
     }
 
@@ -122,26 +183,94 @@ impl AudioStreams {
         Ok(())
     }
 
-    pub fn stop_output_stream(&self, file_save_path: Option<String>) -> anyhow::Result<()> {
-        self.output_stream.pause()?;
-        if let Some(recording) = &self.recording_buffer {
-            if let Ok(recording) = recording.lock() {
-                save_audio_buffer_to_file(
-                    &recording,
-                    self.record_sample_rate,
-                    self.record_channels,
-                    file_save_path
-                )?;
-            }
+    pub fn stop_output_stream(&mut self) -> anyhow::Result<()> {
+        for output_stream in &self.output_streams {
+            output_stream.pause()?;
+        }
+        for recording in self.recording.drain(..) {
+            let path = recording.finish()?;
+            println!("Audio saved to {}", path.display());
         }
         Ok(())
     }
 
-    pub fn get_audio_buffer(&self) -> Arc<Mutex<AudioBuffer>> {
-        Arc::clone(&self.audio_buffer)
+    // Measured clock drift of each output, in the order the outputs were given
+    pub fn get_drift_ppm(&self) -> Vec<f32> {
+        self.drift_ppm
+            .iter()
+            .map(|drift_ppm| f32::from_bits(drift_ppm.load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    pub fn get_audio_buffers(&self) -> Vec<Arc<Mutex<AudioBuffer>>> {
+        self.audio_buffers.clone()
+    }
+}
+
+// Input side of one output - resamples the processed block and keeps the ring at its target fill
+struct OutputFeed {
+    buffer: Arc<Mutex<AudioBuffer>>,
+    resampler: Resampler,
+    drift: DriftController,
+    drift_ppm: Arc<AtomicU32>,
+}
+
+impl OutputFeed {
+    fn write(&mut self, processed: &[f32], block_seconds: f64) {
+        // The fill is read before writing, so it does not swing with the block size
+        if let Ok(buffer) = self.buffer.lock() {
+            let adjust = self.drift.update(buffer.occupied_len(), block_seconds);
+            self.resampler.set_ratio_adjust(adjust);
+        }
+        self.drift_ppm
+            .store((self.drift.drift_ppm() as f32).to_bits(), Ordering::Relaxed);
+
+        // Resampled outside the lock, the output callback only waits for the copy
+        let resampled = self.resampler.process(processed);
+        if let Ok(Err(e)) = self.buffer.lock().map(|mut buffer| buffer.buffer_write(&resampled)) {
+            eprintln!("Input callback error: {}", e);
+        }
     }
 }
 
 fn error_callback(err: cpal::StreamError) {
     eprintln!("Audio stream error: {}", err);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drift_settles_without_overflowing_the_ring() {
+        // 8 kHz mono with a 50 ms ring, output clock 150 ppm fast, 10 ms blocks
+        let rate = 8_000;
+        let latency = 400;
+        let drift = 150e-6;
+        let buffer = Arc::new(Mutex::new(AudioBuffer::new(latency)));
+        let mut feed = OutputFeed {
+            buffer: Arc::clone(&buffer),
+            resampler: Resampler::new(rate, rate, 1),
+            drift: DriftController::new(latency, rate, 1),
+            drift_ppm: Arc::new(AtomicU32::new(0)),
+        };
+
+        let block = vec![0.0; 80];
+        let mut owed = 0.0;
+        let mut read = Vec::new();
+        for _ in 0..30_000 {
+            // 5 minutes
+            feed.write(&block, 0.01);
+            let fill = buffer.lock().unwrap().occupied_len();
+            assert!(fill < latency * 2, "ring overflowed at fill {}", fill);
+
+            owed += 80.0 * (1.0 + drift);
+            read.resize(owed as usize, 0.0);
+            owed -= read.len() as f64;
+            buffer.lock().unwrap().buffer_read(&mut read, 1, 1).unwrap();
+        }
+
+        let ppm = f32::from_bits(feed.drift_ppm.load(Ordering::Relaxed));
+        assert!((ppm - 150.0).abs() < 10.0, "drift {}", ppm);
+    }
+}
@@ -1,17 +1,47 @@
 // App starting point for Tauri applications
 
+use tauri::Manager;
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            // Without a config directory settings fall back to defaults in the temp dir
+            let initialized = app
+                .path()
+                .app_config_dir()
+                .map_err(anyhow::Error::from)
+                .and_then(crate::persistence::init);
+            if let Err(e) = initialized {
+                eprintln!("Config directory unavailable, using defaults: {}", e);
+            }
+            crate::hotkeys::listener::start_listener(Some(app.handle().clone()));
+            if let Err(e) = crate::midi::input::start_listener(Some(app.handle().clone())) {
+                eprintln!("MIDI input unavailable: {}", e);
+            }
+            // A taken port should not keep the app from starting
+            let started = crate::control_server::ControlServerManager::get_instance()
+                .lock()
+                .map_err(|e| anyhow::anyhow!("{}", e))
+                .and_then(|mut control_server| control_server.start());
+            if let Err(e) = started {
+                eprintln!("Control server not started: {}", e);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             super::switches::loopback,
             super::switches::stop_loopback,
             super::switches::throughput,
             super::switches::stop_throughput,
+            super::switches::set_output_gain,
+            super::switches::set_output_muted,
+            super::switches::get_output_levels,
             super::modulation_conf::enable_modulation,
             super::modulation_conf::disable_modulation,
+            super::modulation_conf::is_modulation_active,
             super::devices_lists::get_input_devices_list,
             super::devices_lists::get_output_devices_list,
             super::devices_lists::get_virtual_devices_list,
@@ -19,10 +49,13 @@ pub fn run() {
             super::config_select::set_output_device,
             super::config_select::set_virtual_device,
             super::config_select::set_latency,
+            super::config_select::set_channel_map,
             super::config_getter::get_selected_input_device,
             super::config_getter::get_selected_output_device,
             super::config_getter::get_selected_virtual_input,
             super::config_getter::get_latency,
+            super::config_getter::get_channel_map,
+            super::config_getter::get_input_channel_count,
             super::config_getter::is_loopback_running,
             super::config_getter::is_throughput_running,
             super::visualizer::initialize_audio,
@@ -34,12 +67,58 @@ pub fn run() {
             super::modulation_conf::get_parameters,
             super::modulation_conf::set_auto_tune_scale,
             super::modulation_conf::get_auto_tune_scale,
+            super::modulation_conf::set_vocoder_scale,
+            super::modulation_conf::get_vocoder_scale,
+            super::modulation_conf::set_vocoder_carrier,
+            super::modulation_conf::get_vocoder_carrier,
             super::switches::start_recording,
             super::switches::stop_recording,
             super::switches::set_file_save_path,
             super::switches::is_recording,
             super::switches::get_file_save_path,
-            super::visualizer::is_initialized
+            super::switches::set_recording_settings,
+            super::switches::get_recording_settings,
+            super::switches::set_replay_duration,
+            super::switches::get_replay_duration,
+            super::switches::save_replay,
+            super::visualizer::is_initialized,
+            super::visualizer::set_tuner_reference,
+            super::visualizer::get_tuner_reference,
+            super::visualizer::set_visualizer_settings,
+            super::visualizer::get_visualizer_settings,
+            super::visualizer::get_spectrogram,
+            super::visualizer::reset_meters,
+            super::presets::save_preset,
+            super::presets::load_preset,
+            super::presets::delete_preset,
+            super::presets::get_presets,
+            super::presets::get_current_preset,
+            super::hotkeys::get_hotkey_bindings,
+            super::hotkeys::set_hotkey_binding,
+            super::hotkeys::remove_hotkey_binding,
+            super::control_server::get_control_server_settings,
+            super::control_server::set_control_server_settings,
+            super::control_server::is_control_server_running,
+            super::midi::get_midi_inputs,
+            super::midi::refresh_midi_inputs,
+            super::midi::start_midi_learn,
+            super::midi::cancel_midi_learn,
+            super::midi::get_midi_learn_target,
+            super::midi::get_midi_mappings,
+            super::midi::remove_midi_mapping,
+            super::midi::set_midi_mapping_smoothing,
+            super::modulation_matrix::get_modulation_matrix,
+            super::modulation_matrix::set_modulation_matrix,
+            super::modulation_matrix::add_modulation_route,
+            super::modulation_matrix::remove_modulation_route,
+            super::soundboard::get_soundboard_clips,
+            super::soundboard::set_soundboard_clip,
+            super::soundboard::remove_soundboard_clip,
+            super::soundboard::trigger_soundboard_clip,
+            super::soundboard::stop_soundboard,
+            super::music_bed::get_music_bed_settings,
+            super::music_bed::set_music_bed_settings,
+            super::music_bed::skip_music_bed_track
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,6 +1,7 @@
 // Current settings for the audio controls module
 
 use crate::audio::audio_controls::*;
+use crate::audio::channel_map::ChannelMapSettings;
 
 #[tauri::command]
 pub fn get_selected_input_device() -> Result<String, String> {
@@ -43,6 +44,26 @@ pub fn get_latency() -> Result<f32, String> {
     )
 }
 
+#[tauri::command]
+pub fn get_channel_map() -> Result<ChannelMapSettings, String> {
+    Ok(
+        AudioControls::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_channel_map()
+    )
+}
+
+#[tauri::command]
+pub fn get_input_channel_count() -> Result<usize, String> {
+    Ok(
+        AudioControls::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_input_channel_count()
+    )
+}
+
 #[tauri::command]
 pub fn is_loopback_running() -> Result<bool, String> {
     Ok(
@@ -1,6 +1,7 @@
 // Commands for setting audio devices
 
 use crate::audio::audio_controls::*;
+use crate::audio::channel_map::ChannelMapSettings;
 
 #[tauri::command]
 pub fn set_input_device(device_name: String) -> Result<(), String> {
@@ -36,4 +37,13 @@ pub fn set_latency(latency: f32) -> Result<(), String> {
         .map_err(|e| e.to_string())?
         .set_latency(latency)
         .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_channel_map(settings: ChannelMapSettings) -> Result<(), String> {
+    AudioControls::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_channel_map(settings)
+        .map_err(|e| e.to_string())
 }
\ No newline at end of file
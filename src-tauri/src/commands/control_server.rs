@@ -0,0 +1,32 @@
+// Commands for the local OSC/WebSocket control server
+
+use crate::control_server::{ControlServerManager, ControlServerSettings};
+
+#[tauri::command]
+pub fn get_control_server_settings() -> Result<ControlServerSettings, String> {
+    Ok(
+        ControlServerManager::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_settings()
+    )
+}
+
+#[tauri::command]
+pub fn set_control_server_settings(settings: ControlServerSettings) -> Result<(), String> {
+    ControlServerManager::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_settings(settings)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn is_control_server_running() -> Result<bool, String> {
+    Ok(
+        ControlServerManager::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .is_running()
+    )
+}
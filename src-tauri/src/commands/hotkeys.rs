@@ -0,0 +1,37 @@
+// Commands for global hotkey bindings
+
+use crate::hotkeys::bindings::{HotkeyAction, HotkeyBinding};
+use crate::hotkeys::listener::sync_output_gate;
+use crate::hotkeys::HotkeyManager;
+
+#[tauri::command]
+pub fn get_hotkey_bindings() -> Result<Vec<HotkeyBinding>, String> {
+    Ok(
+        HotkeyManager::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_bindings()
+    )
+}
+
+#[tauri::command]
+pub fn set_hotkey_binding(keys: String, action: HotkeyAction) -> Result<(), String> {
+    let bindings = {
+        let mut manager = HotkeyManager::get_instance().lock().map_err(|e| e.to_string())?;
+        manager.set_binding(&keys, action).map_err(|e| e.to_string())?;
+        manager.get_bindings()
+    };
+    sync_output_gate();
+    HotkeyManager::save(bindings).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_hotkey_binding(keys: String) -> Result<(), String> {
+    let bindings = {
+        let mut manager = HotkeyManager::get_instance().lock().map_err(|e| e.to_string())?;
+        manager.remove_binding(&keys).map_err(|e| e.to_string())?;
+        manager.get_bindings()
+    };
+    sync_output_gate();
+    HotkeyManager::save(bindings).map_err(|e| e.to_string())
+}
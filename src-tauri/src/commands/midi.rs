@@ -0,0 +1,73 @@
+// Commands for MIDI learn and CC mappings
+
+use crate::midi::input::{connect_inputs, get_connected_inputs};
+use crate::midi::mapping::MidiMapping;
+use crate::midi::{LearnTarget, MidiManager};
+
+#[tauri::command]
+pub fn get_midi_inputs() -> Result<Vec<String>, String> {
+    Ok(get_connected_inputs())
+}
+
+// Picks up devices plugged in after startup
+#[tauri::command]
+pub fn refresh_midi_inputs() -> Result<Vec<String>, String> {
+    connect_inputs().map_err(|e| e.to_string())
+}
+
+// The next CC received gets bound to this parameter, reported back as `midi-learned`
+#[tauri::command]
+pub fn start_midi_learn(effect_name: String, parameter_name: String) -> Result<(), String> {
+    MidiManager::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .start_learn(&effect_name, &parameter_name);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn cancel_midi_learn() -> Result<(), String> {
+    MidiManager::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .cancel_learn();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_midi_learn_target() -> Result<Option<LearnTarget>, String> {
+    Ok(
+        MidiManager::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_learn_target()
+    )
+}
+
+#[tauri::command]
+pub fn get_midi_mappings() -> Result<Vec<MidiMapping>, String> {
+    Ok(
+        MidiManager::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_mappings()
+    )
+}
+
+#[tauri::command]
+pub fn remove_midi_mapping(channel: u8, controller: u8) -> Result<(), String> {
+    MidiManager::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove_mapping(channel, controller)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_midi_mapping_smoothing(channel: u8, controller: u8, smoothing_ms: f32) -> Result<(), String> {
+    MidiManager::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_smoothing(channel, controller, smoothing_ms)
+        .map_err(|e| e.to_string())
+}
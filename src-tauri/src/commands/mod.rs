@@ -5,3 +5,9 @@ pub mod devices_lists;
 pub mod config_getter;
 pub mod modulation_conf;
 pub mod visualizer;
+pub mod presets;
+pub mod hotkeys;pub mod control_server;
+pub mod midi;
+pub mod modulation_matrix;
+pub mod soundboard;
+pub mod music_bed;
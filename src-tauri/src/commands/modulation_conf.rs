@@ -29,6 +29,14 @@ pub fn disable_modulation() -> Result<String, String> {
     })
 }
 
+#[tauri::command]
+pub fn is_modulation_active() -> Result<bool, String> {
+    with_audio_controls(|controls| {
+        let active = controls.is_modulation_active();
+        Ok(active)
+    })
+}
+
 #[tauri::command]
 pub fn append_effect(effect_name: &str) -> Result<String, String> {
     with_audio_controls(|controls| {
@@ -73,6 +81,40 @@ pub fn get_auto_tune_scale() -> Result<Option<crate::dsp::modules::effects::auto
     })
 }
 
+// Scale the pitch-tracking carrier snaps to when `quantize` is on
+#[tauri::command]
+pub fn set_vocoder_scale(scale: crate::dsp::modules::effects::auto_tune::Scale) -> Result<String, String> {
+    with_audio_controls(|controls| {
+        controls.set_vocoder_scale(scale)?;
+        Ok("Vocoder scale set successfully".to_string())
+    })
+}
+
+#[tauri::command]
+pub fn get_vocoder_scale() -> Result<Option<crate::dsp::modules::effects::auto_tune::Scale>, String> {
+    with_audio_controls(|controls| {
+        let scale = controls.get_vocoder_scale();
+        Ok(scale)
+    })
+}
+
+// Carrier: oscillators, a WAV file loop, a second input device or noise
+#[tauri::command]
+pub fn set_vocoder_carrier(source: crate::dsp::modules::carrier::CarrierSource) -> Result<String, String> {
+    with_audio_controls(|controls| {
+        controls.set_vocoder_carrier(source)?;
+        Ok("Vocoder carrier set successfully".to_string())
+    })
+}
+
+#[tauri::command]
+pub fn get_vocoder_carrier() -> Result<Option<crate::dsp::modules::carrier::CarrierSource>, String> {
+    with_audio_controls(|controls| {
+        let source = controls.get_vocoder_carrier();
+        Ok(source)
+    })
+}
+
 #[tauri::command]
 pub fn get_parameters(effect_name: &str) -> Result<Vec<crate::dsp::modules::utils::EffectParameter>, String> {
     with_audio_controls(|controls| {
@@ -0,0 +1,41 @@
+// Commands for the chain-level modulation matrix (LFOs, envelope follower, pitch)
+
+use crate::audio::audio_controls::*;
+use crate::dsp::modules::modulation_matrix::{ModulationMatrixSettings, ModulationRoute};
+
+#[tauri::command]
+pub fn get_modulation_matrix() -> Result<ModulationMatrixSettings, String> {
+    Ok(
+        AudioControls::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_modulation_matrix()
+    )
+}
+
+#[tauri::command]
+pub fn set_modulation_matrix(settings: ModulationMatrixSettings) -> Result<(), String> {
+    AudioControls::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_modulation_matrix(settings)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn add_modulation_route(route: ModulationRoute) -> Result<(), String> {
+    AudioControls::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .add_modulation_route(route)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_modulation_route(index: usize) -> Result<(), String> {
+    AudioControls::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove_modulation_route(index)
+        .map_err(|e| e.to_string())
+}
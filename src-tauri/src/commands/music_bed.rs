@@ -0,0 +1,31 @@
+// Commands for the music bed
+
+use crate::music_bed::{MusicBedManager, MusicBedSettings};
+
+#[tauri::command]
+pub fn get_music_bed_settings() -> Result<MusicBedSettings, String> {
+    Ok(
+        MusicBedManager::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_settings()
+    )
+}
+
+#[tauri::command]
+pub fn set_music_bed_settings(settings: MusicBedSettings) -> Result<(), String> {
+    MusicBedManager::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_settings(settings)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn skip_music_bed_track() -> Result<(), String> {
+    MusicBedManager::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .skip_track()
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,48 @@
+use crate::audio::audio_controls::*;
+
+fn with_audio_controls<F, R>(operation: F) -> Result<R, String>
+where
+    F: FnOnce(&mut AudioControls) -> anyhow::Result<R>,
+{
+    match AudioControls::get_instance().lock() {
+        Ok(mut audio_controls) => {
+            operation(&mut audio_controls)
+                .map_err(|e| format!("Audio operation failed: {}", e))
+        }
+        Err(e) => Err(format!("Failed to acquire audio controls lock: {}", e))
+    }
+}
+
+#[tauri::command]
+pub fn save_preset(name: &str) -> Result<String, String> {
+    with_audio_controls(|controls| {
+        controls.save_preset(name)?;
+        Ok(format!("Preset '{}' saved successfully", name))
+    })
+}
+
+#[tauri::command]
+pub fn load_preset(name: &str) -> Result<String, String> {
+    with_audio_controls(|controls| {
+        controls.load_preset(name)?;
+        Ok(format!("Preset '{}' loaded successfully", name))
+    })
+}
+
+#[tauri::command]
+pub fn delete_preset(name: &str) -> Result<String, String> {
+    with_audio_controls(|controls| {
+        controls.delete_preset(name)?;
+        Ok(format!("Preset '{}' deleted successfully", name))
+    })
+}
+
+#[tauri::command]
+pub fn get_presets() -> Result<Vec<String>, String> {
+    with_audio_controls(|controls| Ok(controls.get_presets()))
+}
+
+#[tauri::command]
+pub fn get_current_preset() -> Result<Option<String>, String> {
+    with_audio_controls(|controls| Ok(controls.get_current_preset()))
+}
@@ -0,0 +1,49 @@
+// Commands for the soundboard
+
+use crate::soundboard::{SoundboardClip, SoundboardManager};
+
+#[tauri::command]
+pub fn get_soundboard_clips() -> Result<Vec<SoundboardClip>, String> {
+    Ok(
+        SoundboardManager::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_clips()
+    )
+}
+
+#[tauri::command]
+pub fn set_soundboard_clip(clip: SoundboardClip) -> Result<(), String> {
+    SoundboardManager::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_clip(clip)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_soundboard_clip(name: String) -> Result<(), String> {
+    SoundboardManager::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove_clip(&name)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn trigger_soundboard_clip(name: String) -> Result<(), String> {
+    SoundboardManager::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .trigger(&name)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn stop_soundboard() -> Result<(), String> {
+    SoundboardManager::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .stop_all()
+        .map_err(|e| e.to_string())
+}
@@ -1,4 +1,6 @@
 use crate::audio::audio_controls::*;
+use crate::audio::output::{OutputKind, OutputLevelInfo};
+use crate::audio::recorder::RecordingSettings;
 
 
 fn with_audio_controls<F, R>(operation: F) -> Result<R, String>
@@ -47,6 +49,27 @@ pub fn stop_throughput() -> Result<String, String> {
     })
 }
 
+#[tauri::command]
+pub fn set_output_gain(output: OutputKind, gain: f32) -> Result<String, String> {
+    with_audio_controls(|controls| {
+        controls.set_output_gain(output, gain)?;
+        Ok("Output gain set successfully".to_string())
+    })
+}
+
+#[tauri::command]
+pub fn set_output_muted(output: OutputKind, muted: bool) -> Result<String, String> {
+    with_audio_controls(|controls| {
+        controls.set_output_muted(output, muted);
+        Ok("Output mute set successfully".to_string())
+    })
+}
+
+#[tauri::command]
+pub fn get_output_levels() -> Result<Vec<OutputLevelInfo>, String> {
+    with_audio_controls(|controls| Ok(controls.get_output_levels()))
+}
+
 #[tauri::command]
 pub fn start_recording() -> Result<String, String> {
     with_audio_controls(|controls| {
@@ -86,4 +109,40 @@ pub fn get_file_save_path() -> Result<Option<String>, String> {
         Ok(path)
     })
 }
-    
\ No newline at end of file
+
+#[tauri::command]
+pub fn set_recording_settings(settings: RecordingSettings) -> Result<String, String> {
+    with_audio_controls(|controls| {
+        controls.set_recording_settings(settings)?;
+        Ok("Recording settings updated successfully".to_string())
+    })
+}
+
+#[tauri::command]
+pub fn get_recording_settings() -> Result<RecordingSettings, String> {
+    with_audio_controls(|controls| {
+        let settings = controls.get_recording_settings();
+        Ok(settings)
+    })
+}
+
+#[tauri::command]
+pub fn set_replay_duration(seconds: f32) -> Result<String, String> {
+    with_audio_controls(|controls| {
+        controls.set_replay_duration(seconds)?;
+        Ok("Replay duration set successfully".to_string())
+    })
+}
+
+#[tauri::command]
+pub fn get_replay_duration() -> Result<f32, String> {
+    with_audio_controls(|controls| {
+        let seconds = controls.get_replay_duration();
+        Ok(seconds)
+    })
+}
+
+#[tauri::command]
+pub fn save_replay() -> Result<String, String> {
+    with_audio_controls(|controls| controls.save_replay())
+}
@@ -1,4 +1,6 @@
 use crate::audio::audio_controls::*;
+use crate::dsp::modules::visualizer::audio_spectrum::SpectrogramSnapshot;
+use crate::dsp::modules::visualizer::settings::VisualizerSettings;
 use tauri::AppHandle;
 
 #[tauri::command]
@@ -25,4 +27,60 @@ pub fn is_initialized() -> bool {
         .lock()
         .map(|controls| controls.is_initialized())
         .unwrap_or(false)
+}
+
+#[tauri::command]
+pub fn set_tuner_reference(reference: f32) -> Result<(), String> {
+    AudioControls::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_tuner_reference(reference)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_tuner_reference() -> Result<f32, String> {
+    Ok(
+        AudioControls::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_tuner_reference()
+    )
+}
+
+#[tauri::command]
+pub fn set_visualizer_settings(settings: VisualizerSettings) -> Result<(), String> {
+    AudioControls::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .set_visualizer_settings(settings)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_visualizer_settings() -> Result<VisualizerSettings, String> {
+    Ok(
+        AudioControls::get_instance()
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_visualizer_settings()
+    )
+}
+
+#[tauri::command]
+pub fn get_spectrogram() -> Result<SpectrogramSnapshot, String> {
+    AudioControls::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get_spectrogram()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn reset_meters() -> Result<(), String> {
+    AudioControls::get_instance()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .reset_meters()
+        .map_err(|e| e.to_string())
 }
\ No newline at end of file
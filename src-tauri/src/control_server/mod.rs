@@ -0,0 +1,425 @@
+// Local control server - lets stream decks, OSC surfaces and scripts drive the app.
+// OSC over UDP and JSON over WebSocket share one request set (`protocol`) and both
+// get the current state pushed whenever it changes.
+
+pub mod osc;
+pub mod protocol;
+pub mod target;
+mod websocket;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::net::{SocketAddr, TcpListener, UdpSocket};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::persistence;
+use protocol::{ControlRequest, ControlState, ServerMessage};
+use target::{AudioControlsTarget, ControlTarget};
+
+const CONTROL_SERVER_FILE: &str = "control_server.json";
+/// Socket timeouts - bounds how long stopping the server takes.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How often state is compared against the last push, catches changes made from the UI or hotkeys.
+const STATE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// OSC peers remembered for state pushes, oldest dropped first.
+const MAX_OSC_PEERS: usize = 16;
+/// WebSocket clients served at once, further connections are closed right away.
+const MAX_WEBSOCKET_CLIENTS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlServerSettings {
+    pub enabled: bool,
+    // Loopback by default - nothing on the network can reach it. Web pages open in a local
+    // browser could, so WebSocket handshakes carrying an `Origin` header are refused
+    pub bind_address: String,
+    pub osc_port: u16,
+    pub websocket_port: u16,
+}
+
+impl Default for ControlServerSettings {
+    fn default() -> Self {
+        ControlServerSettings {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            osc_port: 9000,
+            websocket_port: 9001,
+        }
+    }
+}
+
+// Shared by the transport threads - runs requests and fans state out to every client
+struct ControlHub {
+    target: Arc<dyn ControlTarget>,
+    last_state: Mutex<Option<ControlState>>,
+    websocket_clients: Mutex<Vec<Sender<String>>>,
+    osc_socket: UdpSocket,
+    osc_peers: Mutex<Vec<SocketAddr>>,
+}
+
+impl ControlHub {
+    fn handle(&self, request: &ControlRequest) -> anyhow::Result<()> {
+        self.target.execute(request)?;
+        self.publish_state();
+        Ok(())
+    }
+
+    fn state(&self) -> anyhow::Result<ControlState> {
+        self.target.state()
+    }
+
+    fn add_websocket_client(&self, sender: Sender<String>) {
+        if let Ok(mut clients) = self.websocket_clients.lock() {
+            clients.push(sender);
+        }
+    }
+
+    fn add_osc_peer(&self, peer: SocketAddr) {
+        if let Ok(mut peers) = self.osc_peers.lock() {
+            if peers.contains(&peer) {
+                return;
+            }
+            if peers.len() == MAX_OSC_PEERS {
+                peers.remove(0);
+            }
+            peers.push(peer);
+        }
+    }
+
+    fn send_osc_state(&self, peer: SocketAddr) -> anyhow::Result<()> {
+        for message in osc::state_to_messages(&self.state()?) {
+            self.osc_socket.send_to(&message.encode(), peer)?;
+        }
+        Ok(())
+    }
+
+    // Pushes the state to all clients if it differs from the last push
+    fn publish_state(&self) {
+        let state = match self.state() {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("Control server could not read state: {}", e);
+                return;
+            }
+        };
+        {
+            let mut last_state = match self.last_state.lock() {
+                Ok(last_state) => last_state,
+                Err(_) => return,
+            };
+            if last_state.as_ref() == Some(&state) {
+                return;
+            }
+            *last_state = Some(state.clone());
+        }
+
+        let json = serde_json::to_string(&ServerMessage::State(state.clone())).unwrap_or_default();
+        if let Ok(mut clients) = self.websocket_clients.lock() {
+            // Send fails once the client thread has ended
+            clients.retain(|client| client.send(json.clone()).is_ok());
+        }
+
+        let packets: Vec<Vec<u8>> = osc::state_to_messages(&state).iter().map(|m| m.encode()).collect();
+        if let Ok(peers) = self.osc_peers.lock() {
+            for peer in peers.iter() {
+                for packet in &packets {
+                    let _ = self.osc_socket.send_to(packet, peer);
+                }
+            }
+        }
+    }
+}
+
+pub struct ControlServer {
+    osc_address: SocketAddr,
+    websocket_address: SocketAddr,
+    control: Arc<Mutex<bool>>, // true = run, false = stop
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl ControlServer {
+    pub fn start(settings: &ControlServerSettings, target: Arc<dyn ControlTarget>) -> anyhow::Result<Self> {
+        let osc_socket = UdpSocket::bind((settings.bind_address.as_str(), settings.osc_port))
+            .map_err(|e| anyhow::anyhow!("Cannot bind OSC port {}: {}", settings.osc_port, e))?;
+        osc_socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        let listener = TcpListener::bind((settings.bind_address.as_str(), settings.websocket_port))
+            .map_err(|e| anyhow::anyhow!("Cannot bind WebSocket port {}: {}", settings.websocket_port, e))?;
+        listener.set_nonblocking(true)?;
+
+        let osc_address = osc_socket.local_addr()?;
+        let websocket_address = listener.local_addr()?;
+
+        let hub = Arc::new(ControlHub {
+            target,
+            last_state: Mutex::new(None),
+            websocket_clients: Mutex::new(Vec::new()),
+            osc_socket: osc_socket.try_clone()?,
+            osc_peers: Mutex::new(Vec::new()),
+        });
+        let control = Arc::new(Mutex::new(true));
+
+        let mut handles = Vec::new();
+        {
+            let (hub, control) = (hub.clone(), control.clone());
+            handles.push(thread::spawn(move || osc::serve(osc_socket, hub, control)));
+        }
+        {
+            let (hub, control) = (hub.clone(), control.clone());
+            handles.push(thread::spawn(move || websocket::serve(listener, hub, control)));
+        }
+        {
+            let control = control.clone();
+            handles.push(thread::spawn(move || {
+                let mut last_poll = Instant::now();
+                hub.publish_state();
+                while *control.lock().unwrap() {
+                    thread::sleep(POLL_INTERVAL);
+                    if last_poll.elapsed() >= STATE_POLL_INTERVAL {
+                        hub.publish_state();
+                        last_poll = Instant::now();
+                    }
+                }
+            }));
+        }
+
+        Ok(ControlServer {
+            osc_address,
+            websocket_address,
+            control,
+            handles,
+        })
+    }
+
+    // Actual bound addresses - differ from the settings when port 0 was requested
+    pub fn osc_address(&self) -> SocketAddr {
+        self.osc_address
+    }
+
+    pub fn websocket_address(&self) -> SocketAddr {
+        self.websocket_address
+    }
+
+    pub fn stop(&mut self) {
+        if let Ok(mut control) = self.control.lock() {
+            *control = false;
+        }
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+pub struct ControlServerManager {
+    settings: ControlServerSettings,
+    server: Option<ControlServer>,
+}
+
+static CONTROL_SERVER_MANAGER: OnceCell<Mutex<ControlServerManager>> = OnceCell::new();
+
+impl ControlServerManager {
+    fn new() -> Self {
+        ControlServerManager {
+            settings: persistence::load(CONTROL_SERVER_FILE),
+            server: None,
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<ControlServerManager> {
+        CONTROL_SERVER_MANAGER.get_or_init(|| Mutex::new(ControlServerManager::new()))
+    }
+
+    pub fn get_settings(&self) -> ControlServerSettings {
+        self.settings.clone()
+    }
+
+    // Restarts the server with the new settings, they are only saved if it comes up.
+    // The old server has to release its ports first, so it is brought back if the new one fails
+    pub fn set_settings(&mut self, settings: ControlServerSettings) -> anyhow::Result<()> {
+        let was_running = self.is_running();
+        self.stop();
+        if settings.enabled {
+            match ControlServer::start(&settings, Arc::new(AudioControlsTarget)) {
+                Ok(server) => self.server = Some(server),
+                Err(e) => {
+                    if let Some(Err(restart_error)) = was_running.then(|| self.start()) {
+                        eprintln!("Control server could not be restarted: {}", restart_error);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        persistence::save(CONTROL_SERVER_FILE, &settings)?;
+        self.settings = settings;
+        Ok(())
+    }
+
+    // Starts the server if the saved settings have it enabled
+    pub fn start(&mut self) -> anyhow::Result<()> {
+        if self.settings.enabled && self.server.is_none() {
+            self.server = Some(ControlServer::start(&self.settings, Arc::new(AudioControlsTarget))?);
+        }
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut server) = self.server.take() {
+            server.stop();
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.server.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::osc::{OscArg, OscMessage};
+    use super::protocol::EffectState;
+    use super::*;
+    use std::net::TcpStream;
+
+    struct MockTarget {
+        state: Mutex<ControlState>,
+    }
+
+    impl ControlTarget for MockTarget {
+        fn execute(&self, request: &ControlRequest) -> anyhow::Result<()> {
+            let mut state = self.state.lock().unwrap();
+            match request {
+                ControlRequest::EnableModulation => state.modulation_active = true,
+                ControlRequest::AppendEffect { effect_name } => state.effects.push(EffectState {
+                    name: effect_name.clone(),
+                    parameters: Vec::new(),
+                }),
+                ControlRequest::GetState => {}
+                _ => return Err(anyhow::anyhow!("Unsupported in mock")),
+            }
+            Ok(())
+        }
+
+        fn state(&self) -> anyhow::Result<ControlState> {
+            Ok(self.state.lock().unwrap().clone())
+        }
+    }
+
+    // Skips messages until one matches - state pushes can arrive more than once
+    fn wait_for(
+        socket: &mut tungstenite::WebSocket<TcpStream>,
+        matches: impl Fn(&ServerMessage) -> bool,
+    ) -> ServerMessage {
+        loop {
+            if let tungstenite::Message::Text(text) = socket.read().unwrap() {
+                let message = serde_json::from_str(text.as_str()).unwrap();
+                if matches(&message) {
+                    return message;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn requests_from_one_transport_reach_the_other() {
+        let settings = ControlServerSettings {
+            enabled: true,
+            osc_port: 0,
+            websocket_port: 0,
+            ..Default::default()
+        };
+        let target = Arc::new(MockTarget {
+            state: Mutex::new(ControlState {
+                modulation_active: false,
+                effects: Vec::new(),
+                presets: vec!["Robot".to_string()],
+                current_preset: None,
+            }),
+        });
+        let mut server = ControlServer::start(&settings, target).unwrap();
+
+        let stream = TcpStream::connect(server.websocket_address()).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let url = format!("ws://{}", server.websocket_address());
+        let (mut websocket, _) = tungstenite::client(url.as_str(), stream).unwrap();
+        wait_for(&mut websocket, |m| matches!(m, ServerMessage::State(state) if !state.modulation_active));
+
+        // OSC change is pushed to the WebSocket client
+        let osc = UdpSocket::bind("127.0.0.1:0").unwrap();
+        osc.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let enable = OscMessage::new("/pitchslap/modulation/enable", vec![]);
+        osc.send_to(&enable.encode(), server.osc_address()).unwrap();
+        wait_for(&mut websocket, |m| matches!(m, ServerMessage::State(state) if state.modulation_active));
+
+        // WebSocket change is pushed to the OSC peer
+        websocket
+            .send(tungstenite::Message::text(r#"{"command": "append_effect", "effect_name": "reverb"}"#))
+            .unwrap();
+        let result = wait_for(&mut websocket, |m| matches!(m, ServerMessage::Result { .. }));
+        assert!(matches!(result, ServerMessage::Result { ok: true, .. }));
+        let mut packet = [0u8; osc::MAX_PACKET_SIZE];
+        loop {
+            let (size, _) = osc.recv_from(&mut packet).unwrap();
+            let message = OscMessage::decode(&packet[..size]).unwrap().remove(0);
+            if message.address == "/pitchslap/state/effects"
+                && message.args == vec![OscArg::String("reverb".to_string())]
+            {
+                break;
+            }
+        }
+
+        // Failures are reported back instead of dropping the connection
+        websocket
+            .send(tungstenite::Message::text(r#"{"command": "next_preset"}"#))
+            .unwrap();
+        match wait_for(&mut websocket, |m| matches!(m, ServerMessage::Result { .. })) {
+            ServerMessage::Result { ok, error } => assert!(!ok && error.is_some()),
+            _ => unreachable!(),
+        }
+
+        server.stop();
+    }
+
+    #[test]
+    fn browser_handshakes_are_refused() {
+        use tungstenite::client::IntoClientRequest;
+
+        let settings = ControlServerSettings {
+            enabled: true,
+            osc_port: 0,
+            websocket_port: 0,
+            ..Default::default()
+        };
+        let target = Arc::new(MockTarget {
+            state: Mutex::new(ControlState {
+                modulation_active: false,
+                effects: Vec::new(),
+                presets: Vec::new(),
+                current_preset: None,
+            }),
+        });
+        let mut server = ControlServer::start(&settings, target).unwrap();
+
+        let stream = TcpStream::connect(server.websocket_address()).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut request = format!("ws://{}", server.websocket_address()).into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("Origin", "https://example.com".parse().unwrap());
+        match tungstenite::client(request, stream) {
+            Err(tungstenite::HandshakeError::Failure(tungstenite::Error::Http(response))) => {
+                assert_eq!(response.status(), 403)
+            }
+            other => panic!("handshake was not refused: {:?}", other.map(|_| ())),
+        }
+
+        server.stop();
+    }
+}
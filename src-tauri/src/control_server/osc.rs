@@ -0,0 +1,348 @@
+// Minimal OSC 1.0 codec and the address space of the control server.
+//
+// Incoming addresses:
+//   /pitchslap/modulation/enable
+//   /pitchslap/modulation/disable
+//   /pitchslap/effect/append          s:effect
+//   /pitchslap/effect/remove          s:effect
+//   /pitchslap/effect/parameter       s:effect s:parameter f:value
+//   /pitchslap/effect/<effect>/<parameter>  f:value   (fader friendly form)
+//   /pitchslap/preset/load            s:name
+//   /pitchslap/preset/next
+//   /pitchslap/preset/previous
+//   /pitchslap/state                  (asks for a state push)
+//
+// State is pushed back under /pitchslap/state/...
+
+use std::io::ErrorKind;
+use std::net::UdpSocket;
+use std::sync::{Arc, Mutex};
+
+use super::protocol::{ControlRequest, ControlState};
+use super::ControlHub;
+
+const ADDRESS_ROOT: &str = "/pitchslap";
+/// Largest datagram accepted - well above anything a control surface sends.
+pub(super) const MAX_PACKET_SIZE: usize = 8192;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    String(String),
+    Bool(bool),
+}
+
+impl OscArg {
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            OscArg::Int(value) => Some(*value as f32),
+            OscArg::Float(value) => Some(*value),
+            OscArg::Bool(value) => Some(if *value { 1.0 } else { 0.0 }),
+            OscArg::String(_) => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            OscArg::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+impl OscMessage {
+    pub fn new(address: &str, args: Vec<OscArg>) -> Self {
+        OscMessage {
+            address: address.to_string(),
+            args,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut packet = Vec::new();
+        write_string(&mut packet, &self.address);
+
+        let mut tags = String::from(",");
+        for arg in &self.args {
+            tags.push(match arg {
+                OscArg::Int(_) => 'i',
+                OscArg::Float(_) => 'f',
+                OscArg::String(_) => 's',
+                OscArg::Bool(true) => 'T',
+                OscArg::Bool(false) => 'F',
+            });
+        }
+        write_string(&mut packet, &tags);
+
+        for arg in &self.args {
+            match arg {
+                OscArg::Int(value) => packet.extend_from_slice(&value.to_be_bytes()),
+                OscArg::Float(value) => packet.extend_from_slice(&value.to_be_bytes()),
+                OscArg::String(value) => write_string(&mut packet, value),
+                OscArg::Bool(_) => {}
+            }
+        }
+        packet
+    }
+
+    /// Decodes a packet into its messages - bundles are flattened, time tags ignored.
+    pub fn decode(packet: &[u8]) -> anyhow::Result<Vec<OscMessage>> {
+        let mut reader = Reader { data: packet, pos: 0 };
+        if packet.starts_with(b"#bundle\0") {
+            reader.pos = 16; // "#bundle\0" + 8 byte time tag
+            let mut messages = Vec::new();
+            while reader.pos < packet.len() {
+                let size = reader.read_i32()? as usize;
+                let element = reader.take(size)?;
+                messages.extend(Self::decode(element)?);
+            }
+            return Ok(messages);
+        }
+
+        let address = reader.read_string()?;
+        if !address.starts_with('/') {
+            return Err(anyhow::anyhow!("Invalid OSC address '{}'", address));
+        }
+        // Very old senders omit the type tag string entirely
+        let tags = if reader.pos < packet.len() { reader.read_string()? } else { ",".to_string() };
+
+        let mut args = Vec::new();
+        for tag in tags.chars().skip(1) {
+            match tag {
+                'i' => args.push(OscArg::Int(reader.read_i32()?)),
+                'f' => args.push(OscArg::Float(f32::from_bits(reader.read_i32()? as u32))),
+                'h' => {
+                    let value = i64::from_be_bytes(reader.take(8)?.try_into()?);
+                    args.push(OscArg::Int(value.clamp(i32::MIN as i64, i32::MAX as i64) as i32));
+                }
+                'd' => args.push(OscArg::Float(f64::from_be_bytes(reader.take(8)?.try_into()?) as f32)),
+                's' | 'S' => args.push(OscArg::String(reader.read_string()?)),
+                'T' => args.push(OscArg::Bool(true)),
+                'F' => args.push(OscArg::Bool(false)),
+                'N' | 'I' => {}
+                'b' => {
+                    let size = reader.read_i32()? as usize;
+                    reader.take(size.div_ceil(4) * 4)?;
+                }
+                other => return Err(anyhow::anyhow!("Unsupported OSC type tag '{}'", other)),
+            }
+        }
+
+        Ok(vec![OscMessage { address, args }])
+    }
+}
+
+fn write_string(packet: &mut Vec<u8>, value: &str) {
+    packet.extend_from_slice(value.as_bytes());
+    // Null terminated and padded to a multiple of 4 bytes
+    let padding = 4 - value.len() % 4;
+    packet.extend(std::iter::repeat_n(0u8, padding));
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, size: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(size)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| anyhow::anyhow!("Truncated OSC packet"))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn read_i32(&mut self) -> anyhow::Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn read_string(&mut self) -> anyhow::Result<String> {
+        let rest = &self.data[self.pos..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| anyhow::anyhow!("Unterminated OSC string"))?;
+        let value = String::from_utf8(rest[..len].to_vec())?;
+        self.take((len / 4 + 1) * 4)?;
+        Ok(value)
+    }
+}
+
+fn string_arg(message: &OscMessage, index: usize) -> anyhow::Result<String> {
+    message
+        .args
+        .get(index)
+        .and_then(OscArg::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("{} expects a string argument at position {}", message.address, index))
+}
+
+fn float_arg(message: &OscMessage, index: usize) -> anyhow::Result<f32> {
+    message
+        .args
+        .get(index)
+        .and_then(OscArg::as_f32)
+        .ok_or_else(|| anyhow::anyhow!("{} expects a numeric argument at position {}", message.address, index))
+}
+
+pub fn request_from_message(message: &OscMessage) -> anyhow::Result<ControlRequest> {
+    let path = message
+        .address
+        .strip_prefix(ADDRESS_ROOT)
+        .ok_or_else(|| anyhow::anyhow!("Unknown OSC address '{}'", message.address))?;
+    let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+
+    match parts.as_slice() {
+        ["modulation", "enable"] => Ok(ControlRequest::EnableModulation),
+        ["modulation", "disable"] => Ok(ControlRequest::DisableModulation),
+        ["effect", "append"] => Ok(ControlRequest::AppendEffect {
+            effect_name: string_arg(message, 0)?,
+        }),
+        ["effect", "remove"] => Ok(ControlRequest::RemoveEffect {
+            effect_name: string_arg(message, 0)?,
+        }),
+        ["effect", "parameter"] => Ok(ControlRequest::SetEffectParameter {
+            effect_name: string_arg(message, 0)?,
+            parameter_name: string_arg(message, 1)?,
+            value: float_arg(message, 2)?,
+        }),
+        ["effect", effect_name, parameter_name] => Ok(ControlRequest::SetEffectParameter {
+            effect_name: effect_name.to_string(),
+            parameter_name: parameter_name.to_string(),
+            value: float_arg(message, 0)?,
+        }),
+        ["preset", "load"] => Ok(ControlRequest::LoadPreset {
+            name: string_arg(message, 0)?,
+        }),
+        ["preset", "next"] => Ok(ControlRequest::NextPreset),
+        ["preset", "previous"] => Ok(ControlRequest::PreviousPreset),
+        ["state"] => Ok(ControlRequest::GetState),
+        _ => Err(anyhow::anyhow!("Unknown OSC address '{}'", message.address)),
+    }
+}
+
+pub fn state_to_messages(state: &ControlState) -> Vec<OscMessage> {
+    let mut messages = vec![
+        OscMessage::new(
+            "/pitchslap/state/modulation",
+            vec![OscArg::Int(state.modulation_active as i32)],
+        ),
+        OscMessage::new(
+            "/pitchslap/state/effects",
+            state.effects.iter().map(|effect| OscArg::String(effect.name.clone())).collect(),
+        ),
+        OscMessage::new(
+            "/pitchslap/state/presets",
+            state.presets.iter().map(|name| OscArg::String(name.clone())).collect(),
+        ),
+        OscMessage::new(
+            "/pitchslap/state/preset",
+            vec![OscArg::String(state.current_preset.clone().unwrap_or_default())],
+        ),
+    ];
+
+    // Same address scheme as the fader form of the parameter setter, so feedback lands on the right control
+    for effect in &state.effects {
+        for parameter in &effect.parameters {
+            messages.push(OscMessage::new(
+                &format!("/pitchslap/state/effect/{}/{}", effect.name, parameter.name),
+                vec![
+                    OscArg::Float(parameter.value),
+                    OscArg::Float(parameter.get_normalized()),
+                ],
+            ));
+        }
+    }
+    messages
+}
+
+pub fn error_message(error: &str) -> OscMessage {
+    OscMessage::new("/pitchslap/error", vec![OscArg::String(error.to_string())])
+}
+
+// Every sender is remembered as a peer and gets state pushes from then on
+pub(super) fn serve(socket: UdpSocket, hub: Arc<ControlHub>, control: Arc<Mutex<bool>>) {
+    let mut packet = [0u8; MAX_PACKET_SIZE];
+
+    while *control.lock().unwrap() {
+        let (size, peer) = match socket.recv_from(&mut packet) {
+            Ok(received) => received,
+            Err(ref e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => {
+                eprintln!("OSC receive failed: {}", e);
+                continue;
+            }
+        };
+        hub.add_osc_peer(peer);
+
+        let result = OscMessage::decode(&packet[..size]).and_then(|messages| {
+            for message in messages {
+                let request = request_from_message(&message)?;
+                hub.handle(&request)?;
+                if request == ControlRequest::GetState {
+                    hub.send_osc_state(peer)?;
+                }
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            let _ = socket.send_to(&error_message(&e.to_string()).encode(), peer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_round_trip() {
+        let message = OscMessage::new(
+            "/pitchslap/effect/parameter",
+            vec![
+                OscArg::String("reverb".to_string()),
+                OscArg::String("mix".to_string()),
+                OscArg::Float(0.25),
+                OscArg::Int(-3),
+                OscArg::Bool(true),
+            ],
+        );
+        let packet = message.encode();
+        assert_eq!(packet.len() % 4, 0);
+        assert_eq!(OscMessage::decode(&packet).unwrap(), vec![message]);
+
+        assert!(OscMessage::decode(&packet[..packet.len() - 6]).is_err());
+    }
+
+    #[test]
+    fn addresses_map_to_requests() {
+        let fader = OscMessage::new("/pitchslap/effect/chorus/depth", vec![OscArg::Int(1)]);
+        assert_eq!(
+            request_from_message(&fader).unwrap(),
+            ControlRequest::SetEffectParameter {
+                effect_name: "chorus".to_string(),
+                parameter_name: "depth".to_string(),
+                value: 1.0,
+            }
+        );
+
+        let next = OscMessage::new("/pitchslap/preset/next", vec![]);
+        assert_eq!(request_from_message(&next).unwrap(), ControlRequest::NextPreset);
+
+        let missing = OscMessage::new("/pitchslap/effect/append", vec![]);
+        assert!(request_from_message(&missing).is_err());
+        let foreign = OscMessage::new("/other/state", vec![]);
+        assert!(request_from_message(&foreign).is_err());
+    }
+}
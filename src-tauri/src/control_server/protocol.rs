@@ -0,0 +1,43 @@
+// Messages shared by the OSC and WebSocket transports
+
+use serde::{Deserialize, Serialize};
+
+use crate::dsp::modules::utils::EffectParameter;
+
+/// Requests mirroring the `AudioControls` API. Sent as JSON over WebSocket,
+/// e.g. `{"command": "set_effect_parameter", "effect_name": "reverb", "parameter_name": "mix", "value": 0.4}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlRequest {
+    EnableModulation,
+    DisableModulation,
+    AppendEffect { effect_name: String },
+    RemoveEffect { effect_name: String },
+    SetEffectParameter { effect_name: String, parameter_name: String, value: f32 },
+    LoadPreset { name: String },
+    NextPreset,
+    PreviousPreset,
+    GetState,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectState {
+    pub name: String,
+    pub parameters: Vec<EffectParameter>,
+}
+
+/// Snapshot pushed to every client whenever something changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlState {
+    pub modulation_active: bool,
+    pub effects: Vec<EffectState>, // in chain order
+    pub presets: Vec<String>,
+    pub current_preset: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Result { ok: bool, error: Option<String> },
+    State(ControlState),
+}
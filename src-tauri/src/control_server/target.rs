@@ -0,0 +1,64 @@
+// What the control server drives - the app's `AudioControls`, or a stand-in in tests
+
+use super::protocol::{ControlRequest, ControlState, EffectState};
+use crate::audio::audio_controls::AudioControls;
+use crate::dsp::modules::utils::ParameterValue;
+
+pub trait ControlTarget: Send + Sync + 'static {
+    fn execute(&self, request: &ControlRequest) -> anyhow::Result<()>;
+    fn state(&self) -> anyhow::Result<ControlState>;
+}
+
+pub struct AudioControlsTarget;
+
+impl AudioControlsTarget {
+    fn with_controls<R>(
+        operation: impl FnOnce(&mut AudioControls) -> anyhow::Result<R>,
+    ) -> anyhow::Result<R> {
+        let mut controls = AudioControls::get_instance()
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire audio controls lock: {}", e))?;
+        operation(&mut controls)
+    }
+}
+
+impl ControlTarget for AudioControlsTarget {
+    fn execute(&self, request: &ControlRequest) -> anyhow::Result<()> {
+        Self::with_controls(|controls| match request {
+            ControlRequest::EnableModulation => controls.enable_modulation(),
+            ControlRequest::DisableModulation => controls.disable_modulation(),
+            ControlRequest::AppendEffect { effect_name } => controls.append_effect(effect_name),
+            ControlRequest::RemoveEffect { effect_name } => controls.remove_effect(effect_name),
+            // Faders send a stream of these - applied live, never through an engine restart
+            ControlRequest::SetEffectParameter { effect_name, parameter_name, value } => {
+                controls.set_effect_parameter(
+                    effect_name,
+                    ParameterValue {
+                        name: parameter_name.clone(),
+                        value: *value,
+                    },
+                )
+            }
+            ControlRequest::LoadPreset { name } => controls.load_preset(name),
+            ControlRequest::NextPreset => controls.next_preset(),
+            ControlRequest::PreviousPreset => controls.previous_preset(),
+            ControlRequest::GetState => Ok(()),
+        })
+    }
+
+    fn state(&self) -> anyhow::Result<ControlState> {
+        Self::with_controls(|controls| {
+            let mut effects = Vec::new();
+            for name in controls.get_active_effects() {
+                let parameters = controls.get_parameters(&name)?;
+                effects.push(EffectState { name, parameters });
+            }
+            Ok(ControlState {
+                modulation_active: controls.is_modulation_active(),
+                effects,
+                presets: controls.get_presets(),
+                current_preset: controls.get_current_preset(),
+            })
+        })
+    }
+}
@@ -0,0 +1,123 @@
+// WebSocket transport - JSON `ControlRequest`s in, `ServerMessage`s out.
+// One thread per client, up to `MAX_WEBSOCKET_CLIENTS`; reads time out regularly so pushed
+// state can be written in between. Browsers always send an `Origin` header and native
+// clients do not, so handshakes carrying one are refused - a web page can not drive the app.
+
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::{Message, WebSocket};
+
+use super::protocol::{ControlRequest, ServerMessage};
+use super::{ControlHub, MAX_WEBSOCKET_CLIENTS, POLL_INTERVAL};
+
+pub(super) fn serve(listener: TcpListener, hub: Arc<ControlHub>, control: Arc<Mutex<bool>>) {
+    let clients = Arc::new(AtomicUsize::new(0));
+    while *control.lock().unwrap() {
+        match listener.accept() {
+            // Dropping the stream closes the connection
+            Ok(_) if clients.load(Ordering::Acquire) >= MAX_WEBSOCKET_CLIENTS => {
+                eprintln!("WebSocket client refused - {} already connected", MAX_WEBSOCKET_CLIENTS);
+            }
+            Ok((stream, _)) => {
+                let hub = hub.clone();
+                let control = control.clone();
+                let clients = clients.clone();
+                clients.fetch_add(1, Ordering::AcqRel);
+                thread::spawn(move || {
+                    if let Err(e) = serve_client(stream, hub, control) {
+                        eprintln!("WebSocket client error: {}", e);
+                    }
+                    clients.fetch_sub(1, Ordering::AcqRel);
+                });
+            }
+            // Listener is non-blocking so the stop flag gets checked
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                eprintln!("WebSocket accept failed: {}", e);
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+fn serve_client(stream: TcpStream, hub: Arc<ControlHub>, control: Arc<Mutex<bool>>) -> anyhow::Result<()> {
+    stream.set_nonblocking(false)?;
+    let mut socket =
+        tungstenite::accept_hdr(stream, check_origin).map_err(|e| anyhow::anyhow!("Handshake failed: {}", e))?;
+    socket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+
+    let (sender, receiver) = mpsc::channel();
+    hub.add_websocket_client(sender);
+    if let Ok(state) = hub.state() {
+        send(&mut socket, &ServerMessage::State(state))?;
+    }
+
+    while *control.lock().unwrap() {
+        while let Ok(message) = receiver.try_recv() {
+            socket.send(Message::text(message))?;
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                for reply in handle_text(&hub, text.as_str()) {
+                    send(&mut socket, &reply)?;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {} // pings are answered by tungstenite, binary is not part of the protocol
+            Err(tungstenite::Error::Io(ref e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(tungstenite::Error::ConnectionClosed) => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    // Dropping the receiver unregisters the client on the next broadcast
+    let _ = socket.close(None);
+    Ok(())
+}
+
+// The error type is set by tungstenite's handshake callback
+#[allow(clippy::result_large_err)]
+fn check_origin(request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+    if request.headers().contains_key("Origin") {
+        let mut refusal = ErrorResponse::new(Some("Browser connections are not allowed".to_string()));
+        *refusal.status_mut() = StatusCode::FORBIDDEN;
+        return Err(refusal);
+    }
+    Ok(response)
+}
+
+fn handle_text(hub: &ControlHub, text: &str) -> Vec<ServerMessage> {
+    let request: ControlRequest = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => {
+            return vec![ServerMessage::Result {
+                ok: false,
+                error: Some(format!("Invalid request: {}", e)),
+            }];
+        }
+    };
+
+    let mut replies = vec![match hub.handle(&request) {
+        Ok(()) => ServerMessage::Result { ok: true, error: None },
+        Err(e) => ServerMessage::Result {
+            ok: false,
+            error: Some(e.to_string()),
+        },
+    }];
+    if request == ControlRequest::GetState {
+        replies.extend(hub.state().ok().map(ServerMessage::State));
+    }
+    replies
+}
+
+fn send(socket: &mut WebSocket<TcpStream>, message: &ServerMessage) -> anyhow::Result<()> {
+    socket.send(Message::text(serde_json::to_string(message)?))?;
+    Ok(())
+}
@@ -0,0 +1,235 @@
+// Runs effects on interleaved multichannel buffers - one instance per channel (every channel
+// keeps its own state), one instance on the downmix, or one stereo-aware instance on left and right
+
+use super::modules::carrier::{Carrier, CarrierSource};
+use super::modules::effects::auto_tune::Scale;
+use super::modules::utils::{downmix_into, EffectParameter};
+use super::traits::EffectModule;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    PerChannel,
+    Downmixed,
+    Stereo,
+}
+
+pub struct ChannelAdapter {
+    instances: Vec<Box<dyn EffectModule>>,
+    mode: Mode,
+    channels: usize,
+    planes_in: Vec<Vec<f32>>,
+    planes_out: Vec<Vec<f32>>,
+}
+
+impl ChannelAdapter {
+    // One instance per channel, all created with the same settings
+    pub fn per_channel(instances: Vec<Box<dyn EffectModule>>) -> Self {
+        let channels = instances.len().max(1);
+        Self {
+            instances,
+            mode: Mode::PerChannel,
+            channels,
+            planes_in: vec![Vec::new(); channels],
+            planes_out: vec![Vec::new(); channels],
+        }
+    }
+
+    // A single instance fed with the average of all channels, its output copied to each
+    pub fn downmixed(instance: Box<dyn EffectModule>, channels: usize) -> Self {
+        Self {
+            instances: vec![instance],
+            mode: Mode::Downmixed,
+            channels: channels.max(1),
+            planes_in: vec![Vec::new()],
+            planes_out: vec![Vec::new()],
+        }
+    }
+
+    // A single instance that sees left and right together through `process_stereo`
+    pub fn stereo(instance: Box<dyn EffectModule>) -> Self {
+        Self {
+            instances: vec![instance],
+            mode: Mode::Stereo,
+            channels: 2,
+            planes_in: vec![Vec::new(); 2],
+            planes_out: vec![Vec::new(); 2],
+        }
+    }
+}
+
+impl EffectModule for ChannelAdapter {
+    fn process(&mut self, in_b: &[f32], out_b: &mut [f32]) {
+        let channels = self.channels;
+        let frames = in_b.len() / channels;
+
+        if self.mode == Mode::Downmixed {
+            downmix_into(in_b, channels, &mut self.planes_in[0]);
+        } else {
+            for (channel, plane) in self.planes_in.iter_mut().enumerate() {
+                plane.clear();
+                plane.extend(in_b.iter().skip(channel).step_by(channels).take(frames));
+            }
+        }
+
+        if self.mode == Mode::Stereo {
+            let (left, right) = self.planes_out.split_at_mut(1);
+            left[0].resize(frames, 0.0);
+            right[0].resize(frames, 0.0);
+            self.instances[0].process_stereo(&self.planes_in[0], &self.planes_in[1], &mut left[0], &mut right[0]);
+        } else {
+            for ((instance, plane_in), plane_out) in self
+                .instances
+                .iter_mut()
+                .zip(&self.planes_in)
+                .zip(self.planes_out.iter_mut())
+            {
+                plane_out.resize(plane_in.len(), 0.0);
+                instance.process(plane_in, plane_out);
+            }
+        }
+
+        for (frame, out_frame) in out_b.chunks_exact_mut(channels).enumerate() {
+            for (channel, sample) in out_frame.iter_mut().enumerate() {
+                let plane = &self.planes_out[channel % self.planes_out.len()];
+                *sample = plane[frame];
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for instance in self.instances.iter_mut() {
+            instance.reset();
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.instances[0].name()
+    }
+
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+        for instance in self.instances.iter_mut() {
+            instance.set_parameter_value(name, value)?;
+        }
+        Ok(())
+    }
+
+    fn is_modulatable(&self, name: &str) -> bool {
+        self.instances[0].is_modulatable(name)
+    }
+
+    fn get_parameters(&self) -> Vec<EffectParameter> {
+        self.instances[0].get_parameters()
+    }
+
+    fn set_scale(&mut self, scale: Scale) -> anyhow::Result<()> {
+        for instance in self.instances.iter_mut() {
+            instance.set_scale(scale)?;
+        }
+        Ok(())
+    }
+
+    fn get_scale(&self) -> Option<Scale> {
+        self.instances[0].get_scale()
+    }
+
+    // A carrier is a single stream, so only a downmixed effect can take one
+    fn set_carrier(&mut self, carrier: Option<Box<dyn Carrier>>) -> anyhow::Result<()> {
+        if self.mode != Mode::Downmixed {
+            return Err(anyhow::anyhow!("External carriers need the effect to run on the downmix"));
+        }
+        self.instances[0].set_carrier(carrier)
+    }
+
+    fn get_carrier_source(&self) -> Option<CarrierSource> {
+        self.instances[0].get_carrier_source()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::modules::utils::ParameterValue;
+    use crate::dsp::modules::effects::{Amplifier, Panner};
+
+    // Remembers the previous sample - shows whether channels share state
+    struct OneSampleDelay {
+        last: f32,
+    }
+
+    impl EffectModule for OneSampleDelay {
+        fn process(&mut self, in_b: &[f32], out_b: &mut [f32]) {
+            for (input, output) in in_b.iter().zip(out_b.iter_mut()) {
+                *output = self.last;
+                self.last = *input;
+            }
+        }
+        fn reset(&mut self) {
+            self.last = 0.0;
+        }
+        fn name(&self) -> &str {
+            "delay"
+        }
+        fn set_parameter_value(&mut self, _name: &str, _value: f32) -> anyhow::Result<()> {
+            Ok(())
+        }
+        fn get_parameters(&self) -> Vec<EffectParameter> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn channels_keep_their_own_state() {
+        let mut adapter = ChannelAdapter::per_channel(vec![
+            Box::new(OneSampleDelay { last: 0.0 }),
+            Box::new(OneSampleDelay { last: 0.0 }),
+        ]);
+        let mut output = [0.0; 6];
+        adapter.process(&[1.0, -1.0, 2.0, -2.0, 3.0, -3.0], &mut output);
+        assert_eq!(output, [0.0, 0.0, 1.0, -1.0, 2.0, -2.0]);
+
+        let mut downmixed = ChannelAdapter::downmixed(Box::new(OneSampleDelay { last: 0.0 }), 2);
+        downmixed.process(&[1.0, 3.0, 2.0, 4.0], &mut output[..4]);
+        assert_eq!(&output[..4], &[0.0, 0.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn parameters_reach_every_channel() {
+        let mut adapter = ChannelAdapter::per_channel(vec![
+            Box::new(Amplifier::new(1.0, 48_000.0)),
+            Box::new(Amplifier::new(1.0, 48_000.0)),
+        ]);
+        adapter
+            .set_parameter(ParameterValue {
+                name: "gain".to_string(),
+                value: 2.0,
+            })
+            .unwrap();
+        adapter.reset();
+
+        let mut output = [0.0; 2];
+        adapter.process(&[0.25, 0.5], &mut output);
+        assert_eq!(output, [0.5, 1.0]);
+        assert_eq!(adapter.name(), "amplifier");
+        assert!(adapter.set_carrier(None).is_err());
+    }
+
+    #[test]
+    fn stereo_effects_see_both_sides() {
+        let mut panner = Panner::new(48_000.0);
+        panner
+            .set_parameter(ParameterValue {
+                name: "pan".to_string(),
+                value: -1.0,
+            })
+            .unwrap();
+        panner.reset();
+
+        let mut adapter = ChannelAdapter::stereo(Box::new(panner));
+        let mut output = [1.0; 4];
+        adapter.process(&[0.5, 0.5, 0.25, 0.25], &mut output);
+        // Hard left keeps the centred voice at equal power, all of it on the left
+        assert!((output[0] - 0.5 * std::f32::consts::SQRT_2).abs() < 1e-6);
+        assert!(output[1].abs() < 1e-6 && output[3].abs() < 1e-6);
+        assert!(adapter.set_carrier(None).is_err());
+    }
+}
@@ -1,25 +1,58 @@
 use crate::dsp::modules::effects::{
-	Amplifier, AutoTune, Bitcrusher, Chorus, Distortion, PitchShifter, Reverb, Scale, Vibrato,
-	Vocoder,
+	Amplifier, AutoTune, AutoWah, Bitcrusher, Chorus, Distortion, Panner, PitchShifter, Reverb,
+	Scale, StereoWidener, Vibrato, Vocoder,
 };
+use crate::dsp::channel_adapter::ChannelAdapter;
 use crate::dsp::traits::EffectModule;
 
 fn normalize_effect_name(name: &str) -> String {
 	name.trim().to_ascii_lowercase().replace(['-', ' '], "_")
 }
 
+// Builds an effect for interleaved buffers with `channels` channels
 pub fn create_effect_from_name(
 	name: &str,
 	sample_rate: usize,
 	channels: usize,
 ) -> Result<Box<dyn EffectModule>, String> {
-	let normalized = normalize_effect_name(name);
 	let channels = channels.max(1);
+	if channels == 1 {
+		return create_single_effect(name, sample_rate);
+	}
+
+	match normalize_effect_name(name).as_str() {
+		// One carrier drives the whole voice - a robot voice has no stereo image to keep
+		"vocoder" | "vocoder_daft_punk" | "daft_punk" => Ok(Box::new(ChannelAdapter::downmixed(
+			create_single_effect(name, sample_rate)?,
+			channels,
+		))),
+		_ => {
+			let effect = create_single_effect(name, sample_rate)?;
+			// Stereo-aware effects shape the image themselves instead of running once per side
+			if channels == 2 && effect.is_stereo() {
+				return Ok(Box::new(ChannelAdapter::stereo(effect)));
+			}
+
+			let mut instances = vec![effect];
+			for _ in 1..channels {
+				instances.push(create_single_effect(name, sample_rate)?);
+			}
+			Ok(Box::new(ChannelAdapter::per_channel(instances)))
+		}
+	}
+}
+
+// One instance working on a single channel (or on left and right through `process_stereo`)
+fn create_single_effect(
+	name: &str,
+	sample_rate: usize,
+) -> Result<Box<dyn EffectModule>, String> {
+	let normalized = normalize_effect_name(name);
 
 	let effect: Box<dyn EffectModule> = match normalized.as_str() {
-		"amplifier" | "amp" => Box::new(Amplifier::new(1.2)),
-		"distortion" | "drive" => Box::new(Distortion::new(8.0)),
-		"bitcrusher" | "bit_crusher" => Box::new(Bitcrusher::new(8.0, 4)),
+		"amplifier" | "amp" => Box::new(Amplifier::new(1.2, sample_rate as f32)),
+		"distortion" | "drive" => Box::new(Distortion::new(8.0, sample_rate as f32)),
+		"bitcrusher" | "bit_crusher" => Box::new(Bitcrusher::new(8.0, 4, sample_rate as f32)),
 		"chorus" => Box::new(Chorus::new(sample_rate, 0.35, 0.45)),
 		"vibrato" => Box::new(Vibrato::new(5.0, 0.6, 0.5, sample_rate as f32)),
 		"pitch_shifter" | "pitchshifter" | "pitch" => {
@@ -35,18 +68,22 @@ pub fn create_effect_from_name(
 			Box::new(auto_tune)
 		}
 		"reverb" => {
-			let mut reverb = Reverb::new(sample_rate as u32, channels);
+			let mut reverb = Reverb::new(sample_rate as u32);
 			reverb.set_room_size(0.5);
 			reverb.set_damping(0.5);
 			reverb.set_wet_level(0.30);
 			reverb.set_dry_level(0.70);
+			reverb.set_width(1.0);
 			Box::new(reverb)
 		}
+		"auto_wah" | "autowah" | "wah" => Box::new(AutoWah::new(sample_rate)),
+		"panner" | "pan" => Box::new(Panner::new(sample_rate as f32)),
+		"stereo_widener" | "widener" => Box::new(StereoWidener::new(sample_rate as f32)),
 		"vocoder" => Box::new(Vocoder::new(sample_rate)),
 		"vocoder_daft_punk" | "daft_punk" => Box::new(Vocoder::daft_punk(sample_rate)),
 		_ => {
 			return Err(format!(
-				"Unknown effect name: '{}'. Supported effects: amplifier, distortion, bitcrusher, chorus, vibrato, pitch_shifter, auto_tune, reverb, vocoder",
+				"Unknown effect name: '{}'. Supported effects: amplifier, distortion, bitcrusher, chorus, vibrato, pitch_shifter, auto_tune, reverb, auto_wah, vocoder, panner, stereo_widener",
 				name
 			))
 		}
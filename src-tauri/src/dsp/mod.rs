@@ -2,4 +2,5 @@ pub mod modulation_unit;
 pub mod traits;
 pub mod modules;
 pub mod processor;
-pub mod effect_factory;
\ No newline at end of file
+pub mod effect_factory;
+pub mod channel_adapter;
\ No newline at end of file
@@ -1,6 +1,10 @@
 use super::processor::AudioProcessor;
 use super::traits::EffectModule;
+use crate::dsp::modules::carrier::{Carrier, CarrierSource};
+use crate::dsp::modules::modulation_matrix::ModulationMatrixSettings;
 use crate::dsp::modules::utils::ParameterValue;
+use crate::dsp::modules::visualizer::audio_spectrum::SpectrogramSnapshot;
+use crate::dsp::modules::visualizer::settings::VisualizerSettings;
 
 // current effect to option
 pub struct ModulationUnit {
@@ -10,19 +14,21 @@ pub struct ModulationUnit {
 }
 
 impl ModulationUnit {
-    pub fn new(sample_rate: usize) -> Self {
+    pub fn new(sample_rate: usize, channels: usize) -> Self {
         ModulationUnit {
-            audio_processor: AudioProcessor::new(sample_rate),
+            audio_processor: AudioProcessor::new(sample_rate, channels),
             is_active: false,
             app_handle: None,
         }
     }
 
     pub fn set_app_handle(&mut self, handle: tauri::AppHandle) {
+        self.audio_processor.start_analysis(handle.clone());
         self.app_handle = Some(handle);
     }
 
     pub fn clear_app_handle(&mut self) {
+        self.audio_processor.stop_analysis();
         self.app_handle = None;
     }
 
@@ -38,6 +44,14 @@ impl ModulationUnit {
         self.audio_processor.append_effect_from_name(name)
     }
 
+    pub fn create_effect(&self, name: &str) -> anyhow::Result<Box<dyn EffectModule>> {
+        self.audio_processor.create_effect(name)
+    }
+
+    pub fn replace_effects(&mut self, effects: Vec<Box<dyn EffectModule>>) {
+        self.audio_processor.replace_effects(effects)
+    }
+
      pub fn remove_effect_from_name(&mut self, name: &str) -> Option<Box<dyn EffectModule>> {
         self.audio_processor.remove_effect_from_name(name)
     }
@@ -58,25 +72,92 @@ impl ModulationUnit {
         self.audio_processor.get_auto_tune_scale()
     }
 
+    pub fn set_vocoder_scale(&mut self, scale: crate::dsp::modules::effects::auto_tune::Scale) -> anyhow::Result<()> {
+        self.audio_processor.set_vocoder_scale(scale)
+    }
+
+    pub fn get_vocoder_scale(&self) -> Option<crate::dsp::modules::effects::auto_tune::Scale> {
+        self.audio_processor.get_vocoder_scale()
+    }
+
+    pub fn set_vocoder_carrier(&mut self, carrier: Option<Box<dyn Carrier>>) -> anyhow::Result<()> {
+        self.audio_processor.set_vocoder_carrier(carrier)
+    }
+
+    pub fn get_vocoder_carrier(&self) -> Option<CarrierSource> {
+        self.audio_processor.get_vocoder_carrier()
+    }
+
+    pub fn get_sample_rate(&self) -> usize {
+        self.audio_processor.get_sample_rate()
+    }
+
+    // The analysis thread reads interleaved frames, so it restarts with the new channel count
+    pub fn set_channels(&mut self, channels: usize) {
+        self.audio_processor.set_channels(channels);
+        if let Some(ref handle) = self.app_handle {
+            self.audio_processor.start_analysis(handle.clone());
+        }
+    }
+
+    pub fn get_channels(&self) -> usize {
+        self.audio_processor.get_channels()
+    }
+
+    pub fn set_modulation_matrix(&mut self, settings: ModulationMatrixSettings) -> anyhow::Result<()> {
+        self.audio_processor.set_modulation_matrix(settings)
+    }
+
+    pub fn get_modulation_matrix(&self) -> ModulationMatrixSettings {
+        self.audio_processor.get_modulation_matrix()
+    }
+
+    pub fn set_tuner_reference(&mut self, reference: f32) {
+        self.audio_processor.set_tuner_reference(reference);
+    }
+
+    pub fn get_tuner_reference(&self) -> f32 {
+        self.audio_processor.get_tuner_reference()
+    }
+
+    pub fn set_visualizer_settings(&mut self, settings: VisualizerSettings) -> anyhow::Result<()> {
+        self.audio_processor.set_visualizer_settings(settings)
+    }
+
+    pub fn get_visualizer_settings(&self) -> VisualizerSettings {
+        self.audio_processor.get_visualizer_settings()
+    }
+
+    pub fn get_spectrogram(&self) -> SpectrogramSnapshot {
+        self.audio_processor.get_spectrogram()
+    }
+
+    pub fn reset_meters(&self) {
+        self.audio_processor.reset_meters();
+    }
+
     pub fn get_active_effects(&self) -> Vec<String> {
         self.audio_processor.get_active_effects()
     }
 
     pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
         if self.is_active {
-            if let Some(app_handle) = &self.app_handle {
-                let _ = self.audio_processor.process_and_send(input, app_handle);
-            }
             self.audio_processor.process(input)
         } else {
-            if let Some(app_handle) = &self.app_handle {
-                let _ = self.audio_processor.send_spectrum(input, app_handle);
-            }
             input.to_vec()
         }
     }
 
+    // Called by the stream once the bed and clips are mixed in, so the output meter sees the final signal
+    pub fn send_spectrum(&mut self, input: &[f32], output: &[f32]) {
+        self.audio_processor.send_spectrum(input, output);
+    }
+
     pub fn is_app_handle_set(&self) -> bool {
         self.app_handle.is_some()
     }
+
+    pub fn get_app_handle(&self) -> Option<tauri::AppHandle> {
+        self.app_handle.clone()
+    }
 }
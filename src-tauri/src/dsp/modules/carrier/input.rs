@@ -0,0 +1,139 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use ringbuf::traits::{Consumer, Producer, Split};
+use ringbuf::HeapRb;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::{Carrier, CarrierSource};
+use crate::dsp::modules::utils::{downmix_into, Resampler};
+
+/// Most carrier audio buffered between the second input and the vocoder, in ms.
+/// Anything beyond that is dropped so the carrier never lags behind the voice.
+const MAX_LATENCY_MS: usize = 100;
+
+/// Second audio input (synth, music player loopback) used as the carrier.
+/// The cpal stream lives on its own thread at the device's own rate, samples arrive
+/// resampled to the processing rate through a ring buffer.
+pub struct InputCarrier {
+    device_name: String,
+    consumer: <HeapRb<f32> as Split>::Cons,
+    control: Arc<Mutex<bool>>, // true = run, false = stop
+}
+
+impl InputCarrier {
+    pub fn open(device_name: &str, sample_rate: usize) -> anyhow::Result<Self> {
+        let capacity = (sample_rate * MAX_LATENCY_MS / 1000).max(1);
+        let (producer, consumer) = HeapRb::<f32>::new(capacity).split();
+        let control = Arc::new(Mutex::new(true));
+
+        // The stream is not Send - build it on the thread that keeps it alive
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        let thread_control = Arc::clone(&control);
+        let name = device_name.to_string();
+        thread::spawn(move || {
+            let stream = match build_stream(&name, sample_rate, producer) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = ready_sender.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_sender.send(Ok(()));
+
+            while thread_control.lock().map(|run| *run).unwrap_or(false) {
+                thread::sleep(Duration::from_millis(10));
+            }
+            drop(stream);
+        });
+
+        ready_receiver
+            .recv()
+            .map_err(|_| anyhow::anyhow!("Carrier input thread exited"))??;
+
+        Ok(Self {
+            device_name: device_name.to_string(),
+            consumer,
+            control,
+        })
+    }
+}
+
+fn build_stream(
+    device_name: &str,
+    sample_rate: usize,
+    mut producer: <HeapRb<f32> as Split>::Prod,
+) -> anyhow::Result<cpal::Stream> {
+    let host = cpal::default_host();
+    let device = if device_name == "default" {
+        host.default_input_device()
+    } else {
+        host.input_devices()?
+            .find(|device| device.name().unwrap_or_default() == device_name)
+    }
+    .ok_or_else(|| anyhow::anyhow!("Carrier input device '{}' not found", device_name))?;
+
+    // The device runs at its native rate - the callback resamples to the processing rate
+    let default_config = device.default_input_config()?;
+    let config: cpal::StreamConfig = if default_config.sample_format() == cpal::SampleFormat::F32 {
+        default_config.config()
+    } else {
+        device
+            .supported_input_configs()?
+            .find(|range| range.sample_format() == cpal::SampleFormat::F32)
+            .ok_or_else(|| anyhow::anyhow!("Carrier input device '{}' has no f32 format", device_name))?
+            .with_max_sample_rate()
+            .config()
+    };
+    let channels = config.channels.max(1) as usize;
+    let device_rate = config.sample_rate.0;
+    let mut resampler =
+        (device_rate as usize != sample_rate).then(|| Resampler::new(device_rate, sample_rate as u32, 1));
+    let mut mono: Vec<f32> = Vec::new();
+
+    let stream = device.build_input_stream(
+        &config,
+        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            downmix_into(data, channels, &mut mono);
+            let resampled;
+            let samples = match resampler {
+                Some(ref mut resampler) => {
+                    resampled = resampler.process(&mono);
+                    &resampled[..]
+                }
+                None => &mono[..],
+            };
+            producer.push_slice(samples);
+        },
+        |err| eprintln!("Carrier input stream error: {}", err),
+        None,
+    )?;
+    stream.play()?;
+    Ok(stream)
+}
+
+impl Carrier for InputCarrier {
+    fn next_sample(&mut self) -> f32 {
+        // Silence while the device has not delivered yet
+        self.consumer.try_pop().unwrap_or(0.0)
+    }
+
+    fn reset(&mut self) {
+        self.consumer.clear();
+    }
+
+    fn source(&self) -> CarrierSource {
+        CarrierSource::Input {
+            device_name: self.device_name.clone(),
+        }
+    }
+}
+
+impl Drop for InputCarrier {
+    fn drop(&mut self) {
+        if let Ok(mut run) = self.control.lock() {
+            *run = false;
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Vocoder carrier sources.
+//!
+//! The vocoder's own oscillator bank is the default carrier. The sources here replace
+//! it with a looped audio file, a second input device or noise (whispered voice).
+
+pub mod file;
+pub mod input;
+pub mod noise;
+
+use serde::{Deserialize, Serialize};
+
+pub use file::FileCarrier;
+pub use input::InputCarrier;
+pub use noise::NoiseCarrier;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CarrierSource {
+    #[default]
+    Oscillators,
+    File { path: String },
+    Input { device_name: String },
+    Noise,
+}
+
+/// External carrier signal, pulled one mono sample at a time from the audio callback.
+pub trait Carrier: Send {
+    fn next_sample(&mut self) -> f32;
+    fn reset(&mut self);
+    fn source(&self) -> CarrierSource;
+}
+
+/// Builds the carrier for `source` - `None` means the vocoder's own oscillator bank.
+/// Opening files and devices is slow, so call this outside the audio lock.
+pub fn create_carrier(source: &CarrierSource, sample_rate: usize) -> anyhow::Result<Option<Box<dyn Carrier>>> {
+    let carrier: Box<dyn Carrier> = match source {
+        CarrierSource::Oscillators => return Ok(None),
+        CarrierSource::File { path } => Box::new(FileCarrier::load(path, sample_rate)?),
+        CarrierSource::Input { device_name } => Box::new(InputCarrier::open(device_name, sample_rate)?),
+        CarrierSource::Noise => Box::new(NoiseCarrier::new()),
+    };
+    Ok(Some(carrier))
+}
@@ -0,0 +1,34 @@
+use super::{Carrier, CarrierSource};
+use crate::dsp::modules::utils::NoiseOscillator;
+
+/// White noise carrier - turns the vocoder into a whisper.
+#[derive(Debug, Clone)]
+pub struct NoiseCarrier {
+    noise: NoiseOscillator,
+}
+
+impl NoiseCarrier {
+    pub fn new() -> Self {
+        Self {
+            noise: NoiseOscillator::new(1.0),
+        }
+    }
+}
+
+impl Default for NoiseCarrier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Carrier for NoiseCarrier {
+    fn next_sample(&mut self) -> f32 {
+        self.noise.process()
+    }
+
+    fn reset(&mut self) {}
+
+    fn source(&self) -> CarrierSource {
+        CarrierSource::Noise
+    }
+}
@@ -1,14 +1,135 @@
-use crate::dsp::modules::utils::ParameterValue;
+use crate::dsp::modules::carrier::{Carrier, CarrierSource};
+use crate::dsp::modules::modulation_matrix::{ModulationMatrix, ModulationMatrixSettings};
+use crate::dsp::modules::utils::{downmix_into, EffectParameter, ParameterValue};
 use crate::dsp::traits::{EffectChain, EffectModule};
 
+// A routed parameter resolved to its effect's position, so the callback never searches by name
+struct ModulationTarget {
+    effect: usize,
+    parameter: EffectParameter, // value is the base the user set
+    routes: Vec<usize>,
+    last: f32, // what the effect currently holds
+}
+
 pub struct ModulationChain {
     effects: Vec<Box<dyn EffectModule>>,
+    matrix: ModulationMatrix,
+    targets: Vec<ModulationTarget>,
+    channels: usize,
+    downmixed: Vec<f32>,
 }
 
 impl ModulationChain {
-    pub fn new() -> Self {
+    pub fn new(sample_rate: usize, channels: usize) -> Self {
         Self {
             effects: Vec::new(),
+            matrix: ModulationMatrix::new(sample_rate),
+            targets: Vec::new(),
+            channels: channels.max(1),
+            downmixed: Vec::new(),
+        }
+    }
+
+    pub fn set_modulation_matrix(&mut self, settings: ModulationMatrixSettings) -> anyhow::Result<()> {
+        for route in &settings.routes {
+            let refused = self
+                .effects
+                .iter()
+                .any(|e| e.name() == route.effect_name && !e.is_modulatable(&route.parameter_name));
+            if refused {
+                return Err(anyhow::anyhow!(
+                    "Parameter '{}' of '{}' cannot be modulated",
+                    route.parameter_name,
+                    route.effect_name
+                ));
+            }
+        }
+
+        let old_bases: Vec<(String, String, f32)> = self
+            .matrix
+            .get_settings()
+            .routes
+            .into_iter()
+            .filter_map(|route| {
+                self.matrix
+                    .base_value(&route.effect_name, &route.parameter_name)
+                    .map(|base| (route.effect_name, route.parameter_name, base))
+            })
+            .collect();
+        self.matrix.set_settings(settings)?;
+
+        // Parameters that lost all their routes go back to the value the user set
+        for (effect_name, parameter_name, base) in old_bases {
+            if self.matrix.is_modulated(&effect_name, &parameter_name) {
+                continue;
+            }
+            if let Some(effect) = self.effects.iter_mut().find(|e| e.name() == effect_name) {
+                let _ = effect.set_parameter_value(&parameter_name, base);
+            }
+        }
+        self.resolve_targets();
+        Ok(())
+    }
+
+    pub fn get_modulation_matrix(&self) -> ModulationMatrixSettings {
+        self.matrix.get_settings()
+    }
+
+    // Looks up every routed parameter once - called whenever the routes or the effects change.
+    // Parameters that refuse modulation and routes to effects not in the chain are skipped
+    fn resolve_targets(&mut self) {
+        self.targets.clear();
+        for (effect_name, parameter_name) in self.matrix.targets() {
+            let Some(effect) = self.effects.iter().position(|e| e.name() == effect_name) else {
+                continue;
+            };
+            if !self.effects[effect].is_modulatable(&parameter_name) {
+                continue;
+            }
+            let Some(mut parameter) = self.effects[effect]
+                .get_parameters()
+                .into_iter()
+                .find(|p| p.name == parameter_name)
+            else {
+                continue;
+            };
+
+            let current = parameter.value;
+            parameter.value = self
+                .matrix
+                .base_value_or_insert(&effect_name, &parameter_name, current);
+            self.targets.push(ModulationTarget {
+                effect,
+                routes: self.matrix.routes_for(&effect_name, &parameter_name),
+                parameter,
+                last: current,
+            });
+        }
+    }
+
+    // Moves every routed parameter to its base value plus the current modulation offset
+    fn apply_modulation(&mut self, in_b: &[f32]) {
+        if self.matrix.is_empty() {
+            return;
+        }
+
+        // Envelope and pitch sources follow the voice, not the interleaved frames
+        if self.channels > 1 {
+            downmix_into(in_b, self.channels, &mut self.downmixed);
+            self.matrix.process_block(&self.downmixed);
+        } else {
+            self.matrix.process_block(in_b);
+        }
+        for target in self.targets.iter_mut() {
+            let offset: f32 = target.routes.iter().map(|&route| self.matrix.route_offset(route)).sum();
+            let value = target
+                .parameter
+                .value_at_normalized(target.parameter.get_normalized() + offset);
+            if value == target.last {
+                continue;
+            }
+            target.last = value;
+            let _ = self.effects[target.effect].set_parameter_value(&target.parameter.name, value);
         }
     }
 }
@@ -18,11 +139,14 @@ impl EffectChain for ModulationChain {
         for effect in self.effects.iter_mut() {
             effect.reset();
         }
+        self.matrix.reset();
     }
 
     fn apply_processing(&mut self, in_b: &[f32], out_b: &mut [f32]) {
         debug_assert_eq!(in_b.len(), out_b.len());
 
+        self.apply_modulation(in_b);
+
         match self.effects.len() {
             0 => {
                 out_b.copy_from_slice(in_b);
@@ -87,11 +211,15 @@ impl EffectChain for ModulationChain {
 
     fn append_effect(&mut self, effect: Box<dyn EffectModule>) {
         self.effects.push(effect);
+        self.resolve_targets();
     }
 
     fn remove_effect_from_name(&mut self, name: &str) -> Option<Box<dyn EffectModule>> {
         if let Some(pos) = self.effects.iter().position(|e| e.name() == name) {
-            return Some(self.effects.remove(pos));
+            self.matrix.forget_effect(name);
+            let removed = self.effects.remove(pos);
+            self.resolve_targets();
+            return Some(removed);
         } else {
             return None;
         }
@@ -99,7 +227,10 @@ impl EffectChain for ModulationChain {
 
     fn remove_effect_at(&mut self, index: usize) -> Option<Box<dyn EffectModule>> {
         if index < self.effects.len() {
-            Some(self.effects.remove(index))
+            self.matrix.forget_effect(self.effects[index].name());
+            let removed = self.effects.remove(index);
+            self.resolve_targets();
+            Some(removed)
         } else {
             None
         }
@@ -110,8 +241,19 @@ impl EffectChain for ModulationChain {
         effect_name: &str,
         parameter: ParameterValue,
     ) -> anyhow::Result<()> {
-        if let Some(effect) = self.effects.iter_mut().find(|e| e.name() == effect_name) {
-            effect.set_parameter(parameter)
+        if let Some(index) = self.effects.iter().position(|e| e.name() == effect_name) {
+            // A modulated parameter keeps moving - the new value becomes its base
+            if let Some(target) = self
+                .targets
+                .iter_mut()
+                .find(|t| t.effect == index && t.parameter.name == parameter.name)
+            {
+                target.parameter.set_value(parameter.value);
+                target.last = target.parameter.value;
+                self.matrix
+                    .set_base_value(effect_name, &target.parameter.name, target.parameter.value);
+            }
+            self.effects[index].set_parameter(parameter)
         } else {
             Err(anyhow::anyhow!(
                 "Effect '{}' not found in chain",
@@ -139,6 +281,36 @@ impl EffectChain for ModulationChain {
         }
     }
 
+    fn set_vocoder_scale(&mut self, scale: crate::dsp::modules::effects::Scale) -> anyhow::Result<()> {
+        if let Some(effect) = self.effects.iter_mut().find(|e| e.name() == "vocoder") {
+            effect.set_scale(scale)
+        } else {
+            Err(anyhow::anyhow!("Vocoder effect not found in chain"))
+        }
+    }
+
+    fn get_vocoder_scale(&self) -> Option<crate::dsp::modules::effects::Scale> {
+        self.effects
+            .iter()
+            .find(|e| e.name() == "vocoder")
+            .and_then(|effect| effect.get_scale())
+    }
+
+    fn set_vocoder_carrier(&mut self, carrier: Option<Box<dyn Carrier>>) -> anyhow::Result<()> {
+        if let Some(effect) = self.effects.iter_mut().find(|e| e.name() == "vocoder") {
+            effect.set_carrier(carrier)
+        } else {
+            Err(anyhow::anyhow!("Vocoder effect not found in chain"))
+        }
+    }
+
+    fn get_vocoder_carrier(&self) -> Option<CarrierSource> {
+        self.effects
+            .iter()
+            .find(|e| e.name() == "vocoder")
+            .and_then(|effect| effect.get_carrier_source())
+    }
+
     fn get_active_effects(&self) -> Vec<String> {
         self.effects.iter().map(|e| e.name().to_string()).collect()
     }
@@ -148,7 +320,14 @@ impl EffectChain for ModulationChain {
         effect_name: &str,
     ) -> anyhow::Result<Vec<crate::dsp::modules::utils::EffectParameter>> {
         if let Some(effect) = self.effects.iter().find(|e| e.name() == effect_name) {
-            Ok(effect.get_parameters())
+            // Report what the user set, not where the modulation currently is
+            let mut parameters = effect.get_parameters();
+            for parameter in parameters.iter_mut() {
+                if let Some(base) = self.matrix.base_value(effect_name, &parameter.name) {
+                    parameter.value = base;
+                }
+            }
+            Ok(parameters)
         } else {
             Err(anyhow::anyhow!(
                 "Effect '{}' not found in chain",
@@ -157,3 +336,59 @@ impl EffectChain for ModulationChain {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::modules::effects::{Amplifier, Vocoder};
+    use crate::dsp::modules::modulation_matrix::{ModulationRoute, ModulationSource};
+
+    fn envelope_route(effect_name: &str, parameter_name: &str, depth: f32) -> ModulationMatrixSettings {
+        ModulationMatrixSettings {
+            routes: vec![ModulationRoute {
+                source: ModulationSource::Envelope,
+                effect_name: effect_name.to_string(),
+                parameter_name: parameter_name.to_string(),
+                depth,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn gain(chain: &ModulationChain) -> f32 {
+        chain.effects[0].get_parameters()[0].value
+    }
+
+    #[test]
+    fn modulation_moves_around_the_base_value() {
+        let mut chain = ModulationChain::new(48_000, 1);
+        chain.append_effect(Box::new(Amplifier::new(10.0, 48_000.0)));
+        chain.set_modulation_matrix(envelope_route("amplifier", "gain", 0.5)).unwrap();
+
+        // Full scale input pins the envelope at 1.0 - half the 0-50 range on top of 10
+        let mut output = vec![0.0; 4800];
+        chain.apply_processing(&[1.0; 4800], &mut output);
+        assert!((gain(&chain) - 35.0).abs() < 0.5, "gain {}", gain(&chain));
+        assert_eq!(chain.get_effect_parameters("amplifier").unwrap()[0].value, 10.0);
+
+        // A fader move shifts the base, the modulation keeps riding on it
+        chain
+            .set_effect_parameter("amplifier", ParameterValue { name: "gain".to_string(), value: 20.0 })
+            .unwrap();
+        chain.apply_processing(&[1.0; 4800], &mut output);
+        assert!((gain(&chain) - 45.0).abs() < 0.5, "gain {}", gain(&chain));
+
+        // Dropping the route puts the base back
+        chain.set_modulation_matrix(ModulationMatrixSettings::default()).unwrap();
+        assert_eq!(gain(&chain), 20.0);
+    }
+
+    #[test]
+    fn rebuilding_parameters_refuse_routes() {
+        let mut chain = ModulationChain::new(48_000, 1);
+        chain.append_effect(Box::new(Vocoder::new(48_000)));
+        assert!(chain.set_modulation_matrix(envelope_route("vocoder", "band_count", 0.5)).is_err());
+        assert!(chain.set_modulation_matrix(envelope_route("vocoder", "env_gain", 0.5)).is_ok());
+        assert_eq!(chain.targets.len(), 1);
+    }
+}
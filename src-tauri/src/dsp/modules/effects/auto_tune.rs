@@ -2,10 +2,10 @@ use serde::{Serialize, Deserialize};
 
 use super::pitch_shifter::PitchShifter;
 use crate::dsp::modules::yin_detector::detector::PitchDetector;
-use crate::dsp::modules::yin_detector::detector::yin::YINDetector;
+use crate::dsp::modules::yin_detector::detector::pyin::PYINDetector;
 use crate::dsp::traits::EffectModule;
 use std::collections::VecDeque;
-use crate::dsp::modules::utils::effect_parameter::{EffectParameter, ParameterValue};
+use crate::dsp::modules::utils::effect_parameter::EffectParameter;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Scale {
@@ -22,26 +22,42 @@ pub enum Scale {
 }
 
 impl Scale {
-    pub fn get_notes(&self) -> Vec<u8> {
+    /// Pitch classes of the scale - an array, so the audio thread can check it without allocating.
+    pub fn pitch_classes(&self) -> [u8; 7] {
         match self {
-            Scale::CMajor => vec![0, 2, 4, 5, 7, 9, 11], // C D E F G A B
-            Scale::AMajor => vec![9, 11, 0, 2, 4, 5, 7], // A B C# D E F# G#
-            Scale::GMajor => vec![7, 9, 11, 0, 2, 4, 5], // G A B C D E F#
-            Scale::DMajor => vec![2, 4, 6, 7, 9, 11, 0], // D E F# G A B C#
-            Scale::EMajor => vec![4, 6, 8, 9, 11, 1, 3], // E F# G# A B C# D#
-            Scale::FMajor => vec![5, 7, 9, 10, 0, 2, 4], // F G A Bb C D E
-            Scale::GMinor => vec![7, 9, 10, 0, 2, 3, 5], // G A Bb C D Eb F
-            Scale::DMinor => vec![2, 4, 5, 7, 9, 10, 0], // D E F G A Bb C
-            Scale::AMinor => vec![9, 11, 0, 2, 4, 5, 7], // A B C D E F G
-            Scale::EMinor => vec![4, 6, 7, 9, 11, 0, 2], // E F# G A B C D
+            Scale::CMajor => [0, 2, 4, 5, 7, 9, 11], // C D E F G A B
+            Scale::AMajor => [9, 11, 0, 2, 4, 5, 7], // A B C# D E F# G#
+            Scale::GMajor => [7, 9, 11, 0, 2, 4, 5], // G A B C D E F#
+            Scale::DMajor => [2, 4, 6, 7, 9, 11, 0], // D E F# G A B C#
+            Scale::EMajor => [4, 6, 8, 9, 11, 1, 3], // E F# G# A B C# D#
+            Scale::FMajor => [5, 7, 9, 10, 0, 2, 4], // F G A Bb C D E
+            Scale::GMinor => [7, 9, 10, 0, 2, 3, 5], // G A Bb C D Eb F
+            Scale::DMinor => [2, 4, 5, 7, 9, 10, 0], // D E F G A Bb C
+            Scale::AMinor => [9, 11, 0, 2, 4, 5, 7], // A B C D E F G
+            Scale::EMinor => [4, 6, 7, 9, 11, 0, 2], // E F# G A B C D
         }
     }
+
+    pub fn get_notes(&self) -> Vec<u8> {
+        self.pitch_classes().to_vec()
+    }
+
+    /// Nearest note of the scale to a (fractional) MIDI note number.
+    pub fn quantize(&self, note: f32) -> f32 {
+        let notes = self.pitch_classes();
+        let rounded = note.round();
+        (0..=6)
+            .flat_map(|offset| [rounded - offset as f32, rounded + offset as f32])
+            .filter(|candidate| notes.contains(&((*candidate as i32).rem_euclid(12) as u8)))
+            .min_by(|a, b| (a - note).abs().total_cmp(&(b - note).abs()))
+            .unwrap_or(rounded)
+    }
 }
 
 pub struct AutoTune {
     sample_rate: f32,
     pitch_shifter: PitchShifter,
-    yin_detector: YINDetector<f32>,
+    pitch_detector: PYINDetector<f32>,
     detection_buffer: VecDeque<f32>,
 
     // Parametry autotune
@@ -61,7 +77,9 @@ pub struct AutoTune {
 
 impl AutoTune {
     pub fn new(sample_rate: f32) -> Self {
-        let pitch_shifter = PitchShifter::new(30, sample_rate as usize, 0.0, 8);
+        let mut pitch_shifter = PitchShifter::new(30, sample_rate as usize, 0.0, 8);
+        // correction_speed already glides the shift
+        pitch_shifter.set_smoothing_time(0.0);
 
         let detection_window_size = 1536;
 
@@ -75,7 +93,7 @@ impl AutoTune {
             last_valid_shift: 0.0,
             sustain_counter: 0,
             max_sustain: (sample_rate * 0.2) as usize,
-            yin_detector: YINDetector::new(detection_window_size, detection_window_size / 2),
+            pitch_detector: PYINDetector::new(detection_window_size, detection_window_size / 2),
             power_threshold: EffectParameter::new("power_threshold", 0.05, 0.0, 1.0),
             clarity_threshold: EffectParameter::new("clarity_threshold", 0.3, 0.0, 1.0),
             scale: Scale::CMajor,
@@ -105,7 +123,7 @@ impl AutoTune {
     pub fn set_detection_window_size(&mut self, size: usize) {
         self.detection_window_size.set_value(size as f32);
         self.detection_buffer = VecDeque::with_capacity(size);
-        self.yin_detector = YINDetector::new(size, size / 2);
+        self.pitch_detector = PYINDetector::new(size, size / 2);
     }
 
     fn detect_pitch(&mut self) -> Option<f32> {
@@ -115,7 +133,7 @@ impl AutoTune {
 
         let buffer: Vec<f32> = self.detection_buffer.iter().map(|&s| s as f32).collect();
 
-        match self.yin_detector.get_pitch(
+        match self.pitch_detector.get_pitch(
             &buffer,
             self.sample_rate as usize,
             self.power_threshold.value,
@@ -137,7 +155,7 @@ impl AutoTune {
         // Snap to configured scale
         let note_in_octave = (note_num.round() as i32 % 12 + 12) % 12;
 
-        let is_in_scale = self.scale.get_notes().contains(&(note_in_octave as u8));
+        let is_in_scale = self.scale.pitch_classes().contains(&(note_in_octave as u8));
 
         let target_note = if is_in_scale {
             note_num.round()
@@ -151,7 +169,7 @@ impl AutoTune {
             for offset in check_offsets {
                 let candidate = note_num.round() + offset;
                 let cand_oct = (candidate as i32 % 12 + 12) % 12;
-                if self.scale.get_notes().contains(&(cand_oct as u8)) {
+                if self.scale.pitch_classes().contains(&(cand_oct as u8)) {
                     // Check 'true' distance from float note_num
                     let dist = (candidate - note_num).abs();
                     if dist < min_dist {
@@ -222,6 +240,7 @@ impl EffectModule for AutoTune {
         self.current_shift = 0.0;
         self.last_valid_shift = 0.0;
         self.sustain_counter = 0;
+        self.pitch_detector.reset();
         self.pitch_shifter.reset();
     }
 
@@ -234,25 +253,25 @@ impl EffectModule for AutoTune {
         ]
     }
 
-    fn set_parameter(&mut self, parameter: ParameterValue) -> anyhow::Result<()> {
-        match parameter.name.as_str() {
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+        match name {
             "correction_speed" => {
-                self.set_correction_speed(parameter.value);
+                self.set_correction_speed(value);
                 Ok(())
             }
             "detection_window_size" => {
-                self.set_detection_window_size(parameter.value as usize);
+                self.set_detection_window_size(value as usize);
                 Ok(())
             }
             "power_threshold" => {
-                self.set_power_threshold(parameter.value);
+                self.set_power_threshold(value);
                 Ok(())
             }
             "clarity_threshold" => {
-                self.set_clarity_threshold(parameter.value);
+                self.set_clarity_threshold(value);
                 Ok(())
             }
-            _ => Err(anyhow::anyhow!("Unknown parameter: {}", parameter.name)),
+            _ => Err(anyhow::anyhow!("Unknown parameter: {}", name)),
         }
     }
 
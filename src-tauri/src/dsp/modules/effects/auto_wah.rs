@@ -0,0 +1,213 @@
+use crate::dsp::modules::filters::{BandPassFilter, LowPassFilter};
+use crate::dsp::modules::utils::effect_parameter::EffectParameter;
+use crate::dsp::modules::utils::envelope_follower::EnvelopeFollower;
+use crate::dsp::modules::utils::lfo::LFO;
+use crate::dsp::modules::utils::smoothed_parameter::SmoothedParameter;
+use crate::dsp::traits::EffectModule;
+
+/// Highest sweep frequency as a share of the sample rate - keeps the filter stable.
+const MAX_FREQUENCY_RATIO: f32 = 0.45;
+
+#[derive(Debug, Clone)]
+pub struct AutoWah {
+    name: String,
+    mode: EffectParameter,        // 0 = envelope, 1 = LFO (manual wah)
+    sensitivity: EffectParameter, // envelope gain before it drives the sweep
+    attack_ms: EffectParameter,
+    release_ms: EffectParameter,
+    frequency: EffectParameter, // sweep start in Hz
+    range: EffectParameter,     // sweep width in octaves
+    q: EffectParameter,
+    direction: EffectParameter,   // 0 = up with level, 1 = down
+    filter_type: EffectParameter, // 0 = band-pass, 1 = low-pass
+    rate: EffectParameter,        // LFO rate in Hz
+    mix: SmoothedParameter,
+    envelope: EnvelopeFollower,
+    lfo: LFO,
+    band_pass: BandPassFilter,
+    low_pass: LowPassFilter,
+    sample_rate: f32,
+}
+
+impl AutoWah {
+    /// Creates a new auto-wah - a resonant filter swept by the input level or an LFO.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate` - Sample rate in Hz
+    ///
+    /// # Returns
+    ///
+    /// A new `AutoWah` instance in envelope mode with a band-pass filter.
+    pub fn new(sample_rate: usize) -> Self {
+        let sample_rate = sample_rate as f32;
+        let mut effect = Self {
+            name: "auto_wah".to_string(),
+            mode: EffectParameter::new("mode", 0.0, 0.0, 1.0),
+            sensitivity: EffectParameter::new("sensitivity", 4.0, 0.0, 20.0),
+            attack_ms: EffectParameter::new("attack_ms", 5.0, 0.1, 200.0),
+            release_ms: EffectParameter::new("release_ms", 80.0, 1.0, 1000.0),
+            frequency: EffectParameter::new("frequency", 300.0, 50.0, 2000.0),
+            range: EffectParameter::new("range", 3.0, 0.0, 5.0),
+            q: EffectParameter::new("q", 4.0, 0.5, 10.0),
+            direction: EffectParameter::new("direction", 0.0, 0.0, 1.0),
+            filter_type: EffectParameter::new("filter_type", 0.0, 0.0, 1.0),
+            rate: EffectParameter::new("rate", 1.5, 0.1, 10.0),
+            mix: SmoothedParameter::new("mix", 1.0, 0.0, 1.0, sample_rate),
+            envelope: EnvelopeFollower::new(sample_rate, 5.0, 80.0),
+            lfo: LFO::new(1.5, 1.0, sample_rate),
+            band_pass: BandPassFilter::new(sample_rate, 300.0, 75.0),
+            low_pass: LowPassFilter::new(sample_rate, 300.0, 4.0),
+            sample_rate,
+        };
+        effect.update_envelope();
+        effect
+    }
+
+    fn update_envelope(&mut self) {
+        self.envelope
+            .set_times(self.attack_ms.value, self.release_ms.value);
+    }
+
+    fn is_lfo_mode(&self) -> bool {
+        self.mode.value >= 0.5
+    }
+
+    fn is_low_pass(&self) -> bool {
+        self.filter_type.value >= 0.5
+    }
+
+    /// Sweep position (0.0-1.0) for the next sample.
+    fn next_sweep(&mut self, sample: f32) -> f32 {
+        // The follower keeps running in LFO mode so switching back does not jump
+        let level = self.envelope.process(sample);
+        let sweep = if self.is_lfo_mode() {
+            (self.lfo.process() + 1.0) * 0.5
+        } else {
+            (level * self.sensitivity.value).min(1.0)
+        };
+
+        if self.direction.value >= 0.5 {
+            1.0 - sweep
+        } else {
+            sweep
+        }
+    }
+
+    pub fn process_internal(&mut self, input: &[f32], output: &mut [f32]) {
+        let max_frequency = self.sample_rate * MAX_FREQUENCY_RATIO;
+        let low_pass = self.is_low_pass();
+
+        for (i, &sample) in input.iter().enumerate() {
+            let sweep = self.next_sweep(sample);
+            let frequency = (self.frequency.value * 2.0_f32.powf(self.range.value * sweep)).min(max_frequency);
+
+            let wet = if low_pass {
+                self.low_pass.set_cutoff(frequency);
+                self.low_pass.process_internal(sample)
+            } else {
+                self.band_pass.set_center_freq(frequency);
+                self.band_pass.set_bandwidth(frequency / self.q.value);
+                self.band_pass.process_internal(sample, self.sample_rate)
+            };
+
+            let mix = self.mix.next_value();
+            output[i] = sample * (1.0 - mix) + wet * mix;
+        }
+    }
+}
+
+impl EffectModule for AutoWah {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        self.process_internal(input, output);
+    }
+
+    fn reset(&mut self) {
+        self.envelope.reset();
+        self.lfo.reset();
+        self.band_pass.reset();
+        self.low_pass.reset();
+        self.mix.snap();
+    }
+
+    fn get_parameters(&self) -> Vec<EffectParameter> {
+        vec![
+            self.mode.clone(),
+            self.sensitivity.clone(),
+            self.attack_ms.clone(),
+            self.release_ms.clone(),
+            self.frequency.clone(),
+            self.range.clone(),
+            self.q.clone(),
+            self.direction.clone(),
+            self.filter_type.clone(),
+            self.rate.clone(),
+            self.mix.parameter().clone(),
+        ]
+    }
+
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+        match name {
+            "mode" => self.mode.set_value(value),
+            "sensitivity" => self.sensitivity.set_value(value),
+            "attack_ms" => {
+                self.attack_ms.set_value(value);
+                self.update_envelope();
+            }
+            "release_ms" => {
+                self.release_ms.set_value(value);
+                self.update_envelope();
+            }
+            "frequency" => self.frequency.set_value(value),
+            "range" => self.range.set_value(value),
+            "q" => {
+                self.q.set_value(value);
+                self.low_pass.set_resonance(self.q.value);
+            }
+            "direction" => self.direction.set_value(value),
+            "filter_type" => self.filter_type.set_value(value),
+            "rate" => {
+                self.rate.set_value(value);
+                self.lfo.set_frequency(self.rate.value);
+            }
+            "mix" => self.mix.set_value(value),
+            _ => return Err(anyhow::anyhow!("Unknown parameter: {}", name)),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Output over input level for a 2 kHz tone - high once the cutoff has swept past it
+    fn gain_at_level(amplitude: f32) -> f32 {
+        let mut wah = AutoWah::new(48_000);
+        wah.set_parameter_value("filter_type", 1.0).unwrap();
+        wah.reset();
+
+        let input: Vec<f32> = (0..24_000)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * 2_000.0 * i as f32 / 48_000.0).sin())
+            .collect();
+        let mut output = vec![0.0; input.len()];
+        wah.process(&input, &mut output);
+
+        // Skip the attack, the envelope has settled after that
+        let rms = |samples: &[f32]| (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+        rms(&output[4_800..]) / rms(&input[4_800..])
+    }
+
+    #[test]
+    fn cutoff_follows_the_input_envelope() {
+        // Loud input opens the low-pass up to 2.4 kHz, a quiet one leaves it near 300 Hz
+        let loud = gain_at_level(0.5);
+        let quiet = gain_at_level(0.01);
+        assert!(loud > 0.7, "loud gain {}", loud);
+        assert!(quiet < 0.1, "quiet gain {}", quiet);
+    }
+}
@@ -1,7 +1,8 @@
 use crate::dsp::traits::EffectModule;
 use crate::dsp::modules::utils::effect_parameter::EffectParameter;
+use crate::dsp::modules::utils::smoothed_parameter::SmoothedParameter;
 pub struct Bitcrusher {
-    bit_depth: EffectParameter,
+    bit_depth: SmoothedParameter,
     sample_rate_reduction: usize,
     counter: usize,
     last_sample: f32,
@@ -9,17 +10,18 @@ pub struct Bitcrusher {
 
 
 impl Bitcrusher {
-    pub fn new(bit_depth: f32, sample_rate_reduction: usize) -> Self {
+    pub fn new(bit_depth: f32, sample_rate_reduction: usize, sample_rate: f32) -> Self {
         Self {
-            bit_depth: EffectParameter::new("bit_depth", bit_depth, 1.0, 16.0),
+            bit_depth: SmoothedParameter::new("bit_depth", bit_depth, 1.0, 16.0, sample_rate),
             sample_rate_reduction,
             counter: 0,
             last_sample: 0.0,
         }
     }
 
+    // Whole bits only, a glide steps through the depths in between
     fn process_sample(&mut self, sample: f32) -> f32 {
-        let max_amplitude = (1 << (self.bit_depth.value as u32 - 1)) as f32;
+        let max_amplitude = (1 << (self.bit_depth.next_value() as u32 - 1)) as f32;
         (sample * max_amplitude).round() / max_amplitude
     }
 }
@@ -37,6 +39,8 @@ impl EffectModule for Bitcrusher {
             if self.counter % self.sample_rate_reduction == 0 {
                 let quantized = self.process_sample(sample);
                 self.last_sample = quantized;
+            } else {
+                self.bit_depth.next_value();
             }
             output[i] = self.last_sample;
             self.counter += 1;
@@ -46,22 +50,23 @@ impl EffectModule for Bitcrusher {
     fn reset(&mut self) {
         self.counter = 0;
         self.last_sample = 0.0;
+        self.bit_depth.snap();
     }
     fn name(&self) -> &str {
         "bitcrusher"
     }
 
     fn get_parameters(&self) -> Vec<EffectParameter> {
-        vec![self.bit_depth.clone()]
+        vec![self.bit_depth.parameter().clone()]
     }
 
-    fn set_parameter(&mut self, parameter: crate::dsp::modules::utils::ParameterValue) -> anyhow::Result<()> {
-        match parameter.name.as_str() {
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+        match name {
             "bit_depth" => {
-                self.bit_depth.set_value(parameter.value);
+                self.bit_depth.set_value(value);
                 Ok(())
             }
-            _ => Err(anyhow::anyhow!("Unknown parameter: {}", parameter.name)),
+            _ => Err(anyhow::anyhow!("Unknown parameter: {}", name)),
         }
     }
 }
\ No newline at end of file
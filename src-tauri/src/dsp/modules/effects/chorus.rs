@@ -1,15 +1,21 @@
 use crate::dsp::modules::filters::DelayLine;
 use crate::dsp::modules::utils::effect_parameter::EffectParameter;
 use crate::dsp::modules::utils::lfo::LFO;
+use crate::dsp::modules::utils::smoothed_parameter::SmoothedParameter;
 use crate::dsp::traits::EffectModule;
 
+// The right side's LFO runs a quarter cycle ahead, so the two delays never move together
+const RIGHT_LFO_PHASE: f32 = 0.25;
+
 #[derive(Debug, Clone)]
 pub struct Chorus {
     name: String,
-    depth: EffectParameter,
-    mix: EffectParameter,
+    depth: SmoothedParameter,
+    mix: SmoothedParameter,
     delay_line: DelayLine,
     lfo: LFO,
+    delay_line_right: DelayLine,
+    lfo_right: LFO,
     sample_rate: f32,
 }
 
@@ -28,16 +34,19 @@ impl Chorus {
         depth: f32,
         mix: f32,
     ) -> Self {
-        let effect = Self {
+        let mut lfo_right = LFO::new(0.5, 0.5, sample_rate as f32);
+        lfo_right.set_phase(RIGHT_LFO_PHASE);
+
+        Self {
             name: "chorus".to_string(),
-            depth: EffectParameter::new("depth", depth, 0.0, 1.0),
-            mix: EffectParameter::new("mix", mix, 0.0, 1.0),
+            depth: SmoothedParameter::new("depth", depth, 0.0, 1.0, sample_rate as f32),
+            mix: SmoothedParameter::new("mix", mix, 0.0, 1.0, sample_rate as f32),
             delay_line: DelayLine::new(1024, 512.0, 0.5),
             lfo: LFO::new(0.5, 0.5, sample_rate as f32),
+            delay_line_right: DelayLine::new(1024, 512.0, 0.5),
+            lfo_right,
             sample_rate: sample_rate as f32,
-        };
-
-        effect
+        }
     }
 
     pub fn process_internal(&mut self, input: &[f32], output: &mut [f32], sample_rate: f32) {
@@ -45,12 +54,29 @@ impl Chorus {
 
         for (i, sample) in input.iter().enumerate() {
             let lfo_value = self.lfo.process();
-            let delay_samples = 20.0 + lfo_value * self.depth.value * 10.0; // Variable delay
+            let delay_samples = 20.0 + lfo_value * self.depth.next_value() * 10.0; // Variable delay
 
             self.delay_line.set_delay(delay_samples);
             let delayed = self.delay_line.process_internal(*sample);
 
-            output[i] = *sample * (1.0 - self.mix.value) + delayed * self.mix.value;
+            let mix = self.mix.next_value();
+            output[i] = *sample * (1.0 - mix) + delayed * mix;
+        }
+    }
+
+    // Each side has its own delay line and LFO, so left and right come out decorrelated
+    pub fn process_stereo_internal(&mut self, in_l: &[f32], in_r: &[f32], out_l: &mut [f32], out_r: &mut [f32]) {
+        for (i, (&left, &right)) in in_l.iter().zip(in_r).enumerate() {
+            let depth = self.depth.next_value() * 10.0;
+            let mix = self.mix.next_value();
+
+            self.delay_line.set_delay(20.0 + self.lfo.process() * depth);
+            self.delay_line_right.set_delay(20.0 + self.lfo_right.process() * depth);
+            let delayed_l = self.delay_line.process_internal(left);
+            let delayed_r = self.delay_line_right.process_internal(right);
+
+            out_l[i] = left * (1.0 - mix) + delayed_l * mix;
+            out_r[i] = right * (1.0 - mix) + delayed_r * mix;
         }
     }
 }
@@ -64,26 +90,64 @@ impl EffectModule for Chorus {
         self.process_internal(input, output, self.sample_rate);
     }
 
+    fn process_stereo(&mut self, in_l: &[f32], in_r: &[f32], out_l: &mut [f32], out_r: &mut [f32]) {
+        self.process_stereo_internal(in_l, in_r, out_l, out_r);
+    }
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
     fn reset(&mut self) {
         self.delay_line.clear();
         self.lfo.reset();
+        self.delay_line_right.clear();
+        self.lfo_right.set_phase(RIGHT_LFO_PHASE);
+        self.depth.snap();
+        self.mix.snap();
     }
 
     fn get_parameters(&self) -> Vec<EffectParameter> {
-        vec![self.depth.clone(), self.mix.clone()]
+        vec![self.depth.parameter().clone(), self.mix.parameter().clone()]
     }
 
-    fn set_parameter(&mut self, parameter: crate::dsp::modules::utils::ParameterValue) -> anyhow::Result<()> {
-        match parameter.name.as_str() {
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+        match name {
             "depth" => {
-                self.depth.set_value(parameter.value);
+                self.depth.set_value(value);
                 Ok(())
             }
             "mix" => {
-                self.mix.set_value(parameter.value);
+                self.mix.set_value(value);
                 Ok(())
             }
-            _ => Err(anyhow::anyhow!("Unknown parameter: {}", parameter.name)),
+            _ => Err(anyhow::anyhow!("Unknown parameter: {}", name)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stereo_output(chorus: &mut Chorus) -> (Vec<f32>, Vec<f32>) {
+        let input: Vec<f32> = (0..48_000).map(|i| (i as f32 * 0.05).sin()).collect();
+        let (mut out_l, mut out_r) = (vec![0.0; input.len()], vec![0.0; input.len()]);
+        chorus.process_stereo(&input, &input, &mut out_l, &mut out_r);
+        (out_l, out_r)
+    }
+
+    #[test]
+    fn sides_are_modulated_out_of_phase() {
+        let mut chorus = Chorus::new(48_000, 1.0, 0.5);
+        let (out_l, out_r) = stereo_output(&mut chorus);
+        let difference = out_l.iter().zip(&out_r).map(|(l, r)| (l - r).abs()).fold(0.0, f32::max);
+        assert!(difference > 0.05, "difference {}", difference);
+
+        // With both LFOs on the same phase nothing else tells the sides apart
+        let mut chorus = Chorus::new(48_000, 1.0, 0.5);
+        chorus.lfo_right.set_phase(0.0);
+        let (out_l, out_r) = stereo_output(&mut chorus);
+        assert_eq!(out_l, out_r);
+    }
+}
@@ -1,27 +1,28 @@
 use crate::dsp::traits::EffectModule;
 use crate::dsp::modules::utils::effect_parameter::EffectParameter;
+use crate::dsp::modules::utils::smoothed_parameter::SmoothedParameter;
 
 pub struct Distortion {
-    gain: EffectParameter,
+    gain: SmoothedParameter,
 }
 
 impl Distortion {
-    pub fn new(gain: f32) -> Self {
-        Self { gain: EffectParameter::new("gain", gain, 0.0, 50.0) }
+    pub fn new(gain: f32, sample_rate: f32) -> Self {
+        Self { gain: SmoothedParameter::new("gain", gain, 0.0, 50.0, sample_rate) }
     }
 }
 
 impl EffectModule for Distortion {
     fn process(&mut self, input: &[f32], output: &mut [f32]) {
         for (i, &sample) in input.iter().enumerate() {
-            let driven = sample * self.gain.value;
+            let driven = sample * self.gain.next_value();
             let distorted = driven.tanh();
             output[i] = distorted;
         }
     }
 
     fn reset(&mut self) {
-        // No internal state to reset for the amplifier
+        self.gain.snap();
     }
 
     fn name(&self) -> &str {
@@ -29,16 +30,16 @@ impl EffectModule for Distortion {
     }
 
     fn get_parameters(&self) -> Vec<EffectParameter> {
-        vec![self.gain.clone()]
+        vec![self.gain.parameter().clone()]
     }
 
-    fn set_parameter(&mut self, parameter: crate::dsp::modules::utils::ParameterValue) -> anyhow::Result<()> {
-        match parameter.name.as_str() {
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+        match name {
             "gain" => {
-                self.gain.set_value(parameter.value);
+                self.gain.set_value(value);
                 Ok(())
             }
-            _ => Err(anyhow::anyhow!("Unknown parameter: {}", parameter.name)),
+            _ => Err(anyhow::anyhow!("Unknown parameter: {}", name)),
         }
     }
 }
\ No newline at end of file
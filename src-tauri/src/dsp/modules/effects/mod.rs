@@ -7,6 +7,9 @@ pub mod chorus;
 pub mod vocoder;
 pub mod auto_tune;
 pub mod pitch_shifter;
+pub mod auto_wah;
+pub mod panner;
+pub mod stereo_widener;
 
 pub use vibrato::Vibrato;
 pub use bitcrusher::Bitcrusher;
@@ -17,4 +20,7 @@ pub use chorus::Chorus;
 pub use vocoder::Vocoder;
 pub use auto_tune::AutoTune;
 pub use pitch_shifter::PitchShifter;
+pub use auto_wah::AutoWah;
+pub use panner::Panner;
+pub use stereo_widener::StereoWidener;
 pub use auto_tune::Scale;
\ No newline at end of file
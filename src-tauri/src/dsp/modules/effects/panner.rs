@@ -0,0 +1,67 @@
+use crate::dsp::traits::EffectModule;
+use crate::dsp::modules::utils::effect_parameter::EffectParameter;
+use crate::dsp::modules::utils::smoothed_parameter::SmoothedParameter;
+
+/// Places the voice between left and right with a constant-power law.
+/// Scaled so the centre leaves the signal untouched - a centred voice keeps its
+/// loudness across the whole sweep. The price is +3 dB on the side it moves towards:
+/// stereo material already near full scale can clip when panned hard.
+/// Mono chains have nothing to pan and pass through.
+pub struct Panner {
+    pan: SmoothedParameter, // -1.0 hard left, 0.0 centre, 1.0 hard right
+}
+
+impl Panner {
+    pub fn new(sample_rate: f32) -> Self {
+        Self { pan: SmoothedParameter::new("pan", 0.0, -1.0, 1.0, sample_rate) }
+    }
+
+    fn gains(pan: f32) -> (f32, f32) {
+        let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+        (
+            angle.cos() * std::f32::consts::SQRT_2,
+            angle.sin() * std::f32::consts::SQRT_2,
+        )
+    }
+}
+
+impl EffectModule for Panner {
+    fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        output[..input.len()].copy_from_slice(input);
+        self.pan.advance(input.len());
+    }
+
+    fn process_stereo(&mut self, in_l: &[f32], in_r: &[f32], out_l: &mut [f32], out_r: &mut [f32]) {
+        for (i, (&left, &right)) in in_l.iter().zip(in_r).enumerate() {
+            let (gain_l, gain_r) = Self::gains(self.pan.next_value());
+            out_l[i] = left * gain_l;
+            out_r[i] = right * gain_r;
+        }
+    }
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
+    fn reset(&mut self) {
+        self.pan.snap();
+    }
+
+    fn name(&self) -> &str {
+        "panner"
+    }
+
+    fn get_parameters(&self) -> Vec<EffectParameter> {
+        vec![self.pan.parameter().clone()]
+    }
+
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+        match name {
+            "pan" => {
+                self.pan.set_value(value);
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!("Unknown parameter: {}", name)),
+        }
+    }
+}
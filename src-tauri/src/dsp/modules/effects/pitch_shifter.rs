@@ -8,7 +8,8 @@ use crate::dsp::traits::EffectModule;
 
 use std::f32::consts::PI;
 use std::f32::consts::TAU; // = 2xPI
-use crate::dsp::modules::utils::effect_parameter::{EffectParameter, ParameterValue};
+use crate::dsp::modules::utils::effect_parameter::EffectParameter;
+use crate::dsp::modules::utils::smoothed_parameter::SmoothedParameter;
 
 const COMPLEX_ZERO: Complex<f32> = Complex::new(0.0, 0.0);
 
@@ -36,7 +37,7 @@ pub struct PitchShifter {
     sample_rate: usize,
 
     // Configuration parameters
-    shift: EffectParameter,
+    shift: SmoothedParameter,
     over_sampling: usize,
 }
 
@@ -83,7 +84,7 @@ impl PitchShifter {
             overlap: 0,
             sample_rate,
 
-            shift: EffectParameter::new("shift", shift, -12.0, 12.0),
+            shift: SmoothedParameter::new("shift", shift, -12.0, 12.0, sample_rate as f32),
             over_sampling,
         }
     }
@@ -93,12 +94,18 @@ impl PitchShifter {
         self.shift.set_value(shift);    
     }
 
+    // Callers that already glide the shift themselves (auto-tune) can turn this off
+    pub fn set_smoothing_time(&mut self, time_ms: f32) {
+        self.shift.set_smoothing_time(time_ms);
+    }
+
     pub fn shift_pitch(
         &mut self,
         in_b: &[f32],
         out_b: &mut [f32],
     ) {
-        let shift = 2.0_f32.powf(self.shift.value / 12.0);
+        // The shift is fixed for the whole block, so the ramp moves in block sized steps
+        let shift = 2.0_f32.powf(self.shift.advance(out_b.len()) / 12.0);
         let fs_real = self.frame_size as f32;
         let half_frame_size = (self.frame_size / 2) + 1;
 
@@ -205,7 +212,8 @@ impl EffectModule for PitchShifter {
         self.synthesized_frequency.fill(0.0);
         self.synthesized_magnitude.fill(0.0);
         self.overlap = 0;
-        self.shift = EffectParameter::new("shift", 0.0, -12.0, 12.0);
+        self.shift.set_value(0.0);
+        self.shift.snap();
     }
 
     fn name(&self) -> &str {
@@ -213,16 +221,16 @@ impl EffectModule for PitchShifter {
     }
 
     fn get_parameters(&self) -> Vec<EffectParameter> {
-        vec![self.shift.clone()]
+        vec![self.shift.parameter().clone()]
     }
 
-    fn set_parameter(&mut self, parameter: ParameterValue) -> anyhow::Result<()> {
-        match parameter.name.as_str() {
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+        match name {
             "shift" => {
-                self.set_shift(parameter.value);
+                self.set_shift(value);
                 Ok(())
             }
-            _ => Err(anyhow::anyhow!("Unknown parameter: {}", parameter.name)),
+            _ => Err(anyhow::anyhow!("Unknown parameter: {}", name)),
         }
     }
 }
\ No newline at end of file
@@ -4,38 +4,44 @@
 //! for enhanced audio experience.
 
 use crate::dsp::traits::EffectModule;
-use crate::dsp::modules::utils::effect_parameter::{EffectParameter, ParameterValue};
+use crate::dsp::modules::utils::effect_parameter::EffectParameter;
+use crate::dsp::modules::utils::smoothed_parameter::SmoothedParameter;
 use crate::dsp::modules::filters::*;
 
+// The right tank's delays are this much longer than the left's, which decorrelates
+// the two tails (23 samples at 44.1 kHz, as in Freeverb)
+const STEREO_SPREAD_SECONDS: f32 = 23.0 / 44_100.0;
 
 pub struct Reverb {
     enabled: bool,
-    room_size: EffectParameter, // Room size (0.0 - 1.0)
-    damping: EffectParameter,   // High frequency damping
-    wet_level: EffectParameter, // Wet signal level
-    dry_level: EffectParameter, // Dry signal level
-    width: EffectParameter,     // Stereo width
+    room_size: SmoothedParameter, // Room size (0.0 - 1.0)
+    damping: SmoothedParameter,   // High frequency damping
+    wet_level: SmoothedParameter, // Wet signal level
+    dry_level: SmoothedParameter, // Dry signal level
+    width: SmoothedParameter,     // Stereo width - 0 sums both tails, 1 keeps them apart
 
     all_pass_filters: Vec<AllPassFilter>,
     comb_filters: Vec<CombFilter>,
+    all_pass_filters_right: Vec<AllPassFilter>,
+    comb_filters_right: Vec<CombFilter>,
     sample_rate: u32,
-    channels: usize,
 }
 
 impl Reverb {
-    pub fn new(sample_rate: u32, channels: usize) -> Self {
+    pub fn new(sample_rate: u32) -> Self {
         let mut reverb = Self {
             enabled: true,
-            room_size: EffectParameter::new("room_size", 0.5, 0.0, 1.0),
-            damping: EffectParameter::new("damping", 0.5, 0.0, 1.0),
-            wet_level: EffectParameter::new("wet_level", 0.3, 0.0, 1.0),
-            dry_level: EffectParameter::new("dry_level", 0.7, 0.0, 1.0),
-            width: EffectParameter::new("width", 1.0, 0.0, 1.0),
+            room_size: SmoothedParameter::new("room_size", 0.5, 0.0, 1.0, sample_rate as f32),
+            damping: SmoothedParameter::new("damping", 0.5, 0.0, 1.0, sample_rate as f32),
+            wet_level: SmoothedParameter::new("wet_level", 0.3, 0.0, 1.0, sample_rate as f32),
+            dry_level: SmoothedParameter::new("dry_level", 0.7, 0.0, 1.0, sample_rate as f32),
+            width: SmoothedParameter::new("width", 1.0, 0.0, 1.0, sample_rate as f32),
 
             all_pass_filters: Vec::new(),
             comb_filters: Vec::new(),
+            all_pass_filters_right: Vec::new(),
+            comb_filters_right: Vec::new(),
             sample_rate,
-            channels
         };
 
         reverb.initialize_filters();
@@ -65,29 +71,51 @@ impl Reverb {
             (sample_rate * 0.0109) as usize,
         ];
 
+        let spread = (sample_rate * STEREO_SPREAD_SECONDS) as usize;
+
         // Initialize comb filters
         for &delay in &comb_delays {
             self.comb_filters.push(CombFilter::new(delay, 0.84, 0.2));
+            self.comb_filters_right.push(CombFilter::new(delay + spread, 0.84, 0.2));
         }
 
         // Initialize all-pass filters
         for &delay in &allpass_delays {
             self.all_pass_filters.push(AllPassFilter::new(delay, 0.5));
+            self.all_pass_filters_right.push(AllPassFilter::new(delay + spread, 0.5));
         }
 
         self.update_parameters();
     }
 
+    // Comb settings follow the ramps once per block - per sample would be wasted work
     fn update_parameters(&mut self) {
-        let room_scale = self.room_size.value * 0.28 + 0.7;
-        let damping = self.damping.value * 0.4;
+        let room_scale = self.room_size.current() * 0.28 + 0.7;
+        let damping = self.damping.current() * 0.4;
 
-        for comb in &mut self.comb_filters {
+        for comb in self.comb_filters.iter_mut().chain(self.comb_filters_right.iter_mut()) {
             comb.set_feedback(room_scale);
             comb.set_damping(damping);
         }
     }
 
+    // Parallel combs into serial all-passes - one tank
+    fn process_tank(combs: &mut [CombFilter], all_passes: &mut [AllPassFilter], input: f32) -> f32 {
+        let mut wet: f32 = combs.iter_mut().map(|comb| comb.process_internal(input)).sum();
+        for allpass in all_passes {
+            wet = allpass.process_internal(wet);
+        }
+        wet
+    }
+
+    fn advance_block_parameters(&mut self, frames: usize) {
+        if self.room_size.is_smoothing() || self.damping.is_smoothing() {
+            self.room_size.advance(frames);
+            self.damping.advance(frames);
+            self.update_parameters();
+        }
+    }
+
     pub fn set_room_size(&mut self, size: f32) {
         self.room_size.set_value(size);
         self.update_parameters();
@@ -105,6 +133,10 @@ impl Reverb {
     pub fn set_dry_level(&mut self, level: f32) {
         self.dry_level.set_value(level);
     }
+
+    pub fn set_width(&mut self, width: f32) {
+        self.width.set_value(width);
+    }
 }
 
 impl EffectModule for Reverb {
@@ -113,97 +145,137 @@ impl EffectModule for Reverb {
             return;
         }
 
-        let channels = self.channels;
+        self.advance_block_parameters(in_b.len());
 
-        if channels == 1 {
-            // Mono processing
-            for (i, sample) in in_b.iter().enumerate() {
-                let input = *sample;
+        // Mono chains only hear the left tank
+        for (i, sample) in in_b.iter().enumerate() {
+            let input = *sample;
+            let wet = Self::process_tank(&mut self.comb_filters, &mut self.all_pass_filters, input);
 
-                // Process through comb filters
-                let mut comb_sum = 0.0;
-                for comb in &mut self.comb_filters {
-                    comb_sum += comb.process_internal(input);
-                }
+            // Mix dry and wet signals
+            out_b[i] = input * self.dry_level.next_value() + wet * self.wet_level.next_value();
+        }
+    }
 
-                // Process through all-pass filters
-                let mut wet = comb_sum;
-                for allpass in &mut self.all_pass_filters {
-                    wet = allpass.process_internal(wet);
-                }
+    fn process_stereo(&mut self, in_l: &[f32], in_r: &[f32], out_l: &mut [f32], out_r: &mut [f32]) {
+        if !self.enabled {
+            out_l[..in_l.len()].copy_from_slice(in_l);
+            out_r[..in_r.len()].copy_from_slice(in_r);
+            return;
+        }
 
-                // Mix dry and wet signals
-                out_b[i] = input * self.dry_level.value + wet * self.wet_level.value;
-            }
-        } else {
-            // Stereo processing
-            for i in (0..in_b.len()).step_by(2) {
-                let left = in_b[i];
-                let right = in_b[i + 1];
-
-                // Mix to mono for reverb input
-                let mono = (left + right) * 0.5;
-
-                // Process through comb filters
-                let mut comb_sum = 0.0;
-                for comb in &mut self.comb_filters {
-                    comb_sum += comb.process_internal(mono);
-                }
-
-                // Process through all-pass filters
-                let mut wet = comb_sum;
-                for allpass in &mut self.all_pass_filters {
-                    wet = allpass.process_internal(wet);
-                }
-
-                // Create stereo wet signal
-                let wet_left = wet * self.wet_level.value;
-                let wet_right = wet * self.wet_level.value * self.width.value;
-
-                // Mix with dry signal
-                out_b[i] = left * self.dry_level.value + wet_left;
-                out_b[i + 1] = right * self.dry_level.value + wet_right;
-            }
+        self.advance_block_parameters(in_l.len());
+
+        for (i, (&left, &right)) in in_l.iter().zip(in_r).enumerate() {
+            // Both tanks are fed the same mono input, their different delays decorrelate the tails
+            let mono = (left + right) * 0.5;
+            let tail_l = Self::process_tank(&mut self.comb_filters, &mut self.all_pass_filters, mono);
+            let tail_r = Self::process_tank(&mut self.comb_filters_right, &mut self.all_pass_filters_right, mono);
+
+            // Each side gets mostly its own tail and, as width drops, more of the other one
+            let wet_level = self.wet_level.next_value();
+            let width = self.width.next_value();
+            let own = wet_level * (width * 0.5 + 0.5);
+            let other = wet_level * (1.0 - width) * 0.5;
+
+            let dry_level = self.dry_level.next_value();
+            out_l[i] = left * dry_level + tail_l * own + tail_r * other;
+            out_r[i] = right * dry_level + tail_r * own + tail_l * other;
         }
     }
 
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
     fn reset(&mut self) {
-        for allpass in &mut self.all_pass_filters {
+        for allpass in self.all_pass_filters.iter_mut().chain(self.all_pass_filters_right.iter_mut()) {
             allpass.reset();
         }
-        for comb in &mut self.comb_filters {
+        for comb in self.comb_filters.iter_mut().chain(self.comb_filters_right.iter_mut()) {
             comb.reset();
         }
+        self.room_size.snap();
+        self.damping.snap();
+        self.wet_level.snap();
+        self.dry_level.snap();
+        self.width.snap();
+        self.update_parameters();
     }
 
     fn name(&self) -> &str {
         "reverb"
     }
 
-    fn set_parameter(&mut self, parameter: ParameterValue) -> anyhow::Result<()> {
-        match parameter.name.as_str() {
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+        match name {
             "room_size" => {
-                self.set_room_size(parameter.value);
+                self.set_room_size(value);
                 Ok(())
             }
             "damping" => {
-                self.set_damping(parameter.value);
+                self.set_damping(value);
                 Ok(())
             }
             "wet_level" => {
-                self.set_wet_level(parameter.value);
+                self.set_wet_level(value);
                 Ok(())
             }
             "dry_level" => {
-                self.set_dry_level(parameter.value);
+                self.set_dry_level(value);
                 Ok(())
             }
-            _ => Err(anyhow::anyhow!("Unknown parameter: {}", parameter.name)),
+            "width" => {
+                self.set_width(value);
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!("Unknown parameter: {}", name)),
         }
     }
 
     fn get_parameters(&self) -> Vec<EffectParameter> {
-        vec![self.room_size.clone(), self.damping.clone(), self.wet_level.clone(), self.dry_level.clone()]
+        vec![
+            self.room_size.parameter().clone(),
+            self.damping.parameter().clone(),
+            self.wet_level.parameter().clone(),
+            self.dry_level.parameter().clone(),
+            self.width.parameter().clone(),
+        ]
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Correlation of the two tails for a centred noise burst
+    fn tail_correlation(width: f32) -> f32 {
+        let mut reverb = Reverb::new(48_000);
+        reverb.set_parameter_value("dry_level", 0.0).unwrap();
+        reverb.set_parameter_value("wet_level", 1.0).unwrap();
+        reverb.set_parameter_value("width", width).unwrap();
+        reverb.reset();
+
+        let mut seed = 1u32;
+        let noise: Vec<f32> = (0..48_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+            })
+            .collect();
+        let (mut out_l, mut out_r) = (vec![0.0; noise.len()], vec![0.0; noise.len()]);
+        reverb.process_stereo(&noise, &noise, &mut out_l, &mut out_r);
+
+        let dot = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+        dot(&out_l, &out_r) / (dot(&out_l, &out_l) * dot(&out_r, &out_r)).sqrt()
+    }
+
+    #[test]
+    fn tails_are_decorrelated() {
+        let wide = tail_correlation(1.0);
+        assert!(wide.abs() < 0.5, "correlation {}", wide);
+        // Zero width mixes both tails into each side
+        assert!(tail_correlation(0.0) > 0.99);
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+
+use crate::dsp::traits::EffectModule;
+use crate::dsp::modules::utils::effect_parameter::EffectParameter;
+use crate::dsp::modules::utils::smoothed_parameter::SmoothedParameter;
+
+// Past about 35 ms the delayed side is heard as an echo instead of as width
+const MAX_HAAS_MS: f32 = 30.0;
+
+/// Widens the stereo image in two ways:
+/// - mid-side: scales the difference between left and right (0 = mono, 1 = unchanged, 2 = twice as wide)
+/// - Haas: delays the right side by a few ms, which spreads a centred mono voice
+pub struct StereoWidener {
+    width: SmoothedParameter,
+    haas_ms: SmoothedParameter,
+    right_history: VecDeque<f32>, // newest sample at the back
+    sample_rate: f32,
+}
+
+impl StereoWidener {
+    pub fn new(sample_rate: f32) -> Self {
+        let capacity = (MAX_HAAS_MS * 0.001 * sample_rate) as usize + 2;
+        Self {
+            width: SmoothedParameter::new("width", 1.2, 0.0, 2.0, sample_rate),
+            haas_ms: SmoothedParameter::new("haas_ms", 12.0, 0.0, MAX_HAAS_MS, sample_rate),
+            right_history: VecDeque::from(vec![0.0; capacity]),
+            sample_rate,
+        }
+    }
+
+    // Right side `delay` samples ago, linearly interpolated between whole samples
+    fn delayed_right(&mut self, input: f32, delay: f32) -> f32 {
+        self.right_history.pop_front();
+        self.right_history.push_back(input);
+
+        let newest = self.right_history.len() - 1;
+        let whole = (delay.floor() as usize).min(newest - 1);
+        let frac = delay - whole as f32;
+        let near = self.right_history[newest - whole];
+        let far = self.right_history[newest - whole - 1];
+        near + (far - near) * frac
+    }
+}
+
+impl EffectModule for StereoWidener {
+    fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        output[..input.len()].copy_from_slice(input);
+        self.width.advance(input.len());
+        self.haas_ms.advance(input.len());
+    }
+
+    fn process_stereo(&mut self, in_l: &[f32], in_r: &[f32], out_l: &mut [f32], out_r: &mut [f32]) {
+        for (i, (&left, &right)) in in_l.iter().zip(in_r).enumerate() {
+            let mid = (left + right) * 0.5;
+            let side = (left - right) * 0.5 * self.width.next_value();
+
+            let delay = self.haas_ms.next_value() * 0.001 * self.sample_rate;
+            out_l[i] = mid + side;
+            out_r[i] = self.delayed_right(mid - side, delay);
+        }
+    }
+
+    fn is_stereo(&self) -> bool {
+        true
+    }
+
+    fn reset(&mut self) {
+        self.right_history.iter_mut().for_each(|sample| *sample = 0.0);
+        self.width.snap();
+        self.haas_ms.snap();
+    }
+
+    fn name(&self) -> &str {
+        "stereo_widener"
+    }
+
+    fn get_parameters(&self) -> Vec<EffectParameter> {
+        vec![self.width.parameter().clone(), self.haas_ms.parameter().clone()]
+    }
+
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+        match name {
+            "width" => {
+                self.width.set_value(value);
+                Ok(())
+            }
+            "haas_ms" => {
+                self.haas_ms.set_value(value);
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!("Unknown parameter: {}", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn widener(width: f32, haas_ms: f32) -> StereoWidener {
+        let mut widener = StereoWidener::new(48_000.0);
+        widener.set_parameter_value("width", width).unwrap();
+        widener.set_parameter_value("haas_ms", haas_ms).unwrap();
+        widener.reset();
+        widener
+    }
+
+    #[test]
+    fn zero_width_collapses_to_mono() {
+        let mut widener = widener(0.0, 0.0);
+        let (in_l, in_r) = ([1.0, 0.5, -0.25, 0.0], [0.0, 0.5, 0.75, -1.0]);
+        let (mut out_l, mut out_r) = ([0.0; 4], [0.0; 4]);
+        widener.process_stereo(&in_l, &in_r, &mut out_l, &mut out_r);
+        assert_eq!(out_l, [0.5, 0.5, 0.25, -0.5]);
+        assert_eq!(out_l, out_r);
+    }
+
+    #[test]
+    fn haas_delays_the_right_side() {
+        // 1 ms at 48 kHz - the right side hears the click 48 samples late
+        let mut widener = widener(1.0, 1.0);
+        let mut click = [0.0; 64];
+        click[0] = 1.0;
+        let (mut out_l, mut out_r) = ([0.0; 64], [0.0; 64]);
+        widener.process_stereo(&click, &click, &mut out_l, &mut out_r);
+        assert_eq!(out_l, click);
+        // The delay in samples comes out of float maths, so the click may smear by a hair
+        assert!((out_r[48] - 1.0).abs() < 1e-3, "{}", out_r[48]);
+        assert_eq!(out_r.iter().filter(|&&sample| sample.abs() > 1e-3).count(), 1);
+    }
+}
@@ -1,12 +1,13 @@
 use crate::dsp::modules::filters::DelayLine;
-use crate::dsp::modules::utils::effect_parameter::{EffectParameter, ParameterValue};
+use crate::dsp::modules::utils::effect_parameter::EffectParameter;
 use crate::dsp::modules::utils::lfo::LFO;
+use crate::dsp::modules::utils::smoothed_parameter::SmoothedParameter;
 use crate::dsp::traits::EffectModule;
 
 #[derive(Debug, Clone)]
 pub struct Vibrato {
     name: String,
-    intensity: EffectParameter,
+    intensity: SmoothedParameter,
     lfo: LFO,
     delay_line: DelayLine,
     sample_rate: f32,
@@ -28,7 +29,7 @@ impl Vibrato {
             lfo: LFO::new(rate, depth, sample_rate),
             delay_line: DelayLine::new(1024, 512.0, 0.0), // No feedback for vibrato
             sample_rate,
-            intensity: EffectParameter::new("intensity", intensity, 0.0, 1.0),
+            intensity: SmoothedParameter::new("intensity", intensity, 0.0, 1.0, sample_rate),
         };
 
         effect
@@ -37,9 +38,8 @@ impl Vibrato {
     fn process_internal(&mut self, input: &[f32], output: &mut [f32], sample_rate: f32) {
         self.sample_rate = sample_rate;
 
-        let intensity = self.intensity.value;
-
         for (i, sample) in input.iter().enumerate() {
+            let intensity = self.intensity.next_value();
             let lfo_value = self.lfo.process();
             let delay_samples = 10.0 + lfo_value * 5.0; // Variable delay for pitch modulation
 
@@ -63,19 +63,20 @@ impl EffectModule for Vibrato {
     fn reset(&mut self) {
         self.delay_line.clear();
         self.lfo.reset();
+        self.intensity.snap();
     }
 
-    fn set_parameter(&mut self, parameter: ParameterValue) -> anyhow::Result<()> {
-        match parameter.name.as_str() {
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+        match name {
             "intensity" => {
-                self.intensity.value = parameter.value.clamp(self.intensity.min_value, self.intensity.max_value);
+                self.intensity.set_value(value);
                 Ok(())
             }
-            _ => Err(anyhow::anyhow!("Unknown parameter: {}", parameter.name)),
+            _ => Err(anyhow::anyhow!("Unknown parameter: {}", name)),
         }
     }
 
     fn get_parameters(&self) -> Vec<EffectParameter> {
-        vec![self.intensity.clone()]
+        vec![self.intensity.parameter().clone()]
     }
 }
@@ -1,9 +1,77 @@
+use crate::dsp::modules::carrier::{Carrier, CarrierSource};
 use crate::dsp::modules::filters::BandPassFilter;
-use crate::dsp::modules::effects::Reverb;
-use crate::dsp::modules::utils::{EffectParameter, ParameterValue};
+use crate::dsp::modules::effects::{Reverb, Scale};
+use crate::dsp::modules::utils::{EffectParameter, PitchTracker, SmoothedParameter};
+use crate::dsp::modules::utils::{NoiseOscillator, PulseOscillator, SawOscillator, SupersawOscillator};
 use crate::dsp::modules::utils::oscilator::Oscillator;
 use crate::dsp::traits::{EffectModule, FilterModule};
 
+// The harmonics bank sums its sines at carrier_gain / n - around 3.5x carrier_gain
+// with the default harmonics. Full scale external carriers and noise are brought to the same level.
+const EXTERNAL_CARRIER_SCALE: f32 = 3.5;
+/// Most MIDI notes played at once by the carrier - the lowest ones win.
+const MAX_CHORD_NOTES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CarrierTracking {
+	Fixed, // carrier_base_freq
+	Pitch, // detected voice pitch
+	Midi,  // held MIDI notes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CarrierWaveform {
+	Harmonics, // sine per harmonic, carrier_harmonics of them
+	Saw,
+	Pulse,
+	Supersaw,
+}
+
+// One band-limited oscillator per carrier fundamental
+#[derive(Debug, Clone)]
+enum CarrierVoice {
+	Saw(SawOscillator),
+	Pulse(PulseOscillator),
+	Supersaw(SupersawOscillator),
+}
+
+impl CarrierVoice {
+	fn process(&mut self, sample_rate: f32) -> f32 {
+		match self {
+			CarrierVoice::Saw(osc) => osc.process(sample_rate),
+			CarrierVoice::Pulse(osc) => osc.process(sample_rate),
+			CarrierVoice::Supersaw(osc) => osc.process(sample_rate),
+		}
+	}
+
+	fn set_frequency(&mut self, frequency: f32) {
+		match self {
+			CarrierVoice::Saw(osc) => osc.set_frequency(frequency),
+			CarrierVoice::Pulse(osc) => osc.set_frequency(frequency),
+			CarrierVoice::Supersaw(osc) => osc.set_frequency(frequency),
+		}
+	}
+
+	fn set_amplitude(&mut self, amplitude: f32) {
+		match self {
+			CarrierVoice::Saw(osc) => osc.set_amplitude(amplitude),
+			CarrierVoice::Pulse(osc) => osc.set_amplitude(amplitude),
+			CarrierVoice::Supersaw(osc) => osc.set_amplitude(amplitude),
+		}
+	}
+
+	fn reset(&mut self) {
+		match self {
+			CarrierVoice::Saw(osc) => osc.reset(),
+			CarrierVoice::Pulse(osc) => osc.reset(),
+			CarrierVoice::Supersaw(osc) => osc.reset(),
+		}
+	}
+}
+
+fn note_to_frequency(note: f32) -> f32 {
+	440.0 * 2.0_f32.powf((note - 69.0) / 12.0)
+}
 
 // This was implemented BY AI
 pub struct Vocoder {
@@ -15,21 +83,39 @@ pub struct Vocoder {
 	q: EffectParameter,
 	attack_ms: EffectParameter,
 	release_ms: EffectParameter,
-	output_gain: EffectParameter,
-	dry_mix: EffectParameter,
-	env_gain: EffectParameter,
-	env_floor: EffectParameter,
-	soft_clip: EffectParameter,
-	reverb_mix: EffectParameter,
+	output_gain: SmoothedParameter,
+	dry_mix: SmoothedParameter,
+	env_gain: SmoothedParameter,
+	env_floor: SmoothedParameter,
+	soft_clip: SmoothedParameter,
+	reverb_mix: SmoothedParameter,
 	reverb: Reverb,
 	reverb_buffer: Vec<f32>,
 	carrier_base_freq: EffectParameter,
 	carrier_harmonics: EffectParameter,
-	carrier_gain: EffectParameter,
+	carrier_gain: SmoothedParameter,
+	carrier_waveform: EffectParameter,
+	pulse_width: EffectParameter,
+	supersaw_detune: EffectParameter,
+	noise_mix: SmoothedParameter,
+	carrier_tracking: EffectParameter,
+	quantize: EffectParameter,
+	tracking_octave: EffectParameter,
+	tracking_glide_ms: EffectParameter,
+	scale: Scale,
+	pitch_tracker: PitchTracker,
+	// Fundamentals the oscillator bank plays right now, one per chord note - the first `chord_size` are used
+	carrier_fundamentals: [f32; MAX_CHORD_NOTES],
+	chord_size: usize,
 	mod_filters: Vec<BandPassFilter>,
 	car_filters: Vec<BandPassFilter>,
 	envelopes: Vec<f32>,
 	carrier_oscillators: Vec<Oscillator>,
+	active_oscillators: usize, // harmonics of the notes sounding now
+	carrier_voices: Vec<CarrierVoice>, // MAX_CHORD_NOTES of the current waveform, built off the audio thread
+	active_voices: usize,
+	carrier_noise: NoiseOscillator,
+	external_carrier: Option<Box<dyn Carrier>>,
 	attack_coeff: f32,
 	release_coeff: f32,
 }
@@ -45,21 +131,38 @@ impl Vocoder {
 			q: EffectParameter::new("q", 9.0, 0.5, 30.0),
 			attack_ms: EffectParameter::new("attack_ms", 2.0, 0.1, 200.0),
 			release_ms: EffectParameter::new("release_ms", 50.0, 1.0, 500.0),
-			output_gain: EffectParameter::new("output_gain", 4.0, 0.0, 20.0),
-			dry_mix: EffectParameter::new("dry_mix", 0.2, 0.0, 1.0),
-			env_gain: EffectParameter::new("env_gain", 11.0, 0.0, 30.0),
-			env_floor: EffectParameter::new("env_floor", 0.0015, 0.0, 0.1),
-			soft_clip: EffectParameter::new("soft_clip", 1.6, 0.0, 4.0),
-			reverb_mix: EffectParameter::new("reverb_mix", 0.08, 0.0, 1.0),
-			reverb: Reverb::new(sample_rate as u32, 1),
+			output_gain: SmoothedParameter::new("output_gain", 4.0, 0.0, 20.0, sample_rate as f32),
+			dry_mix: SmoothedParameter::new("dry_mix", 0.2, 0.0, 1.0, sample_rate as f32),
+			env_gain: SmoothedParameter::new("env_gain", 11.0, 0.0, 30.0, sample_rate as f32),
+			env_floor: SmoothedParameter::new("env_floor", 0.0015, 0.0, 0.1, sample_rate as f32),
+			soft_clip: SmoothedParameter::new("soft_clip", 1.6, 0.0, 4.0, sample_rate as f32),
+			reverb_mix: SmoothedParameter::new("reverb_mix", 0.08, 0.0, 1.0, sample_rate as f32),
+			reverb: Reverb::new(sample_rate as u32),
 			reverb_buffer: Vec::new(),
 			carrier_base_freq: EffectParameter::new("carrier_base_freq", 110.0, 20.0, 2_000.0),
 			carrier_harmonics: EffectParameter::new("carrier_harmonics", 18.0, 1.0, 64.0),
-			carrier_gain: EffectParameter::new("carrier_gain", 0.22, 0.0, 1.0),
+			carrier_gain: SmoothedParameter::new("carrier_gain", 0.22, 0.0, 1.0, sample_rate as f32),
+			carrier_waveform: EffectParameter::new("carrier_waveform", 0.0, 0.0, 3.0),
+			pulse_width: EffectParameter::new("pulse_width", 0.5, 0.05, 0.95),
+			supersaw_detune: EffectParameter::new("supersaw_detune", 0.35, 0.0, 1.0),
+			noise_mix: SmoothedParameter::new("noise_mix", 0.0, 0.0, 1.0, sample_rate as f32),
+			carrier_tracking: EffectParameter::new("carrier_tracking", 0.0, 0.0, 2.0),
+			quantize: EffectParameter::new("quantize", 0.0, 0.0, 1.0),
+			tracking_octave: EffectParameter::new("tracking_octave", 0.0, -2.0, 2.0),
+			tracking_glide_ms: EffectParameter::new("tracking_glide_ms", 30.0, 0.0, 500.0),
+			scale: Scale::CMajor,
+			pitch_tracker: PitchTracker::new(sample_rate),
+			carrier_fundamentals: [0.0; MAX_CHORD_NOTES],
+			chord_size: 0,
 			mod_filters: Vec::new(),
 			car_filters: Vec::new(),
 			envelopes: Vec::new(),
 			carrier_oscillators: Vec::new(),
+			active_oscillators: 0,
+			carrier_voices: Vec::new(),
+			active_voices: 0,
+			carrier_noise: NoiseOscillator::new(1.0),
+			external_carrier: None,
 			attack_coeff: 0.0,
 			release_coeff: 0.0,
 		};
@@ -92,6 +195,19 @@ impl Vocoder {
 		self.update_env_coeffs();
 		self.rebuild_filters();
 		self.rebuild_carrier();
+		// Settings made while building start right away instead of gliding in
+		self.snap_smoothed();
+	}
+
+	fn snap_smoothed(&mut self) {
+		self.output_gain.snap();
+		self.dry_mix.snap();
+		self.env_gain.snap();
+		self.env_floor.snap();
+		self.carrier_gain.snap();
+		self.soft_clip.snap();
+		self.reverb_mix.snap();
+		self.noise_mix.snap();
 	}
 
 	fn update_env_coeffs(&mut self) {
@@ -120,12 +236,149 @@ impl Vocoder {
 	}
 
 	fn rebuild_carrier(&mut self) {
-		self.carrier_oscillators.clear();
+		// Tracking modes keep their fundamentals, the next block retunes them anyway
+		let tracking = self.tracking();
+		if tracking == CarrierTracking::Fixed
+			|| (tracking == CarrierTracking::Pitch && self.chord_size == 0)
+		{
+			self.carrier_fundamentals[0] = self.carrier_base_freq.value;
+			self.chord_size = 1;
+		}
+		if self.waveform() != CarrierWaveform::Harmonics && self.carrier_voices.len() != MAX_CHORD_NOTES {
+			self.carrier_voices = (0..MAX_CHORD_NOTES)
+				.map(|_| self.new_voice(self.carrier_base_freq.value))
+				.collect();
+		}
+		self.retune_carrier();
+	}
+
+	// One oscillator (or harmonic series) per fundamental. The bank is sized for the largest chord,
+	// so a chord change on the audio thread only retunes - oscillators are reallocated when
+	// carrier_harmonics changes. The bank runs at unit gain, carrier_gain is applied per sample so it ramps
+	fn retune_carrier(&mut self) {
+		if self.waveform() != CarrierWaveform::Harmonics {
+			self.retune_voices();
+			return;
+		}
+		self.active_voices = 0;
+
 		let harmonics = self.carrier_harmonics.value.round() as usize;
-		for i in 1..=harmonics {
-			let freq = self.carrier_base_freq.value * i as f32;
-			let amp = self.carrier_gain.value / i as f32;
-			self.carrier_oscillators.push(Oscillator::new(freq, amp));
+		if self.carrier_oscillators.len() != harmonics * MAX_CHORD_NOTES {
+			self.carrier_oscillators = vec![Oscillator::new(1.0, 0.0); harmonics * MAX_CHORD_NOTES];
+		}
+		self.active_oscillators = harmonics * self.chord_size;
+
+		let nyquist = self.sample_rate * 0.5;
+		let voice_gain = 1.0 / (self.chord_size as f32).sqrt().max(1.0);
+		for (voice, &fundamental) in self.carrier_fundamentals[..self.chord_size].iter().enumerate() {
+			for i in 1..=harmonics {
+				let freq = fundamental * i as f32;
+				let amp = if freq < nyquist {
+					voice_gain / i as f32
+				} else {
+					0.0
+				};
+				let osc = &mut self.carrier_oscillators[voice * harmonics + i - 1];
+				osc.set_frequency(freq);
+				osc.set_amplitude(amp);
+			}
+		}
+	}
+
+	// Voices were built by `rebuild_carrier`, a chord change only retunes them
+	fn retune_voices(&mut self) {
+		self.active_oscillators = 0;
+		self.active_voices = self.chord_size.min(self.carrier_voices.len());
+
+		// A naive saw is pi/2 times the 1/n sine series - same level as the harmonics bank
+		let voice_gain = 1.0 / (self.chord_size as f32).sqrt().max(1.0);
+		let amplitude = std::f32::consts::FRAC_PI_2 * voice_gain;
+		for (voice, &fundamental) in self.carrier_voices.iter_mut().zip(&self.carrier_fundamentals[..self.active_voices]) {
+			voice.set_frequency(fundamental);
+			voice.set_amplitude(amplitude);
+		}
+	}
+
+	fn new_voice(&self, fundamental: f32) -> CarrierVoice {
+		match self.waveform() {
+			CarrierWaveform::Pulse => {
+				CarrierVoice::Pulse(PulseOscillator::new(fundamental, 0.0, self.pulse_width.value))
+			}
+			CarrierWaveform::Supersaw => {
+				CarrierVoice::Supersaw(SupersawOscillator::new(fundamental, 0.0, self.supersaw_detune.value))
+			}
+			_ => CarrierVoice::Saw(SawOscillator::new(fundamental, 0.0)),
+		}
+	}
+
+	fn waveform(&self) -> CarrierWaveform {
+		match self.carrier_waveform.value.round() as i32 {
+			0 => CarrierWaveform::Harmonics,
+			2 => CarrierWaveform::Pulse,
+			3 => CarrierWaveform::Supersaw,
+			_ => CarrierWaveform::Saw,
+		}
+	}
+
+	fn tracking(&self) -> CarrierTracking {
+		match self.carrier_tracking.value.round() as i32 {
+			1 => CarrierTracking::Pitch,
+			2 => CarrierTracking::Midi,
+			_ => CarrierTracking::Fixed,
+		}
+	}
+
+	fn tracked_frequency(&self, detected: f32) -> f32 {
+		let mut note = 12.0 * (detected / 440.0).log2() + 69.0;
+		if self.quantize.value >= 0.5 {
+			note = self.scale.quantize(note);
+		}
+		note_to_frequency(note + 12.0 * self.tracking_octave.value.round())
+	}
+
+	// Moves the oscillator bank towards the voice pitch or the held notes, once per block.
+	// Runs on the audio thread - fixed arrays only
+	fn update_carrier_tracking(&mut self, input: &[f32]) {
+		let mut targets = [0.0; MAX_CHORD_NOTES];
+		let count = match self.tracking() {
+			CarrierTracking::Fixed => return,
+			CarrierTracking::Pitch => {
+				targets[0] = match self.pitch_tracker.process_block(input) {
+					Some(detected) => self.tracked_frequency(detected),
+					None => self.carrier_base_freq.value,
+				};
+				1
+			}
+			CarrierTracking::Midi => {
+				let mut notes = [0u8; MAX_CHORD_NOTES];
+				let count = crate::midi::notes::HELD_NOTES.lowest(&mut notes);
+				for (target, &note) in targets.iter_mut().zip(&notes[..count]) {
+					*target = note_to_frequency(note as f32);
+				}
+				count
+			}
+		};
+
+		self.glide_fundamentals(&targets[..count], input.len());
+		self.retune_carrier();
+	}
+
+	fn glide_fundamentals(&mut self, targets: &[f32], block_len: usize) {
+		if targets.len() == self.chord_size {
+			// Glide in the log domain so every interval takes the same time
+			let glide_samples = self.tracking_glide_ms.value * 0.001 * self.sample_rate;
+			let coeff = if glide_samples > 0.0 {
+				(-(block_len as f32) / glide_samples).exp()
+			} else {
+				0.0
+			};
+			for (current, target) in self.carrier_fundamentals.iter_mut().zip(targets) {
+				*current = target * (*current / target).powf(coeff);
+			}
+		} else {
+			// A new chord shape starts on pitch
+			self.carrier_fundamentals[..targets.len()].copy_from_slice(targets);
+			self.chord_size = targets.len();
 		}
 	}
 
@@ -146,11 +399,28 @@ impl Vocoder {
 	}
 
 	fn next_carrier_sample(&mut self) -> f32 {
-		let mut sample = 0.0;
-		for osc in &mut self.carrier_oscillators {
-			sample += osc.process(self.sample_rate);
+		let carrier_gain = self.carrier_gain.next_value();
+		let full_scale = carrier_gain * EXTERNAL_CARRIER_SCALE;
+		let tone = if let Some(carrier) = self.external_carrier.as_mut() {
+			carrier.next_sample() * full_scale
+		} else {
+			let mut sample = 0.0;
+			for osc in &mut self.carrier_oscillators[..self.active_oscillators] {
+				sample += osc.process(self.sample_rate);
+			}
+			for voice in &mut self.carrier_voices[..self.active_voices] {
+				sample += voice.process(self.sample_rate);
+			}
+			sample * carrier_gain
+		};
+
+		// Noise fills the high bands the tone lacks, so "s" and "t" stay intelligible
+		let noise_mix = self.noise_mix.next_value();
+		if noise_mix > 0.0 {
+			tone * (1.0 - noise_mix) + self.carrier_noise.process() * full_scale * noise_mix
+		} else {
+			tone
 		}
-		sample
 	}
 
 	fn update_envelope(&mut self, index: usize, input: f32) -> f32 {
@@ -171,6 +441,9 @@ impl EffectModule for Vocoder {
 			return;
 		}
 		let band_scale = 1.0 / (self.mod_filters.len() as f32).sqrt().max(1.0);
+		if self.external_carrier.is_none() {
+			self.update_carrier_tracking(&input[..len]);
+		}
 
 		for out in &mut output[..len] {
 			*out = 0.0;
@@ -179,33 +452,36 @@ impl EffectModule for Vocoder {
 		for i in 0..len {
 			let mod_sample = input[i];
 			let carrier_sample = self.next_carrier_sample();
+			let env_gain = self.env_gain.next_value();
+			let env_floor = self.env_floor.next_value();
 			let mut acc = 0.0;
 
 			for band in 0..self.mod_filters.len() {
 				let filtered_mod = self.mod_filters[band].process(mod_sample);
 				let env = self.update_envelope(band, filtered_mod.abs());
-				let env = (env * self.env_gain.value + self.env_floor.value).clamp(0.0, 1.0);
+				let env = (env * env_gain + env_floor).clamp(0.0, 1.0);
 				let filtered_carrier = self.car_filters[band].process(carrier_sample);
 				acc += filtered_carrier * env * band_scale;
 			}
 
-			let mixed = acc * self.output_gain.value + mod_sample * self.dry_mix.value;
-			output[i] = if self.soft_clip.value > 0.0 {
-				(mixed * self.soft_clip.value).tanh()
+			let mixed = acc * self.output_gain.next_value() + mod_sample * self.dry_mix.next_value();
+			let drive = self.soft_clip.next_value();
+			output[i] = if drive > 0.0 {
+				(mixed * drive).tanh()
 			} else {
 				mixed
 			};
 		}
 
-		if self.reverb_mix.value > 0.0 {
+		// Keep running while the mix fades out so turning it off does not click
+		if self.reverb_mix.target() > 0.0 || self.reverb_mix.is_smoothing() {
 			if self.reverb_buffer.len() != len {
 				self.reverb_buffer.resize(len, 0.0);
 			}
 			self.reverb.process(&output[..len], &mut self.reverb_buffer);
-			let wet = self.reverb_mix.value.clamp(0.0, 1.0);
-			let dry = 1.0 - wet;
 			for i in 0..len {
-				output[i] = output[i] * dry + self.reverb_buffer[i] * wet;
+				let wet = self.reverb_mix.next_value();
+				output[i] = output[i] * (1.0 - wet) + self.reverb_buffer[i] * wet;
 			}
 		}
 	}
@@ -223,88 +499,173 @@ impl EffectModule for Vocoder {
 		for osc in &mut self.carrier_oscillators {
 			osc.reset();
 		}
+		for voice in &mut self.carrier_voices {
+			voice.reset();
+		}
+		if let Some(carrier) = self.external_carrier.as_mut() {
+			carrier.reset();
+		}
+		self.pitch_tracker.reset();
 		self.reverb.reset();
+		self.snap_smoothed();
 	}
 
 	fn name(&self) -> &str {
 		&self.name
 	}
 
-	fn set_parameter(&mut self, parameter: ParameterValue) -> anyhow::Result<()> {
-		match parameter.name.as_str() {
+	fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()> {
+		match name {
 			"band_count" => {
-				self.band_count.set_value(parameter.value);
+				self.band_count.set_value(value);
 				self.rebuild_filters();
 				Ok(())
 			}
 			"min_freq" => {
-				self.min_freq.set_value(parameter.value);
+				self.min_freq.set_value(value);
 				self.rebuild_filters();
 				Ok(())
 			}
 			"max_freq" => {
-				self.max_freq.set_value(parameter.value);
+				self.max_freq.set_value(value);
 				self.rebuild_filters();
 				Ok(())
 			}
 			"q" => {
-				self.q.set_value(parameter.value);
+				self.q.set_value(value);
 				self.rebuild_filters();
 				Ok(())
 			}
 			"attack_ms" => {
-				self.attack_ms.set_value(parameter.value);
+				self.attack_ms.set_value(value);
 				self.update_env_coeffs();
 				Ok(())
 			}
 			"release_ms" => {
-				self.release_ms.set_value(parameter.value);
+				self.release_ms.set_value(value);
 				self.update_env_coeffs();
 				Ok(())
 			}
 			"output_gain" => {
-				self.output_gain.set_value(parameter.value);
+				self.output_gain.set_value(value);
 				Ok(())
 			}
 			"dry_mix" => {
-				self.dry_mix.set_value(parameter.value);
+				self.dry_mix.set_value(value);
 				Ok(())
 			}
 			"env_gain" => {
-				self.env_gain.set_value(parameter.value);
+				self.env_gain.set_value(value);
 				Ok(())
 			}
 			"env_floor" => {
-				self.env_floor.set_value(parameter.value);
+				self.env_floor.set_value(value);
 				Ok(())
 			}
 			"soft_clip" => {
-				self.soft_clip.set_value(parameter.value);
+				self.soft_clip.set_value(value);
 				Ok(())
 			}
 			"reverb_mix" => {
-				self.reverb_mix.set_value(parameter.value);
+				self.reverb_mix.set_value(value);
 				Ok(())
 			}
 			"carrier_base_freq" => {
-				self.carrier_base_freq.set_value(parameter.value);
+				self.carrier_base_freq.set_value(value);
 				self.rebuild_carrier();
 				Ok(())
 			}
 			"carrier_harmonics" => {
-				self.carrier_harmonics.set_value(parameter.value);
+				self.carrier_harmonics.set_value(value);
 				self.rebuild_carrier();
 				Ok(())
 			}
 			"carrier_gain" => {
-				self.carrier_gain.set_value(parameter.value);
+				self.carrier_gain.set_value(value);
+				Ok(())
+			}
+			"carrier_waveform" => {
+				self.carrier_waveform.set_value(value);
+				// Voices of the old waveform cannot be retuned into the new one
+				self.carrier_voices.clear();
 				self.rebuild_carrier();
 				Ok(())
 			}
-			_ => Err(anyhow::anyhow!("Unknown parameter: {}", parameter.name)),
+			"pulse_width" => {
+				self.pulse_width.set_value(value);
+				for voice in &mut self.carrier_voices {
+					if let CarrierVoice::Pulse(osc) = voice {
+						osc.set_width(self.pulse_width.value);
+					}
+				}
+				Ok(())
+			}
+			"supersaw_detune" => {
+				self.supersaw_detune.set_value(value);
+				for voice in &mut self.carrier_voices {
+					if let CarrierVoice::Supersaw(osc) = voice {
+						osc.set_detune(self.supersaw_detune.value);
+					}
+				}
+				Ok(())
+			}
+			"noise_mix" => {
+				self.noise_mix.set_value(value);
+				Ok(())
+			}
+			"carrier_tracking" => {
+				self.carrier_tracking.set_value(value);
+				self.rebuild_carrier();
+				Ok(())
+			}
+			"quantize" => {
+				self.quantize.set_value(value);
+				Ok(())
+			}
+			"tracking_octave" => {
+				self.tracking_octave.set_value(value);
+				Ok(())
+			}
+			"tracking_glide_ms" => {
+				self.tracking_glide_ms.set_value(value);
+				Ok(())
+			}
+			_ => Err(anyhow::anyhow!("Unknown parameter: {}", name)),
 		}
 	}
 
+	// These rebuild the filter bank or reallocate the carrier on every write
+	fn is_modulatable(&self, name: &str) -> bool {
+		!matches!(
+			name,
+			"band_count" | "min_freq" | "max_freq" | "q" | "carrier_harmonics" | "carrier_waveform" | "carrier_tracking"
+		)
+	}
+
+	// Scale the tracked pitch snaps to when `quantize` is on
+	fn set_scale(&mut self, scale: Scale) -> anyhow::Result<()> {
+		self.scale = scale;
+		Ok(())
+	}
+
+	fn get_scale(&self) -> Option<Scale> {
+		Some(self.scale)
+	}
+
+	fn set_carrier(&mut self, carrier: Option<Box<dyn Carrier>>) -> anyhow::Result<()> {
+		self.external_carrier = carrier;
+		Ok(())
+	}
+
+	fn get_carrier_source(&self) -> Option<CarrierSource> {
+		Some(
+			self.external_carrier
+				.as_ref()
+				.map(|carrier| carrier.source())
+				.unwrap_or_default(),
+		)
+	}
+
 	fn get_parameters(&self) -> Vec<EffectParameter> {
 		vec![
 			self.band_count.clone(),
@@ -313,15 +674,102 @@ impl EffectModule for Vocoder {
 			self.q.clone(),
 			self.attack_ms.clone(),
 			self.release_ms.clone(),
-			self.output_gain.clone(),
-			self.dry_mix.clone(),
-			self.env_gain.clone(),
-			self.env_floor.clone(),
-			self.soft_clip.clone(),
-			self.reverb_mix.clone(),
+			self.output_gain.parameter().clone(),
+			self.dry_mix.parameter().clone(),
+			self.env_gain.parameter().clone(),
+			self.env_floor.parameter().clone(),
+			self.soft_clip.parameter().clone(),
+			self.reverb_mix.parameter().clone(),
 			self.carrier_base_freq.clone(),
 			self.carrier_harmonics.clone(),
-			self.carrier_gain.clone(),
+			self.carrier_gain.parameter().clone(),
+			self.carrier_waveform.clone(),
+			self.pulse_width.clone(),
+			self.supersaw_detune.clone(),
+			self.noise_mix.parameter().clone(),
+			self.carrier_tracking.clone(),
+			self.quantize.clone(),
+			self.tracking_octave.clone(),
+			self.tracking_glide_ms.clone(),
 		]
 	}
 }
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn set(vocoder: &mut Vocoder, name: &str, value: f32) {
+		vocoder.set_parameter_value(name, value).unwrap();
+	}
+
+	#[test]
+	fn carrier_follows_the_voice_pitch() {
+		let sample_rate = 48_000;
+		let mut vocoder = Vocoder::new(sample_rate);
+		set(&mut vocoder, "carrier_tracking", 1.0);
+		set(&mut vocoder, "tracking_glide_ms", 0.0);
+
+		let voice: Vec<f32> = (0..8_192)
+			.map(|i| (2.0 * std::f32::consts::PI * 230.0 * i as f32 / sample_rate as f32).sin() * 0.5)
+			.collect();
+		for block in voice.chunks(1_024) {
+			vocoder.update_carrier_tracking(block);
+		}
+		assert_eq!(vocoder.chord_size, 1);
+		assert!((vocoder.carrier_fundamentals[0] - 230.0).abs() < 3.0, "carrier {}", vocoder.carrier_fundamentals[0]);
+	}
+
+	#[test]
+	fn tracked_pitch_snaps_to_the_scale() {
+		let mut vocoder = Vocoder::new(48_000);
+		// 230 Hz lies between A3 (220 Hz) and A#3 (233.1 Hz)
+		assert!((vocoder.tracked_frequency(230.0) - 230.0).abs() < 0.01);
+
+		set(&mut vocoder, "quantize", 1.0);
+		assert!((vocoder.tracked_frequency(230.0) - 220.0).abs() < 0.01);
+		vocoder.set_scale(Scale::FMajor).unwrap();
+		assert!((vocoder.tracked_frequency(230.0) - 233.08).abs() < 0.01);
+
+		set(&mut vocoder, "tracking_octave", 1.0);
+		assert!((vocoder.tracked_frequency(230.0) - 466.16).abs() < 0.02);
+	}
+
+	#[test]
+	fn glide_moves_in_the_log_domain() {
+		let mut vocoder = Vocoder::new(48_000);
+		set(&mut vocoder, "tracking_glide_ms", 100.0);
+
+		// A new chord shape starts on pitch
+		vocoder.glide_fundamentals(&[220.0, 330.0], 4_800);
+		assert_eq!(vocoder.carrier_fundamentals[..vocoder.chord_size], [220.0, 330.0]);
+
+		// One glide time covers 1 - 1/e of the interval, in cents
+		vocoder.glide_fundamentals(&[440.0, 660.0], 4_800);
+		let expected = 440.0 * 0.5f32.powf((-1.0f32).exp());
+		assert!((vocoder.carrier_fundamentals[0] - expected).abs() < 0.01);
+		assert!((vocoder.carrier_fundamentals[1] - expected * 1.5).abs() < 0.01);
+
+		for _ in 0..20 {
+			vocoder.glide_fundamentals(&[440.0, 660.0], 4_800);
+		}
+		assert!((vocoder.carrier_fundamentals[0] - 440.0).abs() < 0.01);
+	}
+
+	#[test]
+	fn chord_changes_reuse_the_voices() {
+		let mut vocoder = Vocoder::new(48_000);
+		assert_eq!(vocoder.waveform(), CarrierWaveform::Harmonics);
+		set(&mut vocoder, "carrier_waveform", 3.0);
+		let voices = vocoder.carrier_voices.as_ptr();
+
+		vocoder.glide_fundamentals(&[220.0, 277.2, 329.6], 512);
+		vocoder.retune_carrier();
+		assert_eq!(vocoder.active_voices, 3);
+		vocoder.glide_fundamentals(&[220.0], 512);
+		vocoder.retune_carrier();
+		assert_eq!(vocoder.active_voices, 1);
+		assert_eq!(vocoder.carrier_voices.as_ptr(), voices);
+	}
+}
@@ -16,6 +16,12 @@ pub struct LowPassFilter {
     y1: f32,
     /// Two-sample-delayed output (y[n-2])
     y2: f32,
+    /// Coefficients for the current cutoff and resonance, already divided by a0
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
 }
 
 impl LowPassFilter {
@@ -30,7 +36,7 @@ impl LowPassFilter {
     ///
     /// A new `LowPassFilter` instance.
     pub fn new(sample_rate: f32, cutoff: f32, resonance: f32) -> Self {
-        Self {
+        let mut filter = Self {
             sample_rate,
             cutoff: cutoff.max(1.0),
             resonance: resonance.clamp(0.1, 10.0),
@@ -38,7 +44,29 @@ impl LowPassFilter {
             x2: 0.0,
             y1: 0.0,
             y2: 0.0,
-        }
+            b0: 0.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+        };
+        filter.update_coefficients();
+        filter
+    }
+
+    // Simple 2-pole Butterworth filter
+    fn update_coefficients(&mut self) {
+        let omega = 2.0 * std::f32::consts::PI * self.cutoff / self.sample_rate;
+        let cos_omega = omega.cos();
+        let sin_omega = omega.sin();
+        let alpha = sin_omega / (2.0 * self.resonance);
+        let a0 = 1.0 + alpha;
+
+        self.b0 = (1.0 - cos_omega) / 2.0 / a0;
+        self.b1 = (1.0 - cos_omega) / a0;
+        self.b2 = (1.0 - cos_omega) / 2.0 / a0;
+        self.a1 = -2.0 * cos_omega / a0;
+        self.a2 = (1.0 - alpha) / a0;
     }
 
     /// Processes a single sample through the low-pass filter.
@@ -51,22 +79,7 @@ impl LowPassFilter {
     ///
     /// Filtered audio sample.
     pub fn process_internal(&mut self, input: f32) -> f32 {
-        // Simple 2-pole Butterworth filter
-        let omega = 2.0 * std::f32::consts::PI * self.cutoff / self.sample_rate;
-        let cos_omega = omega.cos();
-        let sin_omega = omega.sin();
-        let q = self.resonance;
-
-        let alpha = sin_omega / (2.0 * q);
-
-        let b0 = (1.0 - cos_omega) / 2.0;
-        let b1 = 1.0 - cos_omega;
-        let b2 = (1.0 - cos_omega) / 2.0;
-        let a0 = 1.0 + alpha;
-        let a1 = -2.0 * cos_omega;
-        let a2 = 1.0 - alpha;
-
-        let output = (b0 * input + b1 * self.x1 + b2 * self.x2 - a1 * self.y1 - a2 * self.y2) / a0;
+        let output = self.b0 * input + self.b1 * self.x1 + self.b2 * self.x2 - self.a1 * self.y1 - self.a2 * self.y2;
 
         self.x2 = self.x1;
         self.x1 = input;
@@ -76,13 +89,18 @@ impl LowPassFilter {
         output
     }
 
-    /// Sets the cutoff frequency.
+    /// Sets the cutoff frequency. Coefficients are only recomputed when it changes,
+    /// so calling this every sample with a held value costs nothing.
     ///
     /// # Arguments
     ///
     /// * `cutoff` - Cutoff frequency in Hz (minimum 1.0)
     pub fn set_cutoff(&mut self, cutoff: f32) {
-        self.cutoff = cutoff.max(1.0);
+        let cutoff = cutoff.max(1.0);
+        if cutoff != self.cutoff {
+            self.cutoff = cutoff;
+            self.update_coefficients();
+        }
     }
 
     /// Sets the resonance/Q factor.
@@ -91,7 +109,11 @@ impl LowPassFilter {
     ///
     /// * `resonance` - Q factor (clamped to 0.1-10.0)
     pub fn set_resonance(&mut self, resonance: f32) {
-        self.resonance = resonance.clamp(0.1, 10.0);
+        let resonance = resonance.clamp(0.1, 10.0);
+        if resonance != self.resonance {
+            self.resonance = resonance;
+            self.update_coefficients();
+        }
     }
 
     /// Resets the filter state by clearing all delay samples.
@@ -3,4 +3,6 @@ pub mod filters;
 pub mod chains;
 pub mod utils;
 pub mod effects;
-pub mod yin_detector;
\ No newline at end of file
+pub mod yin_detector;
+pub mod modulation_matrix;
+pub mod carrier;
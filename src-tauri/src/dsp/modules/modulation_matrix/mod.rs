@@ -0,0 +1,338 @@
+//! Chain-level modulation matrix.
+//!
+//! Free-running LFOs, an envelope follower and a pitch tracker on the chain input
+//! are routed with a depth to any effect parameter. Routes are evaluated once per
+//! block; the effects' own parameter smoothing turns the block steps into ramps.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::dsp::modules::utils::{EnvelopeFollower, LFOWaveform, PitchTracker, LFO};
+
+/// Number of LFO slots routes can refer to.
+pub const LFO_COUNT: usize = 4;
+/// Level mapped to 0.0 by the envelope source - quieter input reads as silence.
+const ENVELOPE_FLOOR_DB: f32 = -60.0;
+/// Pitch range mapped onto 0.0-1.0 (logarithmic, so each octave gets the same share).
+const PITCH_MIN_HZ: f32 = 50.0;
+const PITCH_MAX_HZ: f32 = 1000.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LfoSettings {
+    pub frequency: f32,
+    pub waveform: LFOWaveform,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvelopeSettings {
+    pub attack_ms: f32,
+    pub release_ms: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ModulationSource {
+    Lfo { index: usize }, // bipolar, -1.0 to 1.0
+    Envelope,             // input level, 0.0 to 1.0
+    Pitch,                // detected pitch, 0.0 to 1.0
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModulationRoute {
+    pub source: ModulationSource,
+    pub effect_name: String,
+    pub parameter_name: String,
+    pub depth: f32, // share of the parameter range, -1.0 to 1.0
+}
+
+impl ModulationRoute {
+    fn targets(&self, effect_name: &str, parameter_name: &str) -> bool {
+        self.effect_name == effect_name && self.parameter_name == parameter_name
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModulationMatrixSettings {
+    pub lfos: Vec<LfoSettings>,
+    pub envelope: EnvelopeSettings,
+    pub routes: Vec<ModulationRoute>,
+}
+
+impl Default for ModulationMatrixSettings {
+    fn default() -> Self {
+        let waveforms = [
+            LFOWaveform::Sine,
+            LFOWaveform::Triangle,
+            LFOWaveform::Square,
+            LFOWaveform::Random,
+        ];
+        Self {
+            lfos: waveforms
+                .iter()
+                .map(|&waveform| LfoSettings { frequency: 1.0, waveform })
+                .collect(),
+            envelope: EnvelopeSettings {
+                attack_ms: 10.0,
+                release_ms: 150.0,
+            },
+            routes: Vec::new(),
+        }
+    }
+}
+
+impl ModulationMatrixSettings {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.lfos.len() != LFO_COUNT {
+            return Err(anyhow::anyhow!("Expected {} LFOs, got {}", LFO_COUNT, self.lfos.len()));
+        }
+        for lfo in &self.lfos {
+            if !(0.1..=50.0).contains(&lfo.frequency) {
+                return Err(anyhow::anyhow!("LFO frequency must be between 0.1 and 50 Hz"));
+            }
+        }
+        if self.envelope.attack_ms <= 0.0 || self.envelope.release_ms <= 0.0 {
+            return Err(anyhow::anyhow!("Envelope attack and release must be positive"));
+        }
+        for route in &self.routes {
+            match route.source {
+                ModulationSource::Lfo { index } if index >= LFO_COUNT => {
+                    return Err(anyhow::anyhow!("LFO index {} out of range", index));
+                }
+                _ => {}
+            }
+            if !(-1.0..=1.0).contains(&route.depth) {
+                return Err(anyhow::anyhow!("Route depth must be between -1.0 and 1.0"));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct ModulationMatrix {
+    settings: ModulationMatrixSettings,
+    lfos: Vec<LFO>,
+    envelope: EnvelopeFollower,
+    pitch: PitchTracker,
+    // Source values of the last block
+    lfo_values: [f32; LFO_COUNT],
+    envelope_value: f32,
+    pitch_value: f32,
+    // Values set by the user for modulated parameters - modulation is applied around these
+    base_values: HashMap<(String, String), f32>,
+}
+
+impl ModulationMatrix {
+    pub fn new(sample_rate: usize) -> Self {
+        let settings = ModulationMatrixSettings::default();
+        Self {
+            lfos: Self::build_lfos(&settings, sample_rate),
+            envelope: EnvelopeFollower::new(
+                sample_rate as f32,
+                settings.envelope.attack_ms,
+                settings.envelope.release_ms,
+            ),
+            pitch: PitchTracker::new(sample_rate),
+            lfo_values: [0.0; LFO_COUNT],
+            envelope_value: 0.0,
+            pitch_value: 0.0,
+            base_values: HashMap::new(),
+            settings,
+        }
+    }
+
+    fn build_lfos(settings: &ModulationMatrixSettings, sample_rate: usize) -> Vec<LFO> {
+        settings
+            .lfos
+            .iter()
+            .map(|lfo_settings| {
+                let mut lfo = LFO::new(lfo_settings.frequency, 1.0, sample_rate as f32);
+                lfo.set_waveform(lfo_settings.waveform);
+                lfo
+            })
+            .collect()
+    }
+
+    pub fn set_settings(&mut self, settings: ModulationMatrixSettings) -> anyhow::Result<()> {
+        settings.validate()?;
+
+        // Changing rate or shape keeps the LFOs running instead of restarting them
+        for (lfo, lfo_settings) in self.lfos.iter_mut().zip(&settings.lfos) {
+            lfo.set_frequency(lfo_settings.frequency);
+            lfo.set_waveform(lfo_settings.waveform);
+        }
+        self.envelope
+            .set_times(settings.envelope.attack_ms, settings.envelope.release_ms);
+        self.settings = settings;
+        self.base_values.retain(|(effect_name, parameter_name), _| {
+            self.settings
+                .routes
+                .iter()
+                .any(|route| route.targets(effect_name, parameter_name))
+        });
+        Ok(())
+    }
+
+    pub fn get_settings(&self) -> ModulationMatrixSettings {
+        self.settings.clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.settings.routes.is_empty()
+    }
+
+    pub fn is_modulated(&self, effect_name: &str, parameter_name: &str) -> bool {
+        self.settings
+            .routes
+            .iter()
+            .any(|route| route.targets(effect_name, parameter_name))
+    }
+
+    pub fn base_value(&self, effect_name: &str, parameter_name: &str) -> Option<f32> {
+        self.base_values
+            .get(&(effect_name.to_string(), parameter_name.to_string()))
+            .copied()
+    }
+
+    /// Base value of a modulated parameter, taking `current` if none was recorded yet.
+    pub fn base_value_or_insert(&mut self, effect_name: &str, parameter_name: &str, current: f32) -> f32 {
+        *self
+            .base_values
+            .entry((effect_name.to_string(), parameter_name.to_string()))
+            .or_insert(current)
+    }
+
+    pub fn set_base_value(&mut self, effect_name: &str, parameter_name: &str, value: f32) {
+        self.base_values
+            .insert((effect_name.to_string(), parameter_name.to_string()), value);
+    }
+
+    /// Drops recorded base values of a removed effect - a re-added one starts from its own values.
+    pub fn forget_effect(&mut self, effect_name: &str) {
+        self.base_values.retain(|(name, _), _| name != effect_name);
+    }
+
+    /// Distinct routed parameters as (effect, parameter), in route order.
+    pub fn targets(&self) -> Vec<(String, String)> {
+        let mut targets: Vec<(String, String)> = Vec::new();
+        for route in &self.settings.routes {
+            if !targets.iter().any(|(effect_name, parameter_name)| route.targets(effect_name, parameter_name)) {
+                targets.push((route.effect_name.clone(), route.parameter_name.clone()));
+            }
+        }
+        targets
+    }
+
+    /// Indices of the routes feeding one parameter - resolved once, summed with `route_offset`.
+    pub fn routes_for(&self, effect_name: &str, parameter_name: &str) -> Vec<usize> {
+        self.settings
+            .routes
+            .iter()
+            .enumerate()
+            .filter(|(_, route)| route.targets(effect_name, parameter_name))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Advances every source over `input`. Runs on the audio thread, so nothing here allocates.
+    pub fn process_block(&mut self, input: &[f32]) {
+        for (value, lfo) in self.lfo_values.iter_mut().zip(self.lfos.iter_mut()) {
+            *value = lfo.advance(input.len());
+        }
+        for &sample in input {
+            self.envelope.process(sample);
+        }
+        self.envelope_value = self.envelope.normalized_db(ENVELOPE_FLOOR_DB);
+        // Pitch tracking is the expensive one - only run it when something listens
+        self.pitch_value = if self.settings.routes.iter().any(|r| r.source == ModulationSource::Pitch) {
+            self.pitch.process_block(input).map(normalize_pitch).unwrap_or(0.0)
+        } else {
+            0.0
+        };
+    }
+
+    /// Offset of one route after the last block, as a share of the parameter range.
+    pub fn route_offset(&self, index: usize) -> f32 {
+        let route = &self.settings.routes[index];
+        let value = match route.source {
+            ModulationSource::Lfo { index } => self.lfo_values.get(index).copied().unwrap_or(0.0),
+            ModulationSource::Envelope => self.envelope_value,
+            ModulationSource::Pitch => self.pitch_value,
+        };
+        value * route.depth
+    }
+
+    pub fn reset(&mut self) {
+        for lfo in &mut self.lfos {
+            lfo.reset();
+        }
+        self.envelope.reset();
+        self.pitch.reset();
+    }
+}
+
+fn normalize_pitch(frequency: f32) -> f32 {
+    let clamped = frequency.clamp(PITCH_MIN_HZ, PITCH_MAX_HZ);
+    (clamped / PITCH_MIN_HZ).ln() / (PITCH_MAX_HZ / PITCH_MIN_HZ).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(source: ModulationSource, parameter_name: &str, depth: f32) -> ModulationRoute {
+        ModulationRoute {
+            source,
+            effect_name: "reverb".to_string(),
+            parameter_name: parameter_name.to_string(),
+            depth,
+        }
+    }
+
+    #[test]
+    fn routes_to_one_parameter_are_summed() {
+        let mut matrix = ModulationMatrix::new(48_000);
+        let mut settings = ModulationMatrixSettings::default();
+        settings.lfos[0].waveform = LFOWaveform::Square;
+        settings.routes = vec![
+            route(ModulationSource::Lfo { index: 0 }, "wet_level", 0.25),
+            route(ModulationSource::Envelope, "wet_level", 0.5),
+            route(ModulationSource::Envelope, "room_size", -1.0),
+        ];
+        matrix.set_settings(settings).unwrap();
+
+        // Square starts low, full scale input pins the envelope at 1.0
+        matrix.process_block(&[1.0; 4800]);
+        let offset = |parameter_name: &str| -> f32 {
+            matrix
+                .routes_for("reverb", parameter_name)
+                .into_iter()
+                .map(|index| matrix.route_offset(index))
+                .sum()
+        };
+        assert_eq!(matrix.targets().len(), 2);
+        assert!((offset("wet_level") - 0.25).abs() < 0.01, "offset {}", offset("wet_level"));
+        assert!((offset("room_size") + 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn pitch_is_normalized_per_octave() {
+        assert_eq!(normalize_pitch(20.0), 0.0);
+        assert_eq!(normalize_pitch(PITCH_MAX_HZ), 1.0);
+        let octave = normalize_pitch(200.0) - normalize_pitch(100.0);
+        assert!((normalize_pitch(800.0) - normalize_pitch(400.0) - octave).abs() < 1e-6);
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let mut matrix = ModulationMatrix::new(48_000);
+        let mut settings = ModulationMatrixSettings {
+            routes: vec![route(ModulationSource::Lfo { index: LFO_COUNT }, "mix", 0.5)],
+            ..Default::default()
+        };
+        assert!(matrix.set_settings(settings.clone()).is_err());
+
+        settings.routes = vec![route(ModulationSource::Pitch, "mix", 1.5)];
+        assert!(matrix.set_settings(settings).is_err());
+        assert!(matrix.is_empty());
+    }
+}
@@ -0,0 +1,24 @@
+use std::path::Path;
+
+/// Reads a WAV file as interleaved f32 samples.
+///
+/// # Returns
+///
+/// The samples, the channel count (at least 1) and the file's sample rate in Hz.
+pub fn read_wav(path: impl AsRef<Path>) -> anyhow::Result<(Vec<f32>, usize, u32)> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 * scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    Ok((interleaved, spec.channels.max(1) as usize, spec.sample_rate))
+}
@@ -0,0 +1,210 @@
+//! Band-limited oscillators using PolyBLEP - a polynomial correction around each
+//! discontinuity removes most of the aliasing of the naive waveforms.
+
+/// PolyBLEP residual for a discontinuity at phase 0, `dt` = phase increment per sample.
+fn poly_blep(phase: f32, dt: f32) -> f32 {
+    if phase < dt {
+        let t = phase / dt;
+        t + t - t * t - 1.0
+    } else if phase > 1.0 - dt {
+        let t = (phase - 1.0) / dt;
+        t * t + t + t + 1.0
+    } else {
+        0.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SawOscillator {
+    frequency: f32,
+    amplitude: f32,
+    phase: f32,
+}
+
+impl SawOscillator {
+    pub fn new(frequency: f32, amplitude: f32) -> Self {
+        Self {
+            frequency: frequency.max(1.0),
+            amplitude: amplitude.clamp(0.0, 1.0),
+            phase: 0.0,
+        }
+    }
+
+    /// Starts at `phase` (0.0-1.0) instead of 0 - keeps unison voices from adding up in phase.
+    pub fn with_phase(mut self, phase: f32) -> Self {
+        self.phase = phase.rem_euclid(1.0);
+        self
+    }
+
+    pub fn process(&mut self, sample_rate: f32) -> f32 {
+        let dt = (self.frequency / sample_rate).min(0.5);
+        let output = 2.0 * self.phase - 1.0 - poly_blep(self.phase, dt);
+        self.phase += dt;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        }
+        output * self.amplitude
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency.max(1.0);
+    }
+
+    pub fn set_amplitude(&mut self, amplitude: f32) {
+        self.amplitude = amplitude.clamp(0.0, 1.0);
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PulseOscillator {
+    frequency: f32,
+    amplitude: f32,
+    width: f32,
+    phase: f32,
+}
+
+impl PulseOscillator {
+    pub fn new(frequency: f32, amplitude: f32, width: f32) -> Self {
+        Self {
+            frequency: frequency.max(1.0),
+            amplitude: amplitude.clamp(0.0, 1.0),
+            width: width.clamp(0.05, 0.95),
+            phase: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, sample_rate: f32) -> f32 {
+        let dt = (self.frequency / sample_rate).min(0.5);
+        let naive = if self.phase < self.width { 1.0 } else { -1.0 };
+        // Rising edge at 0, falling edge at `width`
+        let falling = (self.phase - self.width).rem_euclid(1.0);
+        let output = naive + poly_blep(self.phase, dt) - poly_blep(falling, dt);
+
+        self.phase += dt;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        }
+        // Remove the DC offset of asymmetric pulses
+        (output - (2.0 * self.width - 1.0)) * self.amplitude
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency.max(1.0);
+    }
+
+    pub fn set_amplitude(&mut self, amplitude: f32) {
+        self.amplitude = amplitude.clamp(0.0, 1.0);
+    }
+
+    /// Sets the pulse width (duty cycle), clamped to 0.05-0.95.
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width.clamp(0.05, 0.95);
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+}
+
+/// Detune of each unison voice at full `detune`, in semitones.
+const SUPERSAW_DETUNE: [f32; 7] = [0.0, -0.11, 0.11, -0.34, 0.34, -0.58, 0.58];
+
+/// Seven detuned saws around one frequency.
+#[derive(Debug, Clone)]
+pub struct SupersawOscillator {
+    frequency: f32,
+    amplitude: f32,
+    detune: f32,
+    voices: Vec<SawOscillator>,
+}
+
+impl SupersawOscillator {
+    pub fn new(frequency: f32, amplitude: f32, detune: f32) -> Self {
+        let voices = (0..SUPERSAW_DETUNE.len())
+            .map(|i| SawOscillator::new(frequency, 1.0).with_phase(i as f32 * 0.37))
+            .collect();
+        let mut oscillator = Self {
+            frequency: frequency.max(1.0),
+            amplitude: amplitude.clamp(0.0, 1.0),
+            detune: detune.clamp(0.0, 1.0),
+            voices,
+        };
+        oscillator.update_voices();
+        oscillator
+    }
+
+    fn update_voices(&mut self) {
+        for (voice, semitones) in self.voices.iter_mut().zip(SUPERSAW_DETUNE) {
+            voice.set_frequency(self.frequency * 2.0_f32.powf(semitones * self.detune / 12.0));
+        }
+    }
+
+    pub fn process(&mut self, sample_rate: f32) -> f32 {
+        // Uncorrelated voices add up by power
+        let sum: f32 = self.voices.iter_mut().map(|voice| voice.process(sample_rate)).sum();
+        sum / (self.voices.len() as f32).sqrt() * self.amplitude
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency.max(1.0);
+        self.update_voices();
+    }
+
+    pub fn set_amplitude(&mut self, amplitude: f32) {
+        self.amplitude = amplitude.clamp(0.0, 1.0);
+    }
+
+    /// Sets the unison spread (0.0 = all voices in tune, 1.0 = full spread).
+    pub fn set_detune(&mut self, detune: f32) {
+        self.detune = detune.clamp(0.0, 1.0);
+        self.update_voices();
+    }
+
+    pub fn reset(&mut self) {
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            *voice = voice.clone().with_phase(i as f32 * 0.37);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saw_ramps_and_smooths_the_reset() {
+        let sample_rate = 48_000.0;
+        let mut saw = SawOscillator::new(480.0, 1.0);
+        let cycle: Vec<f32> = (0..100).map(|_| saw.process(sample_rate)).collect();
+
+        // Rising ramp mid cycle, the jump back is spread over the samples around it
+        assert!((cycle[50] - 0.0).abs() < 1e-4);
+        assert!(cycle[60] > cycle[50]);
+        assert!(cycle[0].abs() < 0.1, "reset sample {}", cycle[0]);
+        let mean: f32 = cycle.iter().sum::<f32>() / cycle.len() as f32;
+        assert!(mean.abs() < 0.02);
+    }
+
+    #[test]
+    fn pulse_has_no_dc_at_any_width() {
+        let sample_rate = 48_000.0;
+        for width in [0.1, 0.5, 0.8] {
+            let mut pulse = PulseOscillator::new(480.0, 1.0, width);
+            let cycle: Vec<f32> = (0..1000).map(|_| pulse.process(sample_rate)).collect();
+            let mean: f32 = cycle.iter().sum::<f32>() / cycle.len() as f32;
+            assert!(mean.abs() < 0.01, "width {} mean {}", width, mean);
+        }
+    }
+
+    #[test]
+    fn supersaw_stays_near_full_scale() {
+        let sample_rate = 48_000.0;
+        let mut supersaw = SupersawOscillator::new(110.0, 1.0, 1.0);
+        let peak = (0..4800).map(|_| supersaw.process(sample_rate).abs()).fold(0.0, f32::max);
+        assert!(peak > 0.5 && peak < 2.7, "peak {}", peak);
+    }
+}
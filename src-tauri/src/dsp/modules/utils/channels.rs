@@ -0,0 +1,79 @@
+/// Averages interleaved frames down to one channel.
+pub fn downmix(samples: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// Same as `downmix`, written into `output` - reuses its allocation on the audio thread.
+pub fn downmix_into(samples: &[f32], channels: usize, output: &mut Vec<f32>) {
+    let channels = channels.max(1);
+    output.clear();
+    output.extend(
+        samples
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+    );
+}
+
+/// Maps one frame onto another channel count.
+/// Mono goes to the first two outputs (left and right), extra outputs stay silent
+/// and extra inputs are folded in - input channel `i` is averaged into output `i % outputs`.
+pub fn mix_frame(input: &[f32], output: &mut [f32]) {
+    let (inputs, outputs) = (input.len(), output.len());
+    if inputs == 0 || outputs == 0 {
+        output.fill(0.0);
+        return;
+    }
+
+    if inputs == 1 {
+        for (channel, sample) in output.iter_mut().enumerate() {
+            *sample = if channel < 2 { input[0] } else { 0.0 };
+        }
+    } else if inputs <= outputs {
+        output[..inputs].copy_from_slice(input);
+        output[inputs..].fill(0.0);
+    } else {
+        for (channel, sample) in output.iter_mut().enumerate() {
+            let (sum, count) = input
+                .iter()
+                .skip(channel)
+                .step_by(outputs)
+                .fold((0.0, 0), |(sum, count), &x| (sum + x, count + 1));
+            *sample = sum / count as f32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_up_and_downmixed() {
+        let mut stereo = [0.0; 2];
+        mix_frame(&[0.5], &mut stereo);
+        assert_eq!(stereo, [0.5, 0.5]);
+
+        let mut surround = [1.0; 6];
+        mix_frame(&[0.5], &mut surround);
+        assert_eq!(surround, [0.5, 0.5, 0.0, 0.0, 0.0, 0.0]);
+        mix_frame(&[0.25, 0.75], &mut surround);
+        assert_eq!(surround, [0.25, 0.75, 0.0, 0.0, 0.0, 0.0]);
+
+        let mut mono = [0.0];
+        mix_frame(&[0.25, 0.75], &mut mono);
+        assert_eq!(mono, [0.5]);
+
+        // Four interface inputs onto stereo: 1+3 left, 2+4 right
+        mix_frame(&[0.2, 0.4, 0.6, 0.8], &mut stereo);
+        assert!((stereo[0] - 0.4).abs() < 1e-6 && (stereo[1] - 0.6).abs() < 1e-6);
+
+        let mono = downmix(&[0.2, 0.4, 0.6, 0.8], 2);
+        assert!((mono[0] - 0.3).abs() < 1e-6 && (mono[1] - 0.7).abs() < 1e-6);
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EffectParameter {
     pub name: String,
     pub value: f32,
@@ -31,8 +31,12 @@ impl EffectParameter {
     }
 
     pub fn set_normalized(&mut self, normalized: f32) {
-        let normalized = normalized.clamp(0.0, 1.0);
-        self.value = self.min_value + normalized * (self.max_value - self.min_value);
+        self.value = self.value_at_normalized(normalized);
+    }
+
+    /// Value `normalized` of the way through the range, without changing the parameter.
+    pub fn value_at_normalized(&self, normalized: f32) -> f32 {
+        self.min_value + normalized.clamp(0.0, 1.0) * (self.max_value - self.min_value)
     }
 
     pub fn get_normalized(&self) -> f32 {
@@ -0,0 +1,85 @@
+/// Peak envelope follower with separate attack and release times.
+#[derive(Debug, Clone)]
+pub struct EnvelopeFollower {
+    sample_rate: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    envelope: f32,
+}
+
+impl EnvelopeFollower {
+    pub fn new(sample_rate: f32, attack_ms: f32, release_ms: f32) -> Self {
+        let mut follower = Self {
+            sample_rate,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            envelope: 0.0,
+        };
+        follower.set_times(attack_ms, release_ms);
+        follower
+    }
+
+    pub fn set_times(&mut self, attack_ms: f32, release_ms: f32) {
+        self.attack_coeff = Self::coefficient(attack_ms, self.sample_rate);
+        self.release_coeff = Self::coefficient(release_ms, self.sample_rate);
+    }
+
+    fn coefficient(time_ms: f32, sample_rate: f32) -> f32 {
+        (-1.0 / (time_ms.max(0.01) * 0.001 * sample_rate)).exp()
+    }
+
+    /// Follows one sample and returns the linear envelope level.
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let level = sample.abs();
+        let coeff = if level > self.envelope {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.envelope = level + (self.envelope - level) * coeff;
+        self.envelope
+    }
+
+    /// Envelope level mapped from `floor_db`..0 dBFS onto 0.0-1.0.
+    pub fn normalized_db(&self, floor_db: f32) -> f32 {
+        if self.envelope <= 0.0 {
+            return 0.0;
+        }
+        let db = 20.0 * self.envelope.log10();
+        ((db - floor_db) / -floor_db).clamp(0.0, 1.0)
+    }
+
+    pub fn level(&self) -> f32 {
+        self.envelope
+    }
+
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attacks_fast_and_releases_slow() {
+        let mut follower = EnvelopeFollower::new(48_000.0, 1.0, 50.0);
+        for _ in 0..480 {
+            follower.process(0.5);
+        }
+        // 10 attack time constants in
+        assert!((follower.level() - 0.5).abs() < 0.001);
+        // -6 dB is 0.9 of the way from a -60 dB floor
+        assert!((follower.normalized_db(-60.0) - 0.9).abs() < 0.01);
+
+        for _ in 0..480 {
+            follower.process(0.0);
+        }
+        // Only a fifth of a release time constant later
+        assert!(follower.level() > 0.4);
+
+        follower.reset();
+        assert_eq!(follower.normalized_db(-60.0), 0.0);
+    }
+}
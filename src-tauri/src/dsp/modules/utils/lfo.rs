@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone)]
 pub struct LFO {
     /// LFO frequency in Hz
@@ -40,7 +42,29 @@ impl LFO {
     ///
     /// Modulation value in range -amplitude to +amplitude.
     pub fn process(&mut self) -> f32 {
-        let output = match self.waveform {
+        let output = self.value_at_phase();
+
+        self.phase += self.frequency / self.sample_rate;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        }
+
+        output * self.amplitude
+    }
+
+    /// Moves `samples` ahead in one step, for modulation applied once per block.
+    ///
+    /// # Returns
+    ///
+    /// Modulation value at the start of the skipped span, in range -amplitude to +amplitude.
+    pub fn advance(&mut self, samples: usize) -> f32 {
+        let output = self.value_at_phase();
+        self.phase = (self.phase + self.frequency * samples as f32 / self.sample_rate).fract();
+        output * self.amplitude
+    }
+
+    fn value_at_phase(&self) -> f32 {
+        match self.waveform {
             LFOWaveform::Sine => (self.phase * 2.0 * std::f32::consts::PI).sin(),
             LFOWaveform::Triangle => {
                 let normalized = self.phase.fract();
@@ -64,14 +88,7 @@ impl LFO {
                 state = state.wrapping_mul(1103515245).wrapping_add(12345);
                 (state as f32 / u32::MAX as f32) * 2.0 - 1.0
             }
-        };
-
-        self.phase += self.frequency / self.sample_rate;
-        if self.phase >= 1.0 {
-            self.phase -= 1.0;
         }
-
-        output * self.amplitude
     }
 
     /// Sets the LFO frequency.
@@ -105,10 +122,19 @@ impl LFO {
     pub fn reset(&mut self) {
         self.phase = 0.0;
     }
+
+    /// Jumps to a position in the cycle, e.g. to offset two LFOs from each other.
+    ///
+    /// # Arguments
+    ///
+    /// * `phase` - Position in the cycle (0.0-1.0, wrapped)
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase.rem_euclid(1.0);
+    }
 }
 
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LFOWaveform {
     /// Smooth sinusoidal waveform
     Sine,
@@ -1,10 +1,26 @@
 pub mod windows;
 pub mod effect_parameter;
+pub mod smoothed_parameter;
 pub mod lfo;
 pub mod oscilator;
+pub mod band_limited;
+pub mod noise;
+pub mod envelope_follower;
+pub mod pitch_tracker;
+pub mod audio_file;
+pub mod resampler;
+pub mod channels;
 
 
 pub use windows::*;
 pub use effect_parameter::*;
+pub use smoothed_parameter::*;
 pub use lfo::*;
 pub use oscilator::*;
+pub use band_limited::*;
+pub use noise::*;
+pub use envelope_follower::*;
+pub use pitch_tracker::*;
+pub use audio_file::*;
+pub use resampler::*;
+pub use channels::*;
@@ -0,0 +1,26 @@
+/// White noise from a xorshift generator - cheap and plenty random for audio.
+#[derive(Debug, Clone)]
+pub struct NoiseOscillator {
+    amplitude: f32,
+    state: u32,
+}
+
+impl NoiseOscillator {
+    pub fn new(amplitude: f32) -> Self {
+        Self {
+            amplitude: amplitude.clamp(0.0, 1.0),
+            state: 0x2545_f491,
+        }
+    }
+
+    pub fn process(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        ((self.state as f32 / u32::MAX as f32) * 2.0 - 1.0) * self.amplitude
+    }
+
+    pub fn set_amplitude(&mut self, amplitude: f32) {
+        self.amplitude = amplitude.clamp(0.0, 1.0);
+    }
+}
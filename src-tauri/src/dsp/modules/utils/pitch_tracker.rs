@@ -0,0 +1,70 @@
+use crate::dsp::modules::yin_detector::detector::yin::YINDetector;
+use crate::dsp::modules::yin_detector::detector::PitchDetector;
+
+/// Analysis window and hop of the tracker.
+const WINDOW: usize = 2048;
+const HOP: usize = 1024;
+
+/// Block-based YIN pitch tracking. Holds the last voiced pitch through unvoiced
+/// parts, so whatever follows it does not fall back between words.
+pub struct PitchTracker {
+    sample_rate: usize,
+    detector: YINDetector<f32>,
+    window: Vec<f32>,
+    since_detection: usize,
+    frequency: Option<f32>,
+}
+
+impl PitchTracker {
+    pub fn new(sample_rate: usize) -> Self {
+        Self {
+            sample_rate,
+            detector: YINDetector::new(WINDOW, WINDOW / 2),
+            window: vec![0.0; WINDOW],
+            since_detection: 0,
+            frequency: None,
+        }
+    }
+
+    /// Feeds a block and returns the latest voiced pitch in Hz, `None` until the first one.
+    pub fn process_block(&mut self, input: &[f32]) -> Option<f32> {
+        let count = input.len().min(WINDOW);
+        self.window.copy_within(count.., 0);
+        self.window[WINDOW - count..].copy_from_slice(&input[input.len() - count..]);
+        self.since_detection += input.len();
+
+        if self.since_detection >= HOP {
+            self.since_detection = 0;
+            if let Some(pitch) = self.detector.get_pitch(&self.window, self.sample_rate, 0.01, 0.5) {
+                self.frequency = Some(pitch.frequency);
+            }
+        }
+        self.frequency
+    }
+
+    pub fn reset(&mut self) {
+        self.window.fill(0.0);
+        self.since_detection = 0;
+        self.frequency = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_and_holds_pitch() {
+        let sample_rate = 48_000;
+        let mut tracker = PitchTracker::new(sample_rate);
+        assert_eq!(tracker.process_block(&[0.0; WINDOW]), None);
+
+        let tone: Vec<f32> = (0..WINDOW * 2)
+            .map(|i| (2.0 * std::f32::consts::PI * 220.0 * i as f32 / sample_rate as f32).sin() * 0.5)
+            .collect();
+        let frequency = tracker.process_block(&tone).unwrap();
+        assert!((frequency - 220.0).abs() < 2.0, "frequency {}", frequency);
+
+        assert_eq!(tracker.process_block(&[0.0; WINDOW]), Some(frequency));
+    }
+}
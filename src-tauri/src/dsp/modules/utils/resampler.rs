@@ -0,0 +1,180 @@
+/// Sinc kernel taps on each side of the centre - Blackman windowed, about 60 dB into the stopband.
+const HALF_TAPS: usize = 24;
+const TAPS: usize = HALF_TAPS * 2;
+/// Kernel phases stored per input sample, intermediate phases are interpolated.
+const PHASES: usize = 128;
+/// Passband edge relative to the lower Nyquist frequency, leaves room for the transition band.
+const CUTOFF: f64 = 0.9;
+
+/// Streaming polyphase windowed-sinc resampler for interleaved audio.
+/// Keeps its own input history, so blocks of any size can be fed in.
+pub struct Resampler {
+    channels: usize,
+    ratio: f64,    // input frames per output frame
+    adjust: f64,   // relative correction on top of the ratio, for clock drift
+    position: f64, // next output frame, in frames from the start of `history`
+    history: Vec<f32>,
+    table: Vec<f32>, // (PHASES + 1) rows of TAPS coefficients
+}
+
+impl Resampler {
+    pub fn new(from_rate: u32, to_rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        let ratio = from_rate as f64 / to_rate as f64;
+        // Downsampling moves the cutoff to the output Nyquist frequency
+        let cutoff = CUTOFF * (1.0 / ratio).min(1.0);
+
+        let mut table = Vec::with_capacity((PHASES + 1) * TAPS);
+        for phase in 0..=PHASES {
+            let frac = phase as f64 / PHASES as f64;
+            for tap in 0..TAPS {
+                let distance = tap as f64 - (HALF_TAPS as f64 - 1.0) - frac;
+                table.push((sinc(distance * cutoff) * cutoff * blackman(distance)) as f32);
+            }
+        }
+
+        Self {
+            channels,
+            ratio,
+            adjust: 0.0,
+            position: (HALF_TAPS - 1) as f64,
+            // Silence before the first sample, so the kernel is full from the start
+            history: vec![0.0; (HALF_TAPS - 1) * channels],
+            table,
+        }
+    }
+
+    /// Resamples one block, returns every output frame the history allows so far.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        self.history.extend_from_slice(input);
+        let frames = self.history.len() / self.channels;
+
+        let step = self.ratio * (1.0 + self.adjust);
+        let mut output = Vec::with_capacity(((input.len() as f64 / step) as usize + 1) * self.channels);
+        while (self.position as usize) + HALF_TAPS < frames {
+            let index = self.position as usize;
+            let phase = (self.position - index as f64) * PHASES as f64;
+            let row = phase as usize;
+            let blend = (phase - row as f64) as f32;
+            let first = &self.table[row * TAPS..(row + 1) * TAPS];
+            let second = &self.table[(row + 1) * TAPS..(row + 2) * TAPS];
+
+            let start = (index + 1 - HALF_TAPS) * self.channels;
+            for channel in 0..self.channels {
+                let sum: f32 = first
+                    .iter()
+                    .zip(second)
+                    .zip(self.history[start + channel..].iter().step_by(self.channels))
+                    .map(|((a, b), sample)| (a + (b - a) * blend) * sample)
+                    .sum();
+                output.push(sum);
+            }
+            self.position += step;
+        }
+
+        // Frames no future output will reach
+        let consumed = (self.position as usize + 1).saturating_sub(HALF_TAPS).min(frames);
+        self.history.drain(..consumed * self.channels);
+        self.position -= consumed as f64;
+        output
+    }
+
+    /// Stretches the ratio by a small amount, e.g. 1e-4 consumes input 100 ppm faster.
+    /// The filter is not redesigned - meant for clock drift, not for changing rates.
+    pub fn set_ratio_adjust(&mut self, adjust: f64) {
+        self.adjust = adjust;
+    }
+
+    /// Delay the filter adds, in input frames.
+    pub fn latency(&self) -> usize {
+        HALF_TAPS
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        let x = std::f64::consts::PI * x;
+        x.sin() / x
+    }
+}
+
+fn blackman(distance: f64) -> f64 {
+    let x = std::f64::consts::PI * distance / HALF_TAPS as f64;
+    0.42 + 0.5 * x.cos() + 0.08 * (2.0 * x).cos()
+}
+
+/// Resamples one channel of preloaded audio (files, clips) with the same filter as the live path.
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let length = ((samples.len() as f64 * to_rate as f64 / from_rate as f64).round() as usize).max(1);
+    let mut resampler = Resampler::new(from_rate, to_rate, 1);
+    let mut output = resampler.process(samples);
+    // Flush the kernel's tail with silence
+    output.extend(resampler.process(&vec![0.0; resampler.latency() + 1]));
+    output.resize(length, 0.0);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f32, sample_rate: u32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|i| (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn block_size_does_not_change_the_output() {
+        let input: Vec<f32> = sine(440.0, 48_000, 2_000)
+            .into_iter()
+            .flat_map(|s| [s, -s])
+            .collect();
+
+        let mut whole = Resampler::new(48_000, 44_100, 2);
+        let expected = whole.process(&input);
+
+        let mut blocks = Resampler::new(48_000, 44_100, 2);
+        let mut actual = Vec::new();
+        for block in input.chunks(2 * 37) {
+            actual.extend(blocks.process(block));
+        }
+        assert_eq!(actual.len(), expected.len());
+        assert!(actual.iter().zip(&expected).all(|(a, b)| (a - b).abs() < 1e-6));
+        // Channels stay apart
+        assert!(actual.chunks(2).all(|frame| (frame[0] + frame[1]).abs() < 1e-6));
+    }
+
+    #[test]
+    fn sine_survives_conversion() {
+        for (from, to) in [(48_000, 44_100), (44_100, 48_000)] {
+            let output = resample(&sine(1_000.0, from, 4_800), from, to);
+            assert_eq!(output.len(), (4_800.0 * to as f64 / from as f64).round() as usize);
+
+            // Compare away from the edges, where the kernel sees the zero padding
+            let reference = sine(1_000.0, to, output.len());
+            let error = output[100..output.len() - 100]
+                .iter()
+                .zip(&reference[100..])
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f32::max);
+            assert!(error < 0.01, "{} -> {} Hz error {}", from, to, error);
+        }
+    }
+
+    #[test]
+    fn content_above_the_new_nyquist_is_removed() {
+        // 23 kHz aliases to 21.1 kHz at 44.1 kHz unless it is filtered out
+        let output = resample(&sine(23_000.0, 48_000, 4_800), 48_000, 44_100);
+        let peak = output[100..output.len() - 100]
+            .iter()
+            .fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak < 0.003, "alias peak {}", peak);
+    }
+}
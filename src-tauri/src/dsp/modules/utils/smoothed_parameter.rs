@@ -0,0 +1,135 @@
+use super::effect_parameter::EffectParameter;
+
+/// Ramp time used unless an effect asks for something else - short enough to feel
+/// instant, long enough to hide the step of a gain or mix change.
+pub const DEFAULT_SMOOTHING_MS: f32 = 20.0;
+
+/// `EffectParameter` whose audible value ramps linearly to the set value instead of jumping.
+/// The wrapped parameter always holds the target, so it is what the UI and presets see.
+#[derive(Debug, Clone)]
+pub struct SmoothedParameter {
+    parameter: EffectParameter,
+    sample_rate: f32,
+    ramp_samples: usize,
+    current: f32,
+    step: f32,
+    remaining: usize,
+}
+
+impl SmoothedParameter {
+    pub fn new(name: &str, default: f32, min: f32, max: f32, sample_rate: f32) -> Self {
+        let mut smoothed = Self {
+            parameter: EffectParameter::new(name, default, min, max),
+            sample_rate,
+            ramp_samples: 0,
+            current: default,
+            step: 0.0,
+            remaining: 0,
+        };
+        smoothed.set_smoothing_time(DEFAULT_SMOOTHING_MS);
+        smoothed
+    }
+
+    /// A running ramp keeps its old length, the next `set_value` uses the new one.
+    pub fn set_smoothing_time(&mut self, time_ms: f32) {
+        self.ramp_samples = (time_ms.max(0.0) * 0.001 * self.sample_rate) as usize;
+    }
+
+    pub fn set_value(&mut self, value: f32) {
+        self.parameter.set_value(value);
+        let target = self.parameter.value;
+
+        if self.ramp_samples == 0 {
+            self.snap();
+        } else {
+            self.step = (target - self.current) / self.ramp_samples as f32;
+            self.remaining = self.ramp_samples;
+        }
+    }
+
+    /// Value the parameter is heading to.
+    pub fn target(&self) -> f32 {
+        self.parameter.value
+    }
+
+    /// Value reached so far by the ramp.
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    /// Value for the next sample - call once per sample.
+    pub fn next_value(&mut self) -> f32 {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            self.current = if self.remaining == 0 {
+                self.parameter.value
+            } else {
+                self.current + self.step
+            };
+        }
+        self.current
+    }
+
+    /// Skips `samples` ahead and returns the value reached - for parameters only
+    /// read once per block (filter coefficients, FFT frame settings).
+    pub fn advance(&mut self, samples: usize) -> f32 {
+        if samples >= self.remaining {
+            self.snap();
+        } else {
+            self.remaining -= samples;
+            self.current += self.step * samples as f32;
+        }
+        self.current
+    }
+
+    pub fn is_smoothing(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Jumps straight to the target, e.g. after a reset when there is nothing to ramp from.
+    pub fn snap(&mut self) {
+        self.current = self.parameter.value;
+        self.step = 0.0;
+        self.remaining = 0;
+    }
+
+    pub fn parameter(&self) -> &EffectParameter {
+        &self.parameter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramps_linearly_to_target() {
+        // 10 ms at 1 kHz = 10 sample ramp
+        let mut gain = SmoothedParameter::new("gain", 0.0, 0.0, 10.0, 1000.0);
+        gain.set_smoothing_time(10.0);
+        gain.set_value(5.0);
+        assert_eq!(gain.target(), 5.0);
+        assert_eq!(gain.parameter().value, 5.0);
+
+        let ramp: Vec<f32> = (0..10).map(|_| gain.next_value()).collect();
+        assert!((ramp[0] - 0.5).abs() < 1e-6);
+        assert!((ramp[4] - 2.5).abs() < 1e-6);
+        assert_eq!(ramp[9], 5.0);
+        assert!(!gain.is_smoothing());
+        assert_eq!(gain.next_value(), 5.0);
+
+        // Values outside the range ramp to the clamped target
+        gain.set_value(20.0);
+        assert!((gain.advance(4) - 7.0).abs() < 1e-5);
+        assert_eq!(gain.advance(100), 10.0);
+    }
+
+    #[test]
+    fn zero_time_jumps() {
+        let mut mix = SmoothedParameter::new("mix", 0.5, 0.0, 1.0, 48_000.0);
+        mix.set_smoothing_time(0.0);
+        mix.set_value(1.0);
+        assert!(!mix.is_smoothing());
+        assert_eq!(mix.next_value(), 1.0);
+    }
+}
@@ -0,0 +1,174 @@
+// Analysis thread - keeps FFT, pitch detection and Tauri emits off the audio callback.
+// The callback only pushes interleaved samples into lock-free rings (one before and
+// one after the modulation chain), this thread drains them into the meters per channel,
+// downmixes the output for the spectrum, pitch and waveform and lets the visualizer
+// emit frames at its configured frame rate.
+
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::HeapRb;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::fft_visualizer::{FrameSink, SpectrumVisualizer};
+use crate::dsp::modules::utils::downmix_into;
+
+/// How often the thread wakes up to drain the ring.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+/// Frames drained from the ring per pop.
+const DRAIN_CHUNK: usize = 1024;
+
+pub struct AnalysisThread {
+    input_producer: <HeapRb<f32> as Split>::Prod,
+    output_producer: <HeapRb<f32> as Split>::Prod,
+    control: Arc<Mutex<bool>>, // true = run, false = stop
+    handle: Option<JoinHandle<()>>,
+}
+
+impl AnalysisThread {
+    // Ring holds one second of audio - far more than the thread ever lags behind
+    pub fn start<F: FrameSink>(visualizer: Arc<SpectrumVisualizer>, sink: F, channels: usize) -> Self {
+        let channels = channels.max(1);
+        let capacity = visualizer.sample_rate() * channels;
+        let (input_producer, mut input_consumer) = HeapRb::<f32>::new(capacity).split();
+        let (output_producer, mut output_consumer) = HeapRb::<f32>::new(capacity).split();
+        visualizer.set_meter_channels(channels);
+        let control = Arc::new(Mutex::new(true));
+        let thread_control = control.clone();
+
+        let handle = thread::spawn(move || {
+            let fft_size = visualizer.fft_size();
+            // Latest `fft_size` samples for the FFT and pitch detection
+            let mut window = vec![0.0f32; fft_size];
+            // Everything received since the last emitted frame (for the waveform)
+            let mut recent: Vec<f32> = Vec::new();
+            // Whole frames per pop - the callback only pushes whole blocks
+            let mut chunk = vec![0.0f32; DRAIN_CHUNK * channels];
+            let mut mono: Vec<f32> = Vec::with_capacity(DRAIN_CHUNK);
+
+            while *thread_control.lock().unwrap() {
+                loop {
+                    let count = input_consumer.pop_slice(&mut chunk);
+                    if count == 0 {
+                        break;
+                    }
+                    visualizer.meter_input(&chunk[..count]);
+                }
+
+                loop {
+                    let count = output_consumer.pop_slice(&mut chunk);
+                    if count == 0 {
+                        break;
+                    }
+                    visualizer.meter_output(&chunk[..count]);
+                    downmix_into(&chunk[..count], channels, &mut mono);
+                    let received = &mono[..];
+                    let count = received.len();
+                    if count >= fft_size {
+                        window.copy_from_slice(&received[count - fft_size..]);
+                    } else {
+                        window.copy_within(count.., 0);
+                        window[fft_size - count..].copy_from_slice(received);
+                    }
+                    recent.extend_from_slice(received);
+                }
+
+                // Cap the waveform backlog if emits are failing or throttled for long
+                if recent.len() > visualizer.sample_rate() {
+                    let excess = recent.len() - visualizer.sample_rate();
+                    recent.drain(..excess);
+                }
+
+                // Nothing new means the stream is stopped - do not repeat stale frames
+                if !recent.is_empty() {
+                    match visualizer.emit_spectrum(&sink, &window, &recent) {
+                        Ok(true) => recent.clear(),
+                        Ok(false) => {}
+                        Err(e) => {
+                            eprintln!("Spectrum analysis failed: {}", e);
+                            recent.clear();
+                        }
+                    }
+                }
+
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+
+        AnalysisThread {
+            input_producer,
+            output_producer,
+            control,
+            handle: Some(handle),
+        }
+    }
+
+    // Called from the audio callback - never blocks, drops whole blocks if a ring is full
+    // so the interleaved frames stay aligned
+    pub fn push(&mut self, input: &[f32], output: &[f32]) {
+        if self.input_producer.vacant_len() >= input.len() {
+            self.input_producer.push_slice(input);
+        }
+        if self.output_producer.vacant_len() >= output.len() {
+            self.output_producer.push_slice(output);
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Ok(mut should_run) = self.control.lock() {
+            *should_run = false;
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for AnalysisThread {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    // Counts spectrum frames - one per emitted analysis frame
+    struct CountingSink(Arc<Mutex<usize>>);
+
+    impl FrameSink for CountingSink {
+        fn send<S: serde::Serialize + Clone>(&self, event: &str, _payload: &S) -> anyhow::Result<()> {
+            if event == "audio-spectrum" {
+                *self.0.lock().unwrap() += 1;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn emits_are_throttled_to_the_frame_rate() {
+        let visualizer = Arc::new(SpectrumVisualizer::new(48_000, 1024));
+        let mut settings = visualizer.get_settings();
+        settings.frame_rate = 20.0;
+        visualizer.set_settings(settings).unwrap();
+
+        let frames = Arc::new(Mutex::new(0));
+        let mut analysis = AnalysisThread::start(visualizer, CountingSink(frames.clone()), 1);
+
+        // Blocks arrive every 10 ms, five times as often as a frame may go out
+        let block: Vec<f32> = (0..480).map(|i| 0.5 * (i as f32 * 0.1).sin()).collect();
+        let started = Instant::now();
+        while started.elapsed() < Duration::from_millis(500) {
+            analysis.push(&block, &block);
+            thread::sleep(Duration::from_millis(10));
+        }
+        analysis.stop();
+        let elapsed = started.elapsed().as_secs_f32();
+
+        let frames = *frames.lock().unwrap();
+        assert!(frames as f32 <= elapsed * 20.0 + 1.0, "{} frames in {} s", frames, elapsed);
+        assert!(frames >= 5, "{} frames in {} s", frames, elapsed);
+    }
+}
@@ -29,4 +29,31 @@ impl AudioFrame {
             timestamp,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Newest column of the scrolling spectrogram
+#[derive(Clone, Debug, Serialize)]
+pub struct SpectrogramFrame {
+    pub row: Vec<f32>,            // znormalizowane 0-1 pasma, bez wygładzania w czasie
+    pub frequencies: Vec<f32>,    // częstotliwości centralne pasm (oś log / mel)
+    pub history: usize,           // ile wierszy trzyma widok przewijany
+    pub timestamp: u64,
+}
+
+/// Whole spectrogram history (time x frequency), oldest row first
+#[derive(Clone, Debug, Serialize)]
+pub struct SpectrogramSnapshot {
+    pub rows: Vec<Vec<f32>>,
+    pub timestamps: Vec<u64>,
+    pub frequencies: Vec<f32>,
+}
+
+/// Decimated waveform for the oscilloscope view
+#[derive(Clone, Debug, Serialize)]
+pub struct WaveformFrame {
+    pub min: Vec<f32>,            // minimum próbki w każdym kubełku
+    pub max: Vec<f32>,            // maksimum próbki w każdym kubełku
+    pub samples_per_point: f32,   // ile próbek przypada na jeden punkt
+    pub sample_rate: usize,
+    pub timestamp: u64,
+}
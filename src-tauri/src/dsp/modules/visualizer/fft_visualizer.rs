@@ -1,10 +1,15 @@
+use serde::Serialize;
 use tauri::Emitter;
 use super::audio_spectrum::*;
+use super::loudness::{LoudnessMeter, MeterFrame};
+use super::settings::VisualizerSettings;
+use super::tuner::{Tuner, DEFAULT_A4_REFERENCE};
 
 use realfft::RealFftPlanner;
 use rustfft::num_complex::Complex;
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
-use std::time::{Instant};
+use std::time::{Duration, Instant};
 
 use crate::dsp::modules::utils::windows::apply_hanning_window;
 use crate::dsp::modules::yin_detector::detector::yin::YINDetector;
@@ -12,58 +17,182 @@ use crate::dsp::modules::yin_detector::detector::PitchDetector;
 
 /// Noise gate -- all values are zeroed if RMS is below this threshold (to cut off noise).
 const NOISE_GATE_RMS: f32 = 0.002;
-///  Bottom dB for normalization
-const NOISE_FLOOR_DB: f32 = -70.0;
-/// Top dB for normalization (0 dB = pełna skala, -10 dB = 1/3 skali, itp.)
-const MAX_DB: f32 = -10.0;
-/// Współczynnik wygładzania EMA: 0 = brak reakcji, 1 = brak wygładzania.
-const SMOOTHING_ALPHA: f32 = 0.3;
-/// Liczba binów po liniowym próbkowaniu 
-const NUM_BINS: usize = 256;
-/// Dolna częstotliwość wykresu (najniższy głos ludzki ~80 Hz).
-const FREQ_MIN: f32 = 80.0;
-/// Górna częstotliwość wykresu (górna granica głosu ludzkiego ~8 kHz).
-const FREQ_MAX: f32 = 8_000.0;
+
+/// Where analysis frames go - the app's frontend, or a recorder in tests.
+pub trait FrameSink: Send + 'static {
+    fn send<S: Serialize + Clone>(&self, event: &str, payload: &S) -> anyhow::Result<()>;
+}
+
+impl FrameSink for tauri::AppHandle {
+    fn send<S: Serialize + Clone>(&self, event: &str, payload: &S) -> anyhow::Result<()> {
+        self.emit(event, payload)?;
+        Ok(())
+    }
+}
 
 pub struct SpectrumVisualizer {
   fft_planner: Arc<Mutex<RealFftPlanner<f32>>>,
   fft_size: usize,
   sample_rate: usize,
   last_emit: Arc<Mutex<Instant>>,
+  settings: Mutex<VisualizerSettings>,
   smoothed_spectrum: Arc<Mutex<Vec<f32>>>,
+  spectrogram: Mutex<VecDeque<(u64, Vec<f32>)>>,
   yin_detector: Mutex<YINDetector<f32>>,
+  tuner: Mutex<Tuner>,
+  input_meter: Mutex<LoudnessMeter>,
+  output_meter: Mutex<LoudnessMeter>,
 }
 
 impl SpectrumVisualizer {
+    /// Visualizer with the analysis window sized for `sample_rate`, as used by the processor.
+    pub fn for_sample_rate(sample_rate: usize) -> Self {
+        Self::new(sample_rate, Self::analysis_fft_size(sample_rate))
+    }
+
+    /// Window of roughly 40 ms (2048 samples at 48 kHz), so YIN's half-window lag reaches below 50 Hz.
+    pub fn analysis_fft_size(sample_rate: usize) -> usize {
+        (sample_rate / 24).next_power_of_two().max(2048)
+    }
+
     pub fn new(sample_rate: usize, fft_size: usize) -> Self {
+        let settings = VisualizerSettings::default();
         Self {
             fft_planner: Arc::new(Mutex::new(RealFftPlanner::new())),
             fft_size,
             sample_rate,
             last_emit: Arc::new(Mutex::new(Instant::now())),
-            smoothed_spectrum: Arc::new(Mutex::new(vec![0.0f32; NUM_BINS])),
+            smoothed_spectrum: Arc::new(Mutex::new(vec![0.0f32; settings.num_bins])),
+            settings: Mutex::new(settings),
+            spectrogram: Mutex::new(VecDeque::new()),
             yin_detector: Mutex::new(YINDetector::new(fft_size, fft_size / 2)),
+            tuner: Mutex::new(Tuner::new(DEFAULT_A4_REFERENCE)),
+            input_meter: Mutex::new(LoudnessMeter::new(sample_rate, 1)),
+            output_meter: Mutex::new(LoudnessMeter::new(sample_rate, 1)),
+        }
+    }
+
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    pub fn set_tuner_reference(&self, reference: f32) {
+        self.tuner.lock().unwrap().set_reference(reference);
+    }
+
+    pub fn get_tuner_reference(&self) -> f32 {
+        self.tuner.lock().unwrap().get_reference()
+    }
+
+    /// Rebuilds both meters for a new interleaved channel count, readings start over.
+    pub fn set_meter_channels(&self, channels: usize) {
+        for meter in [&self.input_meter, &self.output_meter] {
+            let mut meter = meter.lock().unwrap();
+            if meter.channels() != channels.max(1) {
+                *meter = LoudnessMeter::new(self.sample_rate, channels);
+            }
+        }
+    }
+
+    /// Feeds interleaved frames from before the modulation chain into the input meter.
+    pub fn meter_input(&self, samples: &[f32]) {
+        self.input_meter.lock().unwrap().process(samples);
+    }
+
+    /// Feeds interleaved frames from after the modulation chain into the output meter.
+    pub fn meter_output(&self, samples: &[f32]) {
+        self.output_meter.lock().unwrap().process(samples);
+    }
+
+    /// Clears integrated loudness, held true peaks and clip counters.
+    pub fn reset_meters(&self) {
+        self.input_meter.lock().unwrap().reset();
+        self.output_meter.lock().unwrap().reset();
+    }
+
+    pub fn set_settings(&self, settings: VisualizerSettings) -> anyhow::Result<()> {
+        settings.validate(self.sample_rate)?;
+
+        let mut current = self.settings.lock().unwrap();
+        if current.num_bins != settings.num_bins {
+            *self.smoothed_spectrum.lock().unwrap() = vec![0.0f32; settings.num_bins];
+        }
+        // Wiersze spektrogramu z innymi pasmami nie pasują do nowej osi
+        if current.num_bins != settings.num_bins
+            || current.freq_min != settings.freq_min
+            || current.freq_max != settings.freq_max
+            || current.spectrogram_scale != settings.spectrogram_scale
+            || !settings.spectrogram_enabled
+        {
+            self.spectrogram.lock().unwrap().clear();
+        }
+        *current = settings;
+        Ok(())
+    }
+
+    pub fn get_settings(&self) -> VisualizerSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn get_spectrogram(&self) -> SpectrogramSnapshot {
+        let settings = self.get_settings();
+        let history = self.spectrogram.lock().unwrap();
+        SpectrogramSnapshot {
+            rows: history.iter().map(|(_, row)| row.clone()).collect(),
+            timestamps: history.iter().map(|&(timestamp, _)| timestamp).collect(),
+            frequencies: settings.spectrogram_scale.band_centers(
+                settings.freq_min,
+                settings.freq_max,
+                settings.num_bins,
+            ),
         }
     }
 
-    /// Normalizuje wartości dB do zakresu 0-1, odcinając szum poniżej NOISE_FLOOR_DB.
-    fn normalize(&self, magnitudes_db: &[f32]) -> Vec<f32> {
-        let range = MAX_DB - NOISE_FLOOR_DB;
+    /// Normalizuje wartości dB do zakresu 0-1, odcinając szum poniżej progu szumu.
+    fn normalize(magnitudes_db: &[f32], settings: &VisualizerSettings) -> Vec<f32> {
+        let range = settings.max_db - settings.noise_floor_db;
         magnitudes_db
             .iter()
             .map(|&db| {
-                if db <= NOISE_FLOOR_DB {
+                if db <= settings.noise_floor_db {
                     0.0
-                } else if db >= MAX_DB {
+                } else if db >= settings.max_db {
                     1.0
                 } else {
-                    (db - NOISE_FLOOR_DB) / range
+                    (db - settings.noise_floor_db) / range
                 }
             })
             .collect()
     }
 
-    fn compute_fft(&self, samples: &[f32]) -> anyhow::Result<AudioFrame> {
+    /// Próbkuje biny FFT do pasm o podanych krawędziach (maksimum w każdym paśmie).
+    /// Pasma węższe niż bin (dół skali log/mel) interpolują liniowo w środku pasma,
+    /// żeby sąsiednie pasma nie powtarzały tej samej wartości.
+    fn resample_bands(magnitudes_db: &[f32], hz_per_bin: f32, edges: &[f32]) -> Vec<f32> {
+        let last = magnitudes_db.len() - 1;
+        edges
+            .windows(2)
+            .map(|edge| {
+                let b0 = ((edge[0] / hz_per_bin) as usize).min(last);
+                let b1 = ((edge[1] / hz_per_bin) as usize).clamp(b0, last);
+                if b1 > b0 {
+                    return magnitudes_db[b0..=b1].iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+                }
+                let center = (edge[0] + edge[1]) * 0.5 / hz_per_bin;
+                let i = (center as usize).min(last);
+                let frac = (center - i as f32).clamp(0.0, 1.0);
+                let next = magnitudes_db[(i + 1).min(last)];
+                magnitudes_db[i] + (next - magnitudes_db[i]) * frac
+            })
+            .collect()
+    }
+
+    fn compute_fft(&self, samples: &[f32]) -> anyhow::Result<(AudioFrame, Option<SpectrogramFrame>)> {
+        let settings = self.get_settings();
         let rms = (samples.iter().map(|&s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
 
         let mut input = samples.to_vec();
@@ -86,23 +215,14 @@ impl SpectrumVisualizer {
           })
           .collect();
 
-        // --- Liniowe próbkowanie → NUM_BINS binów (FREQ_MIN – FREQ_MAX) ---
-        let bin_start_global = (FREQ_MIN / hz_per_bin) as usize;
-        let bin_end_global   = ((FREQ_MAX / hz_per_bin) as usize + 1).min(magnitudes_db.len() - 1);
-        let total_bins = bin_end_global - bin_start_global;
-        let step = total_bins as f32 / NUM_BINS as f32;
-
-        let linear_db: Vec<f32> = (0..NUM_BINS).map(|i| {
-            let b0 = bin_start_global + (i as f32 * step) as usize;
-            let b1 = (bin_start_global + ((i + 1) as f32 * step) as usize).min(bin_end_global);
-            let b1 = b1.max(b0 + 1).min(magnitudes_db.len() - 1);
-            magnitudes_db[b0..=b1].iter().cloned().fold(f32::NEG_INFINITY, f32::max)
-        }).collect();
-
-        let mut normalized = self.normalize(&linear_db);
+        // --- Próbkowanie → num_bins pasm (freq_min – freq_max) na wybranej osi ---
+        let edges = settings.scale.band_edges(settings.freq_min, settings.freq_max, settings.num_bins);
+        let band_db = Self::resample_bands(&magnitudes_db, hz_per_bin, &edges);
+        let mut normalized = Self::normalize(&band_db, &settings);
 
         // Noise gate: jeśli RMS jest poniżej progu, wyzeruj wszystkie wartości (odcięcie szumu).
-        if rms < NOISE_GATE_RMS {
+        let gated = rms < NOISE_GATE_RMS;
+        if gated {
             for v in normalized.iter_mut() {
                 *v = 0.0;
             }
@@ -110,15 +230,13 @@ impl SpectrumVisualizer {
 
         let mut smoothed = self.smoothed_spectrum.lock().unwrap();
         for (s, n) in smoothed.iter_mut().zip(normalized.iter()) {
-            *s = SMOOTHING_ALPHA * n + (1.0 - SMOOTHING_ALPHA) * *s;
+            *s = settings.smoothing * n + (1.0 - settings.smoothing) * *s;
         }
         let spec = smoothed.clone();
         drop(smoothed);
 
-        // Częstotliwości centralne dla każdego binu (liniowo)
-        let frequencies: Vec<f32> = (0..NUM_BINS)
-            .map(|i| FREQ_MIN + (i as f32 + 0.5) * (FREQ_MAX - FREQ_MIN) / NUM_BINS as f32)
-            .collect();
+        // Częstotliwości centralne dla każdego pasma
+        let frequencies = settings.scale.band_centers(settings.freq_min, settings.freq_max, settings.num_bins);
 
         let pitch = {
             let mut yin = self.yin_detector.lock().unwrap();
@@ -132,38 +250,237 @@ impl SpectrumVisualizer {
             .unwrap_or(0.0)
         };
 
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+        let timestamp = Self::timestamp();
 
-        Ok(AudioFrame::new(
-            rms,
-            pitch,
-            spec,
-            frequencies,
-            timestamp,
+        let spectrogram = if settings.spectrogram_enabled {
+            let row = if gated {
+                vec![0.0; settings.num_bins]
+            } else {
+                let edges = settings.spectrogram_scale.band_edges(
+                    settings.freq_min,
+                    settings.freq_max,
+                    settings.num_bins,
+                );
+                Self::normalize(&Self::resample_bands(&magnitudes_db, hz_per_bin, &edges), &settings)
+            };
+
+            let mut history = self.spectrogram.lock().unwrap();
+            history.push_back((timestamp, row.clone()));
+            while history.len() > settings.spectrogram_history {
+                history.pop_front();
+            }
+
+            Some(SpectrogramFrame {
+                row,
+                frequencies: settings.spectrogram_scale.band_centers(
+                    settings.freq_min,
+                    settings.freq_max,
+                    settings.num_bins,
+                ),
+                history: settings.spectrogram_history,
+                timestamp,
+            })
+        } else {
+            None
+        };
+
+        Ok((
+            AudioFrame::new(
+                rms,
+                pitch,
+                spec,
+                frequencies,
+                timestamp,
+            ),
+            spectrogram,
         ))
     }
 
-    pub fn emit_spectrum(
+    /// Zmniejsza bufor do `points` par min/max (widok oscyloskopu).
+    fn decimate_waveform(&self, samples: &[f32], points: usize, timestamp: u64) -> WaveformFrame {
+        let points = points.min(samples.len()).max(1);
+        let samples_per_point = samples.len() as f32 / points as f32;
+
+        let mut min = Vec::with_capacity(points);
+        let mut max = Vec::with_capacity(points);
+        for i in 0..points {
+            let start = (i as f32 * samples_per_point) as usize;
+            let end = (((i + 1) as f32 * samples_per_point) as usize).clamp(start + 1, samples.len());
+            let bucket = &samples[start..end];
+            min.push(bucket.iter().cloned().fold(f32::INFINITY, f32::min));
+            max.push(bucket.iter().cloned().fold(f32::NEG_INFINITY, f32::max));
+        }
+
+        WaveformFrame {
+            min,
+            max,
+            samples_per_point,
+            sample_rate: self.sample_rate,
+            timestamp,
+        }
+    }
+
+    fn timestamp() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    }
+
+    /// Emits one frame of every enabled stream if the frame interval has elapsed since the last emit.
+    /// `window` holds the latest `fft_size` samples, `recent` everything received since the last frame.
+    /// Returns whether a frame was emitted.
+    pub fn emit_spectrum<F: FrameSink>(
         &self,
-        app_handle: &tauri::AppHandle,
-        processed: &[f32],
-    ) -> anyhow::Result<()> {
-        if processed.len() < self.fft_size {
-            return Ok(());
+        sink: &F,
+        window: &[f32],
+        recent: &[f32],
+    ) -> anyhow::Result<bool> {
+        if window.len() < self.fft_size {
+            return Ok(false);
         }
 
+        let settings = self.get_settings();
         let mut last_emit = self.last_emit.lock().unwrap();
         let now = Instant::now();
+        if now.duration_since(*last_emit) < Duration::from_secs_f32(1.0 / settings.frame_rate) {
+            return Ok(false);
+        }
+        *last_emit = now;
+        drop(last_emit);
 
-        let spectrum = self.compute_fft(&processed[..self.fft_size])?;
-        app_handle.emit("audio-spectrum", &spectrum)?;
+        let (spectrum, spectrogram) = self.compute_fft(&window[window.len() - self.fft_size..])?;
+        sink.send("audio-spectrum", &spectrum)?;
 
-        *last_emit = now;
+        if let Some(spectrogram) = spectrogram {
+            sink.send("audio-spectrogram", &spectrogram)?;
+        }
 
-        Ok(())
+        if settings.waveform_enabled && !recent.is_empty() {
+            let waveform = self.decimate_waveform(recent, settings.waveform_points, spectrum.timestamp);
+            sink.send("audio-waveform", &waveform)?;
+        }
+
+        let tuner_frame = self.tuner.lock().unwrap().analyze(spectrum.pitch, spectrum.timestamp);
+        sink.send("audio-tuner", &tuner_frame)?;
+
+        let meter_frame = MeterFrame {
+            input: self.input_meter.lock().unwrap().take_reading(),
+            output: self.output_meter.lock().unwrap().take_reading(),
+            timestamp: spectrum.timestamp,
+        };
+        sink.send("audio-meter", &meter_frame)?;
+
+        Ok(true)
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::settings::FrequencyScale;
+
+    fn sine(frequency: f32, sample_rate: usize, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| 0.5 * (std::f32::consts::TAU * frequency * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn band_edges_follow_scale() {
+        let linear = FrequencyScale::Linear.band_edges(100.0, 1_100.0, 10);
+        assert!((linear[1] - 200.0).abs() < 1e-3);
+
+        let log = FrequencyScale::Log.band_edges(100.0, 10_000.0, 2);
+        assert!((log[1] - 1_000.0).abs() < 1.0);
+
+        let mel = FrequencyScale::Mel.band_edges(100.0, 8_000.0, 16);
+        assert!((mel[0] - 100.0).abs() < 0.1);
+        assert!((mel[16] - 8_000.0).abs() < 1.0);
+        // Mel bands widen with frequency
+        assert!(mel[16] - mel[15] > mel[1] - mel[0]);
+    }
+
+    #[test]
+    fn spectrogram_peak_follows_tone() {
+        let sample_rate = 48_000;
+        let visualizer = SpectrumVisualizer::new(sample_rate, 2048);
+        let mut settings = visualizer.get_settings();
+        settings.num_bins = 64;
+        settings.spectrogram_enabled = true;
+        settings.spectrogram_scale = FrequencyScale::Log;
+        settings.spectrogram_history = 4;
+        visualizer.set_settings(settings).unwrap();
+
+        for _ in 0..6 {
+            visualizer.compute_fft(&sine(1_000.0, sample_rate, 2048)).unwrap();
+        }
+
+        let snapshot = visualizer.get_spectrogram();
+        assert_eq!(snapshot.rows.len(), 4);
+        let row = snapshot.rows.last().unwrap();
+        let peak = row
+            .iter()
+            .enumerate()
+            .fold(0, |best, (i, &v)| if v > row[best] { i } else { best });
+        assert!((snapshot.frequencies[peak] / 1_000.0).log2().abs() < 0.15);
+    }
+
+    #[test]
+    fn low_tones_reach_the_tuner() {
+        let sample_rate = 48_000;
+        let visualizer = SpectrumVisualizer::for_sample_rate(sample_rate);
+        let (frame, _) = visualizer
+            .compute_fft(&sine(98.0, sample_rate, visualizer.fft_size()))
+            .unwrap();
+        assert!((frame.pitch - 98.0).abs() < 1.0, "pitch {}", frame.pitch);
+    }
+
+    #[test]
+    fn low_log_bands_are_distinct() {
+        let sample_rate = 48_000;
+        let visualizer = SpectrumVisualizer::for_sample_rate(sample_rate);
+        let settings = VisualizerSettings {
+            scale: FrequencyScale::Log,
+            noise_floor_db: -120.0,
+            smoothing: 1.0,
+            ..Default::default()
+        };
+        visualizer.set_settings(settings).unwrap();
+
+        let (frame, _) = visualizer
+            .compute_fft(&sine(110.0, sample_rate, visualizer.fft_size()))
+            .unwrap();
+        let low: Vec<f32> = frame
+            .frequencies
+            .iter()
+            .zip(frame.spectrum.iter())
+            .take_while(|(f, _)| **f < 150.0)
+            .map(|(_, v)| *v)
+            .collect();
+        assert!(low.len() > 16);
+        assert!(low.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let visualizer = SpectrumVisualizer::new(48_000, 480);
+        let settings = VisualizerSettings { freq_max: 30_000.0, ..Default::default() };
+        assert!(visualizer.set_settings(settings).is_err());
+
+        let settings = VisualizerSettings { noise_floor_db: 0.0, ..Default::default() };
+        assert!(visualizer.set_settings(settings).is_err());
+    }
+
+    #[test]
+    fn waveform_keeps_extremes() {
+        let visualizer = SpectrumVisualizer::new(48_000, 480);
+        let samples = sine(100.0, 48_000, 960);
+        let waveform = visualizer.decimate_waveform(&samples, 32, 0);
+        assert_eq!(waveform.min.len(), 32);
+        let peak = waveform.max.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let trough = waveform.min.iter().cloned().fold(f32::INFINITY, f32::min);
+        assert!((peak - 0.5).abs() < 0.01);
+        assert!((trough + 0.5).abs() < 0.01);
+    }
+}
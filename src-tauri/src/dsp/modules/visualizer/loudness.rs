@@ -0,0 +1,443 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+use crate::dsp::modules::filters::BiquadFilter;
+
+/// Level reported for silence instead of -inf (which JSON can not carry).
+pub const SILENCE_DB: f32 = -120.0;
+/// Samples at or above this magnitude count as clipped.
+const CLIP_LEVEL: f32 = 1.0;
+/// EBU R128 measurement blocks are built from 100 ms sub-blocks.
+const SUB_BLOCK_MS: usize = 100;
+const MOMENTARY_SUB_BLOCKS: usize = 4; // 400 ms
+const SHORT_TERM_SUB_BLOCKS: usize = 30; // 3 s
+/// Absolute and relative gates for the integrated loudness (BS.1770-4).
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Gating blocks are binned by loudness from the absolute gate up, so the integrated
+/// value never rescans them. 0.1 LU bins up to +6 LUFS (K-weighting adds gain on top of 0 dBFS).
+const HISTOGRAM_STEP_LU: f64 = 0.1;
+const HISTOGRAM_BINS: usize = 760;
+/// True-peak oversampling factor and interpolation taps per phase.
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+/// Meter readout of one signal point
+#[derive(Clone, Debug, Serialize)]
+pub struct LoudnessReading {
+    pub sample_peak_db: f32,      // największa próbka od ostatniej ramki (dBFS)
+    pub true_peak_db: f32,        // 4x nadpróbkowany szczyt od ostatniej ramki (dBTP)
+    pub max_true_peak_db: f32,    // największy true peak od resetu
+    pub rms_db: f32,              // RMS z ostatnich 400 ms (dBFS)
+    pub momentary_lufs: f32,      // EBU R128 momentary (400 ms)
+    pub short_term_lufs: f32,     // EBU R128 short-term (3 s)
+    pub integrated_lufs: f32,     // EBU R128 integrated (bramkowany, od resetu)
+    pub clip_count: u64,          // przesterowane próbki od resetu
+    pub clipped: bool,            // czy od ostatniej ramki coś się przesterowało
+}
+
+/// Meters before and after the modulation chain, emitted as `audio-meter`
+#[derive(Clone, Debug, Serialize)]
+pub struct MeterFrame {
+    pub input: LoudnessReading,
+    pub output: LoudnessReading,
+    pub timestamp: u64,
+}
+
+fn to_db(amplitude: f32) -> f32 {
+    if amplitude > 0.0 {
+        (20.0 * amplitude.log10()).max(SILENCE_DB)
+    } else {
+        SILENCE_DB
+    }
+}
+
+fn power_to_lufs(power: f64) -> f64 {
+    if power > 0.0 {
+        -0.691 + 10.0 * power.log10()
+    } else {
+        SILENCE_DB as f64
+    }
+}
+
+fn histogram_bin(lufs: f64) -> usize {
+    (((lufs - ABSOLUTE_GATE_LUFS) / HISTOGRAM_STEP_LU) as usize).min(HISTOGRAM_BINS - 1)
+}
+
+/// K-weighting filters and true-peak history of one channel
+#[derive(Clone)]
+struct MeterChannel {
+    k_shelf: BiquadFilter,
+    k_highpass: BiquadFilter,
+    true_peak_history: VecDeque<f32>,
+}
+
+impl MeterChannel {
+    fn reset(&mut self) {
+        self.k_shelf.reset();
+        self.k_highpass.reset();
+        self.true_peak_history.iter_mut().for_each(|s| *s = 0.0);
+    }
+
+    fn interpolated_peak(&mut self, sample: f32, interpolator: &[f32]) -> f32 {
+        self.true_peak_history.pop_back();
+        self.true_peak_history.push_front(sample);
+
+        let mut peak = sample.abs();
+        for phase in interpolator.chunks_exact(TAPS_PER_PHASE) {
+            let value: f32 = phase
+                .iter()
+                .zip(self.true_peak_history.iter())
+                .map(|(h, x)| h * x)
+                .sum();
+            peak = peak.max(value.abs());
+        }
+        peak
+    }
+}
+
+/// Loudness meter over interleaved frames following ITU-R BS.1770-4 / EBU R128.
+/// Channel powers are summed with unit weights (left, right and centre); peaks
+/// and clips are the largest over all channels.
+pub struct LoudnessMeter {
+    channels: Vec<MeterChannel>,
+
+    sub_block_len: usize,
+    sub_block_pos: usize,
+    sub_block_weighted: f64,  // sum of squares of the K-weighted signal, summed over channels
+    sub_block_plain: f64,     // sum of squares of the raw signal, averaged over channels
+    weighted_history: VecDeque<f64>,
+    plain_history: VecDeque<f64>,
+    // Every 400 ms block above the absolute gate: summed mean squares and counts per bin, and in total
+    gating_power: Vec<f64>,
+    gating_count: Vec<u64>,
+    gated_power: f64,
+    gated_count: u64,
+
+    interpolator: Vec<f32>,   // polyphase low-pass, phase-major
+
+    sample_peak: f32,
+    true_peak: f32,
+    max_true_peak: f32,
+    clip_count: u64,
+    clipped: bool,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: usize, channels: usize) -> Self {
+        let mut channel = MeterChannel {
+            k_shelf: BiquadFilter::new(),
+            k_highpass: BiquadFilter::new(),
+            true_peak_history: VecDeque::from(vec![0.0; TAPS_PER_PHASE]),
+        };
+        Self::configure_k_weighting(&mut channel, sample_rate);
+
+        Self {
+            channels: vec![channel; channels.max(1)],
+
+            sub_block_len: (sample_rate * SUB_BLOCK_MS / 1000).max(1),
+            sub_block_pos: 0,
+            sub_block_weighted: 0.0,
+            sub_block_plain: 0.0,
+            weighted_history: VecDeque::with_capacity(SHORT_TERM_SUB_BLOCKS),
+            plain_history: VecDeque::with_capacity(MOMENTARY_SUB_BLOCKS),
+            gating_power: vec![0.0; HISTOGRAM_BINS],
+            gating_count: vec![0; HISTOGRAM_BINS],
+            gated_power: 0.0,
+            gated_count: 0,
+
+            interpolator: Self::design_interpolator(),
+
+            sample_peak: 0.0,
+            true_peak: 0.0,
+            max_true_peak: 0.0,
+            clip_count: 0,
+            clipped: false,
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    // K-weighting: high shelf (head effects) followed by the RLB high-pass,
+    // recomputed for any sample rate as in the reference implementation
+    fn configure_k_weighting(channel: &mut MeterChannel, sample_rate: usize) {
+        let fs = sample_rate as f64;
+
+        let f0 = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (PI * f0 / fs).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        channel.k_shelf.set_coefficients(
+            (vh + vb * k / q + k * k) as f32,
+            (2.0 * (k * k - vh)) as f32,
+            (vh - vb * k / q + k * k) as f32,
+            (1.0 + k / q + k * k) as f32,
+            (2.0 * (k * k - 1.0)) as f32,
+            (1.0 - k / q + k * k) as f32,
+        );
+
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (PI * f0 / fs).tan();
+        channel.k_highpass.set_coefficients(
+            1.0,
+            -2.0,
+            1.0,
+            (1.0 + k / q + k * k) as f32,
+            (2.0 * (k * k - 1.0)) as f32,
+            (1.0 - k / q + k * k) as f32,
+        );
+    }
+
+    // Hann-windowed sinc low-pass at the original Nyquist, split into OVERSAMPLING phases
+    fn design_interpolator() -> Vec<f32> {
+        let taps = OVERSAMPLING * TAPS_PER_PHASE;
+        let center = (taps - 1) as f64 / 2.0;
+        let prototype: Vec<f64> = (0..taps)
+            .map(|i| {
+                let x = (i as f64 - center) / OVERSAMPLING as f64;
+                let sinc = if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) };
+                let window = 0.5 - 0.5 * (2.0 * PI * (i as f64 + 0.5) / taps as f64).cos();
+                sinc * window
+            })
+            .collect();
+
+        let mut phases = vec![0.0f32; taps];
+        for phase in 0..OVERSAMPLING {
+            for tap in 0..TAPS_PER_PHASE {
+                phases[phase * TAPS_PER_PHASE + tap] = prototype[tap * OVERSAMPLING + phase] as f32;
+            }
+        }
+        phases
+    }
+
+    pub fn reset(&mut self) {
+        self.channels.iter_mut().for_each(MeterChannel::reset);
+
+        self.sub_block_pos = 0;
+        self.sub_block_weighted = 0.0;
+        self.sub_block_plain = 0.0;
+        self.weighted_history.clear();
+        self.plain_history.clear();
+        self.gating_power.iter_mut().for_each(|power| *power = 0.0);
+        self.gating_count.iter_mut().for_each(|count| *count = 0);
+        self.gated_power = 0.0;
+        self.gated_count = 0;
+
+        self.sample_peak = 0.0;
+        self.true_peak = 0.0;
+        self.max_true_peak = 0.0;
+        self.clip_count = 0;
+        self.clipped = false;
+    }
+
+    /// Meters interleaved frames - a trailing partial frame is ignored.
+    pub fn process(&mut self, frames: &[f32]) {
+        let channel_count = self.channels.len();
+        for frame in frames.chunks_exact(channel_count) {
+            for (channel, &sample) in self.channels.iter_mut().zip(frame) {
+                let magnitude = sample.abs();
+                self.sample_peak = self.sample_peak.max(magnitude);
+                if magnitude >= CLIP_LEVEL {
+                    self.clip_count += 1;
+                    self.clipped = true;
+                }
+
+                self.true_peak = self.true_peak.max(channel.interpolated_peak(sample, &self.interpolator));
+
+                let weighted = channel.k_highpass.process_internal(channel.k_shelf.process_internal(sample)) as f64;
+                self.sub_block_weighted += weighted * weighted;
+                self.sub_block_plain += (sample as f64) * (sample as f64) / channel_count as f64;
+            }
+            self.sub_block_pos += 1;
+            if self.sub_block_pos == self.sub_block_len {
+                self.finish_sub_block();
+            }
+        }
+        self.max_true_peak = self.max_true_peak.max(self.true_peak);
+    }
+
+    fn finish_sub_block(&mut self) {
+        let len = self.sub_block_len as f64;
+        self.weighted_history.push_back(self.sub_block_weighted / len);
+        self.plain_history.push_back(self.sub_block_plain / len);
+        if self.weighted_history.len() > SHORT_TERM_SUB_BLOCKS {
+            self.weighted_history.pop_front();
+        }
+        if self.plain_history.len() > MOMENTARY_SUB_BLOCKS {
+            self.plain_history.pop_front();
+        }
+        self.sub_block_pos = 0;
+        self.sub_block_weighted = 0.0;
+        self.sub_block_plain = 0.0;
+
+        // Every 100 ms closes a 400 ms gating block (75 % overlap)
+        if self.weighted_history.len() >= MOMENTARY_SUB_BLOCKS {
+            let power = self.mean_of_last(MOMENTARY_SUB_BLOCKS);
+            let lufs = power_to_lufs(power);
+            if lufs > ABSOLUTE_GATE_LUFS {
+                let bin = histogram_bin(lufs);
+                self.gating_power[bin] += power;
+                self.gating_count[bin] += 1;
+                self.gated_power += power;
+                self.gated_count += 1;
+            }
+        }
+    }
+
+    fn mean_of_last(&self, count: usize) -> f64 {
+        let count = count.min(self.weighted_history.len());
+        if count == 0 {
+            return 0.0;
+        }
+        self.weighted_history.iter().rev().take(count).sum::<f64>() / count as f64
+    }
+
+    // Two-pass gate over the histogram - the relative gate lands on a bin edge, 0.1 LU at most off
+    pub fn integrated_lufs(&self) -> f32 {
+        if self.gated_count == 0 {
+            return SILENCE_DB;
+        }
+        let mean = self.gated_power / self.gated_count as f64;
+        let relative_gate = power_to_lufs(mean) + RELATIVE_GATE_LU;
+
+        let first_bin = if relative_gate > ABSOLUTE_GATE_LUFS {
+            histogram_bin(relative_gate)
+        } else {
+            0
+        };
+        let power: f64 = self.gating_power[first_bin..].iter().sum();
+        let count: u64 = self.gating_count[first_bin..].iter().sum();
+        if count == 0 {
+            return SILENCE_DB;
+        }
+        power_to_lufs(power / count as f64) as f32
+    }
+
+    /// Current readout. Peaks and the clip flag restart for the next frame.
+    pub fn take_reading(&mut self) -> LoudnessReading {
+        let rms = if self.plain_history.is_empty() {
+            0.0
+        } else {
+            (self.plain_history.iter().sum::<f64>() / self.plain_history.len() as f64).sqrt()
+        };
+
+        let reading = LoudnessReading {
+            sample_peak_db: to_db(self.sample_peak),
+            true_peak_db: to_db(self.true_peak),
+            max_true_peak_db: to_db(self.max_true_peak),
+            rms_db: to_db(rms as f32),
+            momentary_lufs: power_to_lufs(self.mean_of_last(MOMENTARY_SUB_BLOCKS)) as f32,
+            short_term_lufs: power_to_lufs(self.mean_of_last(SHORT_TERM_SUB_BLOCKS)) as f32,
+            integrated_lufs: self.integrated_lufs(),
+            clip_count: self.clip_count,
+            clipped: self.clipped,
+        };
+
+        self.sample_peak = 0.0;
+        self.true_peak = 0.0;
+        self.clipped = false;
+        reading
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(amplitude: f32, frequency: f32, phase: f32, sample_rate: usize, seconds: f32) -> Vec<f32> {
+        let len = (sample_rate as f32 * seconds) as usize;
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32 + phase).sin())
+            .collect()
+    }
+
+    #[test]
+    fn sine_at_minus_20_dbfs_reads_minus_23_lufs() {
+        // A 1 kHz sine is practically unaffected by K-weighting, so a mono
+        // -20 dBFS tone reads -20 dB - 3.01 dB for the sine's mean power
+        for sample_rate in [44_100, 48_000] {
+            let mut meter = LoudnessMeter::new(sample_rate, 1);
+            meter.process(&sine(0.1, 997.0, 0.0, sample_rate, 5.0));
+            let reading = meter.take_reading();
+
+            assert!((reading.integrated_lufs + 23.0).abs() < 0.1, "{}", reading.integrated_lufs);
+            assert!((reading.momentary_lufs + 23.0).abs() < 0.1);
+            assert!((reading.short_term_lufs + 23.0).abs() < 0.1);
+            assert!((reading.rms_db + 23.01).abs() < 0.05);
+            assert!((reading.sample_peak_db + 20.0).abs() < 0.05);
+            assert_eq!(reading.clip_count, 0);
+        }
+    }
+
+    #[test]
+    fn quiet_passages_fall_under_the_relative_gate() {
+        // 40 dB quieter than the rest - above the absolute gate, well under the relative one
+        let sample_rate = 48_000;
+        let mut meter = LoudnessMeter::new(sample_rate, 1);
+        meter.process(&sine(0.1, 997.0, 0.0, sample_rate, 5.0));
+        meter.process(&sine(0.001, 997.0, 0.0, sample_rate, 5.0));
+        let integrated = meter.take_reading().integrated_lufs;
+        assert!((integrated + 23.0).abs() < 0.3, "{}", integrated);
+
+        meter.reset();
+        assert_eq!(meter.take_reading().integrated_lufs, SILENCE_DB);
+    }
+
+    #[test]
+    fn stereo_channels_are_summed_and_clipped_separately() {
+        // The same -20 dBFS tone on both channels is twice the power of mono, +3.01 LU
+        let sample_rate = 48_000;
+        let mono = sine(0.1, 997.0, 0.0, sample_rate, 5.0);
+        let mut stereo: Vec<f32> = mono.iter().flat_map(|&s| [s, s]).collect();
+        let mut meter = LoudnessMeter::new(sample_rate, 2);
+        meter.process(&stereo);
+        let reading = meter.take_reading();
+        assert!((reading.integrated_lufs + 19.99).abs() < 0.1, "{}", reading.integrated_lufs);
+        assert!((reading.rms_db + 23.01).abs() < 0.05);
+
+        // A clip on one channel only, cancelled by the other in a downmix
+        stereo[100] = 1.0;
+        stereo[101] = -1.0;
+        meter.process(&stereo[..1000]);
+        let reading = meter.take_reading();
+        assert_eq!(reading.clip_count, 2);
+        assert!(reading.sample_peak_db > -0.01);
+    }
+
+    #[test]
+    fn true_peak_finds_inter_sample_peaks() {
+        // Sine at fs/4 shifted by 45 degrees never hits its peak on a sample
+        let sample_rate = 48_000;
+        let mut meter = LoudnessMeter::new(sample_rate, 1);
+        meter.process(&sine(1.0, 12_000.0, std::f32::consts::FRAC_PI_4, sample_rate, 0.1));
+        let reading = meter.take_reading();
+
+        assert!((reading.sample_peak_db + 3.01).abs() < 0.05);
+        assert!(reading.true_peak_db > -0.5, "{}", reading.true_peak_db);
+        assert_eq!(reading.clip_count, 0);
+    }
+
+    #[test]
+    fn clips_are_counted_and_silence_gated() {
+        let mut meter = LoudnessMeter::new(48_000, 1);
+        meter.process(&[0.5, 1.0, -1.2, 0.3]);
+        let reading = meter.take_reading();
+        assert_eq!(reading.clip_count, 2);
+        assert!(reading.clipped);
+
+        meter.process(&[0.0; 16]);
+        let reading = meter.take_reading();
+        assert_eq!(reading.clip_count, 2);
+        assert!(!reading.clipped);
+
+        let mut meter = LoudnessMeter::new(48_000, 1);
+        meter.process(&vec![0.0; 48_000]);
+        assert_eq!(meter.take_reading().integrated_lufs, SILENCE_DB);
+    }
+}
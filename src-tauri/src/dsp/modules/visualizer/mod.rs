@@ -1,2 +1,6 @@
+pub mod analysis_thread;
 pub mod audio_spectrum;
-pub mod fft_visualizer;
\ No newline at end of file
+pub mod fft_visualizer;
+pub mod loudness;
+pub mod settings;
+pub mod tuner;
\ No newline at end of file
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+/// Frequency axis used when resampling FFT bins into display bands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrequencyScale {
+    Linear,
+    Log,
+    Mel,
+}
+
+impl FrequencyScale {
+    fn hz_to_axis(self, hz: f32) -> f32 {
+        match self {
+            FrequencyScale::Linear => hz,
+            FrequencyScale::Log => hz.ln(),
+            FrequencyScale::Mel => 2595.0 * (1.0 + hz / 700.0).log10(),
+        }
+    }
+
+    fn axis_to_hz(self, value: f32) -> f32 {
+        match self {
+            FrequencyScale::Linear => value,
+            FrequencyScale::Log => value.exp(),
+            FrequencyScale::Mel => 700.0 * (10f32.powf(value / 2595.0) - 1.0),
+        }
+    }
+
+    /// `bands + 1` band edges in Hz, evenly spaced on this axis between `min` and `max`.
+    pub fn band_edges(self, min: f32, max: f32, bands: usize) -> Vec<f32> {
+        let (lo, hi) = (self.hz_to_axis(min), self.hz_to_axis(max));
+        (0..=bands)
+            .map(|i| self.axis_to_hz(lo + (hi - lo) * i as f32 / bands as f32))
+            .collect()
+    }
+
+    /// Centre frequency of every band, taken in the middle of the band on this axis.
+    pub fn band_centers(self, min: f32, max: f32, bands: usize) -> Vec<f32> {
+        let (lo, hi) = (self.hz_to_axis(min), self.hz_to_axis(max));
+        (0..bands)
+            .map(|i| self.axis_to_hz(lo + (hi - lo) * (i as f32 + 0.5) / bands as f32))
+            .collect()
+    }
+}
+
+/// Runtime configuration of the spectrum, spectrogram and waveform streams
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VisualizerSettings {
+    pub freq_min: f32,                      // lowest displayed frequency in Hz
+    pub freq_max: f32,                      // highest displayed frequency in Hz
+    pub num_bins: usize,                    // number of display bands
+    pub scale: FrequencyScale,              // axis of the `audio-spectrum` stream
+    pub noise_floor_db: f32,                // dB mapped to 0
+    pub max_db: f32,                        // dB mapped to 1
+    pub smoothing: f32,                     // EMA factor: 0 = frozen, 1 = no smoothing
+    pub frame_rate: f32,                    // analysis frames emitted per second
+
+    pub spectrogram_enabled: bool,
+    pub spectrogram_scale: FrequencyScale,  // axis of the `audio-spectrogram` stream
+    pub spectrogram_history: usize,         // rows kept for the scrolling view
+
+    pub waveform_enabled: bool,
+    pub waveform_points: usize,             // min/max pairs per emitted waveform frame
+}
+
+impl Default for VisualizerSettings {
+    fn default() -> Self {
+        Self {
+            freq_min: 80.0,
+            freq_max: 8_000.0,
+            num_bins: 256,
+            scale: FrequencyScale::Linear,
+            noise_floor_db: -70.0,
+            max_db: -10.0,
+            smoothing: 0.3,
+            frame_rate: 30.0,
+
+            spectrogram_enabled: false,
+            spectrogram_scale: FrequencyScale::Mel,
+            spectrogram_history: 200,
+
+            waveform_enabled: false,
+            waveform_points: 256,
+        }
+    }
+}
+
+impl VisualizerSettings {
+    pub fn validate(&self, sample_rate: usize) -> anyhow::Result<()> {
+        let nyquist = sample_rate as f32 / 2.0;
+        if !(self.freq_min > 0.0 && self.freq_min < self.freq_max && self.freq_max <= nyquist) {
+            return Err(anyhow::anyhow!(
+                "Frequency range must satisfy 0 < min < max <= {} Hz, got {}..{} Hz",
+                nyquist,
+                self.freq_min,
+                self.freq_max
+            ));
+        }
+        if !(8..=1024).contains(&self.num_bins) {
+            return Err(anyhow::anyhow!("Bin count must be between 8 and 1024, got {}", self.num_bins));
+        }
+        if self.noise_floor_db >= self.max_db {
+            return Err(anyhow::anyhow!(
+                "Noise floor ({} dB) must be below the maximum level ({} dB)",
+                self.noise_floor_db,
+                self.max_db
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.smoothing) {
+            return Err(anyhow::anyhow!("Smoothing must be between 0 and 1, got {}", self.smoothing));
+        }
+        if !(1.0..=120.0).contains(&self.frame_rate) {
+            return Err(anyhow::anyhow!("Frame rate must be between 1 and 120 fps, got {}", self.frame_rate));
+        }
+        if !(1..=2000).contains(&self.spectrogram_history) {
+            return Err(anyhow::anyhow!(
+                "Spectrogram history must be between 1 and 2000 rows, got {}",
+                self.spectrogram_history
+            ));
+        }
+        if !(16..=4096).contains(&self.waveform_points) {
+            return Err(anyhow::anyhow!(
+                "Waveform points must be between 16 and 4096, got {}",
+                self.waveform_points
+            ));
+        }
+        Ok(())
+    }
+}
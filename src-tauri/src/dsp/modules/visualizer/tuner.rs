@@ -0,0 +1,201 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Default concert pitch for A4 in Hz.
+pub const DEFAULT_A4_REFERENCE: f32 = 440.0;
+/// Allowed range for the A4 reference (covers baroque to modern orchestra tunings).
+pub const MIN_A4_REFERENCE: f32 = 400.0;
+pub const MAX_A4_REFERENCE: f32 = 480.0;
+/// How far back the stability measure looks.
+const STABILITY_WINDOW_MS: u64 = 500;
+/// Pitch spread (standard deviation in cents) at which stability reaches zero.
+const STABILITY_MAX_DEVIATION_CENTS: f32 = 50.0;
+/// Minimum number of voiced frames in the window before stability is reported.
+const STABILITY_MIN_FRAMES: usize = 3;
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Tuner readout sent to the frontend on every analysis frame
+#[derive(Clone, Debug, Serialize)]
+pub struct TunerFrame {
+    pub frequency: f32,           // detected pitch in Hz, 0 when unvoiced
+    pub note: Option<String>,     // nearest note name, e.g. "C#"
+    pub octave: Option<i32>,      // scientific pitch notation octave (A4 = 440 Hz)
+    pub midi_note: Option<i32>,   // nearest MIDI note number
+    pub cents: f32,               // offset from the nearest note, -50..50
+    pub reference: f32,           // A4 reference used for the conversion
+    pub stability: f32,           // 0 = wandering pitch, 1 = rock steady
+    pub deviation_cents: f32,     // standard deviation of the pitch over the stability window
+    pub timestamp: u64,
+}
+
+/// Nearest note of `frequency` as `(midi note, cents offset)` for a given A4 reference.
+pub fn frequency_to_note(frequency: f32, reference: f32) -> Option<(i32, f32)> {
+    if frequency <= 0.0 || !frequency.is_finite() {
+        return None;
+    }
+    let midi = 69.0 + 12.0 * (frequency / reference).log2();
+    let nearest = midi.round();
+    Some((nearest as i32, (midi - nearest) * 100.0))
+}
+
+/// Note name and octave of a MIDI note number (60 = C4).
+pub fn note_name(midi_note: i32) -> (&'static str, i32) {
+    let name = NOTE_NAMES[midi_note.rem_euclid(12) as usize];
+    let octave = midi_note.div_euclid(12) - 1;
+    (name, octave)
+}
+
+pub struct Tuner {
+    reference: f32,
+    /// Recent voiced frames as (timestamp in ms, fractional MIDI note)
+    history: VecDeque<(u64, f32)>,
+    /// Timestamps of all recent frames, voiced or not
+    frames: VecDeque<u64>,
+}
+
+impl Tuner {
+    pub fn new(reference: f32) -> Self {
+        Self {
+            reference: reference.clamp(MIN_A4_REFERENCE, MAX_A4_REFERENCE),
+            history: VecDeque::new(),
+            frames: VecDeque::new(),
+        }
+    }
+
+    pub fn set_reference(&mut self, reference: f32) {
+        self.reference = reference.clamp(MIN_A4_REFERENCE, MAX_A4_REFERENCE);
+        // Stored notes depend on the reference, so start over
+        self.history.clear();
+        self.frames.clear();
+    }
+
+    pub fn get_reference(&self) -> f32 {
+        self.reference
+    }
+
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.frames.clear();
+    }
+
+    /// Converts one detected pitch (0 when unvoiced) into a tuner readout.
+    pub fn analyze(&mut self, frequency: f32, timestamp: u64) -> TunerFrame {
+        let note = frequency_to_note(frequency, self.reference);
+
+        self.frames.push_back(timestamp);
+        if let Some((midi_note, cents)) = note {
+            self.history.push_back((timestamp, midi_note as f32 + cents / 100.0));
+        }
+        let oldest = timestamp.saturating_sub(STABILITY_WINDOW_MS);
+        while self.frames.front().is_some_and(|&t| t < oldest) {
+            self.frames.pop_front();
+        }
+        while self.history.front().is_some_and(|&(t, _)| t < oldest) {
+            self.history.pop_front();
+        }
+
+        let (stability, deviation_cents) = self.stability();
+
+        match note {
+            Some((midi_note, cents)) => {
+                let (name, octave) = note_name(midi_note);
+                TunerFrame {
+                    frequency,
+                    note: Some(name.to_string()),
+                    octave: Some(octave),
+                    midi_note: Some(midi_note),
+                    cents,
+                    reference: self.reference,
+                    stability,
+                    deviation_cents,
+                    timestamp,
+                }
+            }
+            None => TunerFrame {
+                frequency: 0.0,
+                note: None,
+                octave: None,
+                midi_note: None,
+                cents: 0.0,
+                reference: self.reference,
+                stability,
+                deviation_cents,
+                timestamp,
+            },
+        }
+    }
+
+    /// Stability in 0..1 and the pitch spread in cents over the stability window.
+    /// Unvoiced gaps lower the stability proportionally to how much of the window they cover.
+    fn stability(&self) -> (f32, f32) {
+        if self.history.len() < STABILITY_MIN_FRAMES {
+            return (0.0, 0.0);
+        }
+
+        let count = self.history.len() as f32;
+        let mean = self.history.iter().map(|&(_, note)| note).sum::<f32>() / count;
+        let variance = self
+            .history
+            .iter()
+            .map(|&(_, note)| (note - mean) * (note - mean))
+            .sum::<f32>()
+            / count;
+        let deviation_cents = variance.sqrt() * 100.0;
+
+        let voiced_ratio = count / self.frames.len().max(1) as f32;
+        let steadiness = (1.0 - deviation_cents / STABILITY_MAX_DEVIATION_CENTS).clamp(0.0, 1.0);
+
+        (steadiness * voiced_ratio, deviation_cents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_conversion() {
+        assert_eq!(frequency_to_note(440.0, 440.0), Some((69, 0.0)));
+        assert_eq!(note_name(69), ("A", 4));
+        assert_eq!(note_name(60), ("C", 4));
+        assert_eq!(note_name(59), ("B", 3));
+
+        let (midi, cents) = frequency_to_note(261.63 * 2f32.powf(20.0 / 1200.0), 440.0).unwrap();
+        assert_eq!(midi, 60);
+        assert!((cents - 20.0).abs() < 0.5);
+
+        // With A4 = 432 Hz, 432 Hz is an in-tune A4
+        let (midi, cents) = frequency_to_note(432.0, 432.0).unwrap();
+        assert_eq!(midi, 69);
+        assert!(cents.abs() < 1e-3);
+
+        assert_eq!(frequency_to_note(0.0, 440.0), None);
+    }
+
+    #[test]
+    fn steady_pitch_is_stable() {
+        let mut tuner = Tuner::new(DEFAULT_A4_REFERENCE);
+        let mut frame = tuner.analyze(0.0, 0);
+        for i in 1..50 {
+            frame = tuner.analyze(440.0, i * 10);
+        }
+        assert_eq!(frame.note.as_deref(), Some("A"));
+        assert_eq!(frame.octave, Some(4));
+        assert!(frame.stability > 0.95);
+    }
+
+    #[test]
+    fn wandering_pitch_is_unstable() {
+        let mut tuner = Tuner::new(DEFAULT_A4_REFERENCE);
+        let mut frame = tuner.analyze(0.0, 0);
+        for i in 1..50 {
+            let frequency = if i % 2 == 0 { 440.0 } else { 466.16 };
+            frame = tuner.analyze(frequency, i * 10);
+        }
+        assert!(frame.deviation_cents > 40.0);
+        assert!(frame.stability < 0.2);
+    }
+}
@@ -0,0 +1,492 @@
+//! The pYIN pitch detection algorithm is based on the paper
+//! *[pYIN: A fundamental frequency estimator using probabilistic threshold distributions](https://www.eecs.qmul.ac.uk/~simond/pub/2014/MauchDixon-PYIN-ICASSP2014.pdf)*.
+//! It extends [YIN][super::yin] so that a single frame no longer commits to one period estimate.
+//!
+//! Plain YIN picks the first dip of the *cumulative mean normalized difference function* $d\'(t)$
+//! below one fixed threshold. On breathy or noisy input the dip at the true period and the dip at
+//! twice the period are often both close to that threshold, so the estimate flips between octaves
+//! from frame to frame.
+//!
+//! pYIN instead sweeps the threshold $s$ over $(0, 1]$ with a Beta prior. Every threshold selects
+//! one dip, and each dip collects the prior mass of the thresholds that selected it. This gives a
+//! set of *pitch candidates* with probabilities for every frame.
+//!
+//! ## Note tracking
+//! The candidates are treated as observations of a hidden Markov model whose states are pitch bins
+//! (a fraction of a semitone wide), each in a voiced and an unvoiced variant. Transitions only allow
+//! small pitch moves between consecutive frames and make voicing changes unlikely. Viterbi decoding
+//! over this model gives a smooth track that ignores isolated octave errors and knows when the
+//! signal is unvoiced.
+//!
+//! ## Implementation
+//! The detector is frame-based like the other [PitchDetector]s: every call to `get_pitch` feeds one
+//! frame into the model. Decoding runs online, so the returned pitch is the end of the most likely
+//! path. [PYINDetector::set_decoding_lag] trades latency for a more stable track by backtracking a
+//! few frames before answering.
+
+use std::collections::VecDeque;
+
+use super::internals::{windowed_square_error, yin_normalize_square_error, DetectorInternals, Pitch};
+use super::PitchDetector;
+use crate::dsp::modules::yin_detector::float::Float;
+use crate::dsp::modules::yin_detector::utils::buffer::square_sum;
+
+/// Number of thresholds swept over `(0, 1]`.
+const THRESHOLD_COUNT: usize = 100;
+/// Parameters of the Beta prior over thresholds (mean 0.1, as in the paper).
+const THRESHOLD_PRIOR_ALPHA: f64 = 2.0;
+const THRESHOLD_PRIOR_BETA: f64 = 18.0;
+/// Weight given to the global minimum when no dip falls below a threshold.
+const ABSOLUTE_MIN_WEIGHT: f64 = 0.01;
+/// How much of the candidate mass is trusted as evidence of voicing.
+const YIN_TRUST: f64 = 0.5;
+
+/// Lowest pitch state of the model (A1).
+const MIN_FREQUENCY: f64 = 55.0;
+const BINS_PER_SEMITONE: usize = 5;
+/// Five octaves of pitch states: 55 Hz to 1760 Hz.
+const PITCH_BINS: usize = 5 * 12 * BINS_PER_SEMITONE;
+/// Largest pitch move allowed between two frames, in bins (two semitones).
+const TRANSITION_HALF_WIDTH: usize = 2 * BINS_PER_SEMITONE;
+/// Probability of staying voiced (or unvoiced) between two frames.
+const VOICING_SELF_TRANSITION: f64 = 0.99;
+
+#[derive(Debug, Clone)]
+pub struct PYINDetector<T>
+where
+    T: Float + std::iter::Sum,
+{
+    internals: DetectorInternals<T>,
+    threshold_prior: Vec<f64>,
+    tracker: PitchTracker,
+}
+
+impl<T> PYINDetector<T>
+where
+    T: Float + std::iter::Sum,
+{
+    pub fn new(size: usize, padding: usize) -> Self {
+        let internals = DetectorInternals::<T>::new(size, padding);
+        PYINDetector {
+            internals,
+            threshold_prior: beta_threshold_prior(),
+            tracker: PitchTracker::new(),
+        }
+    }
+
+    /// Number of frames the decoder looks back before reporting a pitch.
+    ///
+    /// With a lag of 0 the pitch of the newest frame is returned. A larger lag lets later frames
+    /// correct the decision, at the cost of reporting the pitch `lag` frames late.
+    pub fn set_decoding_lag(&mut self, lag: usize) {
+        self.tracker.lag = lag;
+    }
+
+    /// Forgets the decoded track, e.g. when the input stream restarts.
+    pub fn reset(&mut self) {
+        self.tracker.reset();
+    }
+
+    /// Pitch candidates of one frame as `(frequency, probability)` pairs.
+    fn candidates(&mut self, signal: &[T], sample_rate: usize) -> Vec<(f64, f64)> {
+        let window_size = signal.len() / 2;
+        let sample_rate = sample_rate as f64;
+
+        let result_ref = self.internals.buffers.get_real_buffer();
+        let mut result_guard = result_ref.lock().unwrap();
+        let result = &mut result_guard[..window_size];
+
+        windowed_square_error(signal, window_size, &mut self.internals.buffers, result);
+        yin_normalize_square_error(result);
+
+        let max_frequency = MIN_FREQUENCY * 2f64.powf(PITCH_BINS as f64 / (12.0 * BINS_PER_SEMITONE as f64));
+        let min_tau = ((sample_rate / max_frequency).floor() as usize).max(1);
+        let max_tau = ((sample_rate / MIN_FREQUENCY).ceil() as usize).min(window_size.saturating_sub(2));
+
+        // Every local minimum of d'(t), as (refined period, depth).
+        let mut minima: Vec<(f64, f64)> = Vec::new();
+        for tau in min_tau..=max_tau {
+            let prev = result[tau - 1].to_f64().unwrap_or(1.0);
+            let curr = result[tau].to_f64().unwrap_or(1.0);
+            let next = result[tau + 1].to_f64().unwrap_or(1.0);
+            if curr < prev && curr <= next {
+                let curvature = prev - 2.0 * curr + next;
+                let offset = if curvature > 0.0 {
+                    ((prev - next) / (2.0 * curvature)).clamp(-0.5, 0.5)
+                } else {
+                    0.0
+                };
+                minima.push((tau as f64 + offset, curr));
+            }
+        }
+
+        if minima.is_empty() {
+            return Vec::new();
+        }
+
+        let global_min = minima
+            .iter()
+            .enumerate()
+            .min_by(|a, b| a.1.1.total_cmp(&b.1.1))
+            .map(|(i, _)| i)
+            .unwrap();
+
+        let mut probabilities = vec![0.0; minima.len()];
+        for (i, weight) in self.threshold_prior.iter().enumerate() {
+            let threshold = (i + 1) as f64 / THRESHOLD_COUNT as f64;
+            match minima.iter().position(|&(_, depth)| depth < threshold) {
+                Some(index) => probabilities[index] += weight,
+                None => probabilities[global_min] += weight * ABSOLUTE_MIN_WEIGHT,
+            }
+        }
+
+        minima
+            .iter()
+            .zip(probabilities)
+            .filter(|&(_, p)| p > 0.0)
+            .map(|(&(period, _), p)| (sample_rate / period, p))
+            .collect()
+    }
+}
+
+/// Pitch detection based on the pYIN algorithm. See <https://www.eecs.qmul.ac.uk/~simond/pub/2014/MauchDixon-PYIN-ICASSP2014.pdf>
+impl<T> PitchDetector<T> for PYINDetector<T>
+where
+    T: Float + std::iter::Sum,
+{
+    fn get_pitch(
+        &mut self,
+        signal: &[T],
+        sample_rate: usize,
+        power_threshold: T,
+        clarity_threshold: T,
+    ) -> Option<Pitch<T>> {
+        assert_eq!(signal.len(), self.internals.size);
+
+        // Quiet frames still advance the model, as evidence of silence.
+        let candidates = if square_sum(signal) < power_threshold {
+            Vec::new()
+        } else {
+            self.candidates(signal, sample_rate)
+        };
+
+        let (frequency, voiced_probability) = self.tracker.push_frame(candidates)?;
+        let clarity = T::from_f64(voiced_probability).unwrap();
+        if clarity < clarity_threshold {
+            return None;
+        }
+
+        Some(Pitch {
+            frequency: T::from_f64(frequency).unwrap(),
+            clarity,
+        })
+    }
+}
+
+/// Discretized Beta(2, 18) density over the swept thresholds, normalized to sum to 1.
+fn beta_threshold_prior() -> Vec<f64> {
+    let density: Vec<f64> = (1..=THRESHOLD_COUNT)
+        .map(|i| {
+            let s = (i as f64 - 0.5) / THRESHOLD_COUNT as f64;
+            s.powf(THRESHOLD_PRIOR_ALPHA - 1.0) * (1.0 - s).powf(THRESHOLD_PRIOR_BETA - 1.0)
+        })
+        .collect();
+    let total: f64 = density.iter().sum();
+    density.into_iter().map(|d| d / total).collect()
+}
+
+fn frequency_to_bin(frequency: f64) -> Option<usize> {
+    if frequency <= 0.0 {
+        return None;
+    }
+    let bin = (12.0 * BINS_PER_SEMITONE as f64 * (frequency / MIN_FREQUENCY).log2()).round();
+    if bin < 0.0 || bin >= PITCH_BINS as f64 {
+        None
+    } else {
+        Some(bin as usize)
+    }
+}
+
+fn bin_to_frequency(bin: usize) -> f64 {
+    MIN_FREQUENCY * 2f64.powf(bin as f64 / (12.0 * BINS_PER_SEMITONE as f64))
+}
+
+/// Online Viterbi decoder over `PITCH_BINS` voiced states followed by `PITCH_BINS` unvoiced states.
+#[derive(Debug, Clone)]
+struct PitchTracker {
+    /// Probability of the best path ending in each state, normalized every frame.
+    delta: Vec<f64>,
+    /// Best predecessor of every state, newest frame last.
+    backpointers: VecDeque<Vec<usize>>,
+    /// Candidates and voiced mass of the frames kept for backtracking, newest frame last.
+    frames: VecDeque<(Vec<(f64, f64)>, f64)>,
+    /// Triangular pitch transition weights indexed by bin distance.
+    transition_weights: Vec<f64>,
+    /// Per source bin normalization so that every row of the transition matrix sums to 1.
+    row_norms: Vec<f64>,
+    lag: usize,
+}
+
+impl PitchTracker {
+    fn new() -> Self {
+        let transition_weights: Vec<f64> = (0..=TRANSITION_HALF_WIDTH)
+            .map(|distance| (TRANSITION_HALF_WIDTH + 1 - distance) as f64)
+            .collect();
+
+        let row_norms = (0..PITCH_BINS)
+            .map(|bin| {
+                let low = bin.saturating_sub(TRANSITION_HALF_WIDTH);
+                let high = (bin + TRANSITION_HALF_WIDTH).min(PITCH_BINS - 1);
+                (low..=high).map(|to| transition_weights[bin.abs_diff(to)]).sum()
+            })
+            .collect();
+
+        PitchTracker {
+            delta: vec![1.0 / (2 * PITCH_BINS) as f64; 2 * PITCH_BINS],
+            backpointers: VecDeque::new(),
+            frames: VecDeque::new(),
+            transition_weights,
+            row_norms,
+            lag: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.delta.fill(1.0 / (2 * PITCH_BINS) as f64);
+        self.backpointers.clear();
+        self.frames.clear();
+    }
+
+    fn observation_probabilities(candidates: &[(f64, f64)]) -> (Vec<f64>, f64) {
+        let mut observations = vec![0.0; 2 * PITCH_BINS];
+        for &(frequency, probability) in candidates {
+            if let Some(bin) = frequency_to_bin(frequency) {
+                observations[bin] += probability;
+            }
+        }
+
+        let voiced_mass: f64 = observations[..PITCH_BINS].iter().sum();
+        let trusted_mass = YIN_TRUST * voiced_mass.min(1.0);
+        if voiced_mass > 0.0 {
+            let scale = trusted_mass / voiced_mass;
+            observations[..PITCH_BINS].iter_mut().for_each(|o| *o *= scale);
+        }
+        let unvoiced = (1.0 - trusted_mass) / PITCH_BINS as f64;
+        observations[PITCH_BINS..].iter_mut().for_each(|o| *o = unvoiced);
+
+        (observations, voiced_mass.min(1.0))
+    }
+
+    /// Adds one frame and returns the decoded `(frequency, voiced probability)` of the frame
+    /// `lag` frames back, or `None` when that frame is decoded as unvoiced.
+    fn push_frame(&mut self, candidates: Vec<(f64, f64)>) -> Option<(f64, f64)> {
+        let (observations, voiced_mass) = Self::observation_probabilities(&candidates);
+
+        let states = 2 * PITCH_BINS;
+        let mut next_delta = vec![0.0; states];
+        let mut pointers = vec![0; states];
+
+        for to in 0..states {
+            let to_voiced = to < PITCH_BINS;
+            let to_bin = to % PITCH_BINS;
+            let low = to_bin.saturating_sub(TRANSITION_HALF_WIDTH);
+            let high = (to_bin + TRANSITION_HALF_WIDTH).min(PITCH_BINS - 1);
+
+            let mut best = 0.0;
+            let mut best_from = to;
+            for from_bin in low..=high {
+                let pitch_weight =
+                    self.transition_weights[from_bin.abs_diff(to_bin)] / self.row_norms[from_bin];
+                for from_voiced in [true, false] {
+                    let from = if from_voiced { from_bin } else { from_bin + PITCH_BINS };
+                    let voicing = if from_voiced == to_voiced {
+                        VOICING_SELF_TRANSITION
+                    } else {
+                        1.0 - VOICING_SELF_TRANSITION
+                    };
+                    let score = self.delta[from] * pitch_weight * voicing;
+                    if score > best {
+                        best = score;
+                        best_from = from;
+                    }
+                }
+            }
+
+            next_delta[to] = best * observations[to];
+            pointers[to] = best_from;
+        }
+
+        let total: f64 = next_delta.iter().sum();
+        if total > 0.0 && total.is_finite() {
+            next_delta.iter_mut().for_each(|d| *d /= total);
+        } else {
+            next_delta.fill(1.0 / states as f64);
+        }
+        self.delta = next_delta;
+
+        self.backpointers.push_back(pointers);
+        self.frames.push_back((candidates, voiced_mass));
+        while self.backpointers.len() > self.lag + 1 {
+            self.backpointers.pop_front();
+            self.frames.pop_front();
+        }
+
+        // Backtrack from the most likely current state to the frame being reported.
+        let mut state = self
+            .delta
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| i)
+            .unwrap();
+        for pointers in self.backpointers.iter().skip(1).rev() {
+            state = pointers[state];
+        }
+
+        if state >= PITCH_BINS {
+            return None;
+        }
+
+        // Report the candidate closest to the decoded bin; fall back to the bin centre.
+        let (candidates, voiced_mass) = self.frames.front().unwrap();
+        let frequency = candidates
+            .iter()
+            .filter_map(|&(frequency, _)| frequency_to_bin(frequency).map(|bin| (frequency, bin)))
+            .filter(|&(_, bin)| bin.abs_diff(state) <= BINS_PER_SEMITONE)
+            .min_by_key(|&(_, bin)| bin.abs_diff(state))
+            .map(|(frequency, _)| frequency)
+            .unwrap_or_else(|| bin_to_frequency(state));
+
+        Some((frequency, *voiced_mass))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: usize = 44_100;
+    const FRAME_SIZE: usize = 2048;
+    const HOP_SIZE: usize = 512;
+
+    /// Voice-like test tone: a fundamental with decaying harmonics, driven by an
+    /// instantaneous frequency curve so glides and vibrato stay phase-continuous.
+    fn synthesize<F: Fn(f64) -> f64>(duration: f64, frequency_at: F) -> Vec<f32> {
+        let len = (duration * SAMPLE_RATE as f64) as usize;
+        let mut phase = 0.0f64;
+        (0..len)
+            .map(|n| {
+                let t = n as f64 / SAMPLE_RATE as f64;
+                phase += std::f64::consts::TAU * frequency_at(t) / SAMPLE_RATE as f64;
+                let sample = phase.sin() + 0.5 * (2.0 * phase).sin() + 0.3 * (3.0 * phase).sin();
+                (0.3 * sample) as f32
+            })
+            .collect()
+    }
+
+    /// Runs the detector over `signal` and pairs every reported pitch with the true
+    /// frequency at the centre of its frame.
+    fn track<F: Fn(f64) -> f64>(signal: &[f32], frequency_at: F) -> Vec<(Option<f32>, f64)> {
+        let mut detector = PYINDetector::<f32>::new(FRAME_SIZE, FRAME_SIZE / 2);
+        (0..=signal.len() - FRAME_SIZE)
+            .step_by(HOP_SIZE)
+            .map(|start| {
+                let frame = &signal[start..start + FRAME_SIZE];
+                let pitch = detector
+                    .get_pitch(frame, SAMPLE_RATE, 0.01, 0.3)
+                    .map(|p| p.frequency);
+                let centre = (start + FRAME_SIZE / 2) as f64 / SAMPLE_RATE as f64;
+                (pitch, frequency_at(centre))
+            })
+            .collect()
+    }
+
+    fn cents(a: f64, b: f64) -> f64 {
+        1200.0 * (a / b).log2()
+    }
+
+    #[test]
+    fn threshold_prior_is_normalized() {
+        let prior = beta_threshold_prior();
+        assert_eq!(prior.len(), THRESHOLD_COUNT);
+        assert!((prior.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn steady_tone() {
+        let signal = synthesize(0.5, |_| 220.0);
+        let track = track(&signal, |_| 220.0);
+
+        for (pitch, expected) in track.iter().skip(2) {
+            let pitch = pitch.expect("steady tone should be voiced") as f64;
+            assert!(cents(pitch, *expected).abs() < 10.0, "{} Hz vs {} Hz", pitch, expected);
+        }
+    }
+
+    #[test]
+    fn glide_is_tracked_without_octave_jumps() {
+        // One octave up in one second, 150 Hz to 300 Hz.
+        let glide = |t: f64| 150.0 * 2f64.powf(t);
+        let signal = synthesize(1.0, glide);
+        let track = track(&signal, glide);
+
+        let mut previous: Option<f64> = None;
+        for (pitch, expected) in track.iter().skip(2) {
+            let pitch = pitch.expect("glide should be voiced") as f64;
+            assert!(cents(pitch, *expected).abs() < 50.0, "{} Hz vs {} Hz", pitch, expected);
+            if let Some(previous) = previous {
+                assert!(cents(pitch, previous).abs() < 100.0, "jump {} Hz -> {} Hz", previous, pitch);
+            }
+            previous = Some(pitch);
+        }
+    }
+
+    #[test]
+    fn vibrato_is_tracked() {
+        // 5.5 Hz vibrato of +-50 cents around A3.
+        let vibrato = |t: f64| 220.0 * 2f64.powf(0.5 / 12.0 * (std::f64::consts::TAU * 5.5 * t).sin());
+        let signal = synthesize(1.0, vibrato);
+        let track = track(&signal, vibrato);
+
+        for (pitch, expected) in track.iter().skip(2) {
+            let pitch = pitch.expect("vibrato should be voiced") as f64;
+            assert!(cents(pitch, *expected).abs() < 40.0, "{} Hz vs {} Hz", pitch, expected);
+        }
+    }
+
+    #[test]
+    fn breathy_tone_keeps_its_octave() {
+        // Weak fundamental under a strong second harmonic plus noise, the case where plain
+        // YIN tends to lock onto the octave above for some frames.
+        let mut seed = 0x2545_f491u32;
+        let len = SAMPLE_RATE;
+        let signal: Vec<f32> = (0..len)
+            .map(|n| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = (seed >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0;
+                let phase = std::f32::consts::TAU * 180.0 * n as f32 / SAMPLE_RATE as f32;
+                0.1 * phase.sin() + 0.3 * (2.0 * phase).sin() + 0.05 * noise
+            })
+            .collect();
+        let track = track(&signal, |_| 180.0);
+
+        let voiced = track.iter().filter(|(pitch, _)| pitch.is_some()).count();
+        assert!(voiced > track.len() / 2, "only {} of {} frames voiced", voiced, track.len());
+        for (pitch, expected) in track.iter().skip(2) {
+            if let Some(pitch) = pitch {
+                assert!(cents(*pitch as f64, *expected).abs() < 50.0, "{} Hz vs {} Hz", pitch, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn silence_is_unvoiced() {
+        let mut signal = synthesize(0.5, |_| 220.0);
+        let silence_start = signal.len() / 2;
+        signal[silence_start..].fill(0.0);
+        let track = track(&signal, |_| 220.0);
+
+        let (pitch, _) = track.last().unwrap();
+        assert!(pitch.is_none());
+    }
+}
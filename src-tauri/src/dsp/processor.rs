@@ -3,28 +3,38 @@
 use super::modules::chains::filters_chain::*;
 use super::modules::chains::modulation_chain::*;
 
+use super::modules::visualizer::analysis_thread::AnalysisThread;
 use super::modules::visualizer::fft_visualizer::*;
+use super::modules::visualizer::audio_spectrum::SpectrogramSnapshot;
+use super::modules::visualizer::settings::VisualizerSettings;
 use super::modules::utils::ParameterValue;
+use super::modules::modulation_matrix::ModulationMatrixSettings;
+use super::modules::carrier::{Carrier, CarrierSource};
 use super::traits::{EffectChain, FilterChain};
 use crate::dsp::traits::EffectModule;
 use super::effect_factory::create_effect_from_name;
+use std::sync::Arc;
 
 pub struct AudioProcessor {
-    fft_visualizer: SpectrumVisualizer,
+    fft_visualizer: Arc<SpectrumVisualizer>,
+    analysis_thread: Option<AnalysisThread>,
     filters_chain: FiltersChain,
     modulation_chain: ModulationChain,
     sample_rate: usize,
+    channels: usize, // interleaved channels the effects run on
 }
 
 impl AudioProcessor {
-    pub fn new(sample_rate: usize) -> Self {
-        let modulation_chain = ModulationChain::new();
+    pub fn new(sample_rate: usize, channels: usize) -> Self {
+        let modulation_chain = ModulationChain::new(sample_rate, channels);
 
         AudioProcessor {
-            fft_visualizer: SpectrumVisualizer::new(sample_rate, 480),
+            fft_visualizer: Arc::new(SpectrumVisualizer::for_sample_rate(sample_rate)),
+            analysis_thread: None,
             filters_chain: FiltersChain::new(),
             modulation_chain: modulation_chain,
             sample_rate,
+            channels,
         }
     }
 
@@ -40,26 +50,74 @@ impl AudioProcessor {
         modulated_output
     }
 
-    pub fn process_and_send(&mut self, input: &[f32], app_handle: &tauri::AppHandle) -> Vec<f32> {
-        let output = self.process(input);
+    // Only hands interleaved samples over to the analysis thread - safe to call from the audio callback.
+    // The thread meters every channel and downmixes for the spectrum and pitch.
+    pub fn send_spectrum(&mut self, input: &[f32], output: &[f32]) {
+        if let Some(ref mut analysis) = self.analysis_thread {
+            analysis.push(input, output);
+        }
+    }
 
-        self.fft_visualizer.emit_spectrum(app_handle, &output).ok();
+    pub fn start_analysis(&mut self, app_handle: tauri::AppHandle) {
+        self.stop_analysis();
+        self.analysis_thread = Some(AnalysisThread::start(
+            self.fft_visualizer.clone(),
+            app_handle,
+            self.channels,
+        ));
+    }
 
-        output
+    pub fn stop_analysis(&mut self) {
+        if let Some(mut analysis) = self.analysis_thread.take() {
+            analysis.stop();
+        }
     }
 
-    pub fn send_spectrum(&mut self, input: &[f32], app_handle: &tauri::AppHandle) -> anyhow::Result<()> {
-        self.fft_visualizer.emit_spectrum(app_handle, input)?;
-        Ok(())
+    pub fn set_tuner_reference(&mut self, reference: f32) {
+        self.fft_visualizer.set_tuner_reference(reference);
+    }
+
+    pub fn get_tuner_reference(&self) -> f32 {
+        self.fft_visualizer.get_tuner_reference()
+    }
+
+    pub fn set_visualizer_settings(&mut self, settings: VisualizerSettings) -> anyhow::Result<()> {
+        self.fft_visualizer.set_settings(settings)
+    }
+
+    pub fn get_visualizer_settings(&self) -> VisualizerSettings {
+        self.fft_visualizer.get_settings()
+    }
+
+    pub fn get_spectrogram(&self) -> SpectrogramSnapshot {
+        self.fft_visualizer.get_spectrogram()
+    }
+
+    pub fn reset_meters(&self) {
+        self.fft_visualizer.reset_meters();
+    }
+
+    // Builds an effect for this chain's sample rate and channels without adding it
+    pub fn create_effect(&self, name: &str) -> anyhow::Result<Box<dyn EffectModule>> {
+        create_effect_from_name(name, self.sample_rate, self.channels).map_err(anyhow::Error::msg)
     }
 
     pub fn append_effect_from_name(&mut self, name: &str) -> anyhow::Result<()> {
-        let effect = create_effect_from_name(name, self.sample_rate, 1)
-            .map_err(anyhow::Error::msg)?;
+        let effect = self.create_effect(name)?;
         self.modulation_chain.append_effect(effect);
         Ok(())
     }
 
+    // Swaps the whole chain - the new effects are built and configured before the old ones go
+    pub fn replace_effects(&mut self, effects: Vec<Box<dyn EffectModule>>) {
+        for name in self.modulation_chain.get_active_effects() {
+            self.modulation_chain.remove_effect_from_name(&name);
+        }
+        for effect in effects {
+            self.modulation_chain.append_effect(effect);
+        }
+    }
+
     pub fn remove_effect_from_name(&mut self, name: &str) -> Option<Box<dyn EffectModule>> {
         self.modulation_chain.remove_effect_from_name(name)
     }
@@ -80,6 +138,44 @@ impl AudioProcessor {
         self.modulation_chain.get_effect_parameters(effect_name)
     }
 
+    pub fn set_vocoder_scale(&mut self, scale: crate::dsp::modules::effects::auto_tune::Scale) -> anyhow::Result<()> {
+        self.modulation_chain.set_vocoder_scale(scale)
+    }
+
+    pub fn get_vocoder_scale(&self) -> Option<crate::dsp::modules::effects::auto_tune::Scale> {
+        self.modulation_chain.get_vocoder_scale()
+    }
+
+    pub fn set_vocoder_carrier(&mut self, carrier: Option<Box<dyn Carrier>>) -> anyhow::Result<()> {
+        self.modulation_chain.set_vocoder_carrier(carrier)
+    }
+
+    pub fn get_vocoder_carrier(&self) -> Option<CarrierSource> {
+        self.modulation_chain.get_vocoder_carrier()
+    }
+
+    pub fn get_sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    // Effects are built for a fixed channel count, so the chain starts over empty
+    pub fn set_channels(&mut self, channels: usize) {
+        self.channels = channels.max(1);
+        self.modulation_chain = ModulationChain::new(self.sample_rate, self.channels);
+    }
+
+    pub fn get_channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn set_modulation_matrix(&mut self, settings: ModulationMatrixSettings) -> anyhow::Result<()> {
+        self.modulation_chain.set_modulation_matrix(settings)
+    }
+
+    pub fn get_modulation_matrix(&self) -> ModulationMatrixSettings {
+        self.modulation_chain.get_modulation_matrix()
+    }
+
     pub fn get_active_effects(&self) -> Vec<String> {
         self.modulation_chain.get_active_effects()  
     }
@@ -1,19 +1,50 @@
 
 use super::modules::utils::{ParameterValue, EffectParameter};
 use super::modules::effects::auto_tune::Scale;
+use super::modules::carrier::{Carrier, CarrierSource};
 
 pub trait EffectModule: Send {
     fn process(&mut self, in_b: &[f32], out_b: &mut [f32]);
     fn reset(&mut self);
     fn name(&self) -> &str; 
-    fn set_parameter(&mut self, parameter: ParameterValue) -> anyhow::Result<()>;
+    fn set_parameter(&mut self, parameter: ParameterValue) -> anyhow::Result<()> {
+        self.set_parameter_value(&parameter.name, parameter.value)
+    }
+    // Takes the name by reference - modulation writes it from the audio callback every block
+    fn set_parameter_value(&mut self, name: &str, value: f32) -> anyhow::Result<()>;
     fn get_parameters(&self) -> Vec<EffectParameter>;
+    // Parameters that rebuild state on every write (filter banks, oscillator counts) refuse
+    // modulation - a route would rebuild them once per block
+    fn is_modulatable(&self, _name: &str) -> bool {
+        true
+    }
     fn set_scale(&mut self, _scale: Scale) -> anyhow::Result<()> {
         Err(anyhow::anyhow!("This effect does not support setting a scale"))
     }
     fn get_scale(&self) -> Option<Scale> {
         None
     }
+    // `None` switches back to the effect's own carrier
+    fn set_carrier(&mut self, _carrier: Option<Box<dyn Carrier>>) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("This effect does not support an external carrier"))
+    }
+    fn get_carrier_source(&self) -> Option<CarrierSource> {
+        None
+    }
+    // Effects with their own stereo image (pan, width, decorrelated tails) take both sides at once
+    fn is_stereo(&self) -> bool {
+        false
+    }
+    // Planar stereo - only called when `is_stereo` is true. The default runs the effect
+    // on the centre and copies it to both sides, so a single instance never sees two streams.
+    // The centre is built in `out_r`, which is overwritten afterwards anyway
+    fn process_stereo(&mut self, in_l: &[f32], in_r: &[f32], out_l: &mut [f32], out_r: &mut [f32]) {
+        for (mid, (l, r)) in out_r.iter_mut().zip(in_l.iter().zip(in_r)) {
+            *mid = (l + r) * 0.5;
+        }
+        self.process(out_r, out_l);
+        out_r.copy_from_slice(out_l);
+    }
 }
 
 pub trait EffectChain {
@@ -25,6 +56,10 @@ pub trait EffectChain {
     fn set_effect_parameter(&mut self, effect_name: &str, parameter: ParameterValue) -> anyhow::Result<()>;
     fn set_auto_tune_scale(&mut self, scale_name: Scale) -> anyhow::Result<()>;
     fn get_auto_tune_scale(&self) -> Option<Scale>;
+    fn set_vocoder_scale(&mut self, scale: Scale) -> anyhow::Result<()>;
+    fn get_vocoder_scale(&self) -> Option<Scale>;
+    fn set_vocoder_carrier(&mut self, carrier: Option<Box<dyn Carrier>>) -> anyhow::Result<()>;
+    fn get_vocoder_carrier(&self) -> Option<CarrierSource>;
     fn get_effect_parameters(&self, effect_name: &str) -> anyhow::Result<Vec<EffectParameter>>;
     fn get_active_effects(&self) -> Vec<String>;
 }
@@ -0,0 +1,246 @@
+// Hotkey actions and key combinations ("Ctrl+Shift+M", "F9", ...)
+
+use rdev::Key;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HotkeyAction {
+    ToggleModulation,
+    NextPreset,
+    PreviousPreset,
+    LoadPreset(String),
+    ToggleRecording,
+    SaveReplay,
+    PlayClip(String), // soundboard clip by name
+    StopClips,
+    PushToTalk, // output audible only while held
+    PushToMute, // output silent while held
+}
+
+impl HotkeyAction {
+    // Hold actions react to both press and release, the rest only to press
+    pub fn is_hold(&self) -> bool {
+        matches!(self, HotkeyAction::PushToTalk | HotkeyAction::PushToMute)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    pub keys: String,
+    pub action: HotkeyAction,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+impl Modifiers {
+    // Tracks modifier state, returns true if the key was a modifier
+    pub fn update(&mut self, key: Key, pressed: bool) -> bool {
+        match key {
+            Key::ControlLeft | Key::ControlRight => self.ctrl = pressed,
+            Key::ShiftLeft | Key::ShiftRight => self.shift = pressed,
+            Key::Alt | Key::AltGr => self.alt = pressed,
+            Key::MetaLeft | Key::MetaRight => self.meta = pressed,
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyCombo {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl KeyCombo {
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut modifiers = Modifiers::default();
+        let mut key = None;
+
+        for part in text.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "shift" => modifiers.shift = true,
+                "alt" => modifiers.alt = true,
+                "meta" | "super" | "win" | "cmd" => modifiers.meta = true,
+                _ => {
+                    if key.is_some() {
+                        return Err(anyhow::anyhow!("Hotkey '{}' has more than one non-modifier key", text));
+                    }
+                    key = Some(parse_key(part).ok_or_else(|| anyhow::anyhow!("Unknown key '{}'", part))?);
+                }
+            }
+        }
+
+        let key = key.ok_or_else(|| anyhow::anyhow!("Hotkey '{}' has no non-modifier key", text))?;
+        Ok(KeyCombo { modifiers, key })
+    }
+
+    pub fn matches(&self, key: Key, modifiers: &Modifiers) -> bool {
+        self.key == key && self.modifiers == *modifiers
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.meta {
+            write!(f, "Meta+")?;
+        }
+        write!(f, "{}", key_name(self.key))
+    }
+}
+
+const NAMED_KEYS: &[(&str, Key)] = &[
+    ("Space", Key::Space),
+    ("Tab", Key::Tab),
+    ("Enter", Key::Return),
+    ("Escape", Key::Escape),
+    ("Backspace", Key::Backspace),
+    ("Insert", Key::Insert),
+    ("Delete", Key::Delete),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("Up", Key::UpArrow),
+    ("Down", Key::DownArrow),
+    ("Left", Key::LeftArrow),
+    ("Right", Key::RightArrow),
+    ("CapsLock", Key::CapsLock),
+    ("ScrollLock", Key::ScrollLock),
+    ("NumLock", Key::NumLock),
+    ("Pause", Key::Pause),
+    ("PrintScreen", Key::PrintScreen),
+    ("`", Key::BackQuote),
+    ("-", Key::Minus),
+    ("=", Key::Equal),
+    ("[", Key::LeftBracket),
+    ("]", Key::RightBracket),
+    (";", Key::SemiColon),
+    ("'", Key::Quote),
+    ("\\", Key::BackSlash),
+    (",", Key::Comma),
+    (".", Key::Dot),
+    ("/", Key::Slash),
+    ("F1", Key::F1),
+    ("F2", Key::F2),
+    ("F3", Key::F3),
+    ("F4", Key::F4),
+    ("F5", Key::F5),
+    ("F6", Key::F6),
+    ("F7", Key::F7),
+    ("F8", Key::F8),
+    ("F9", Key::F9),
+    ("F10", Key::F10),
+    ("F11", Key::F11),
+    ("F12", Key::F12),
+    ("Num0", Key::Kp0),
+    ("Num1", Key::Kp1),
+    ("Num2", Key::Kp2),
+    ("Num3", Key::Kp3),
+    ("Num4", Key::Kp4),
+    ("Num5", Key::Kp5),
+    ("Num6", Key::Kp6),
+    ("Num7", Key::Kp7),
+    ("Num8", Key::Kp8),
+    ("Num9", Key::Kp9),
+    ("NumEnter", Key::KpReturn),
+    ("NumPlus", Key::KpPlus),
+    ("NumMinus", Key::KpMinus),
+    ("NumMultiply", Key::KpMultiply),
+    ("NumDivide", Key::KpDivide),
+];
+
+const LETTER_KEYS: [Key; 26] = [
+    Key::KeyA, Key::KeyB, Key::KeyC, Key::KeyD, Key::KeyE, Key::KeyF, Key::KeyG,
+    Key::KeyH, Key::KeyI, Key::KeyJ, Key::KeyK, Key::KeyL, Key::KeyM, Key::KeyN,
+    Key::KeyO, Key::KeyP, Key::KeyQ, Key::KeyR, Key::KeyS, Key::KeyT, Key::KeyU,
+    Key::KeyV, Key::KeyW, Key::KeyX, Key::KeyY, Key::KeyZ,
+];
+
+const DIGIT_KEYS: [Key; 10] = [
+    Key::Num0, Key::Num1, Key::Num2, Key::Num3, Key::Num4,
+    Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+];
+
+fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphabetic() {
+            return Some(LETTER_KEYS[(c.to_ascii_uppercase() as u8 - b'A') as usize]);
+        }
+        if c.is_ascii_digit() {
+            return Some(DIGIT_KEYS[(c as u8 - b'0') as usize]);
+        }
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|&(_, key)| key)
+}
+
+fn key_name(key: Key) -> String {
+    if let Some(index) = LETTER_KEYS.iter().position(|&k| k == key) {
+        return ((b'A' + index as u8) as char).to_string();
+    }
+    if let Some(index) = DIGIT_KEYS.iter().position(|&k| k == key) {
+        return ((b'0' + index as u8) as char).to_string();
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|&&(_, k)| k == key)
+        .map(|(name, _)| name.to_string())
+        .unwrap_or_else(|| format!("{:?}", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format_combos() {
+        let combo = KeyCombo::parse("shift + ctrl+m").unwrap();
+        assert_eq!(combo.key, Key::KeyM);
+        assert!(combo.modifiers.ctrl && combo.modifiers.shift);
+        assert!(!combo.modifiers.alt && !combo.modifiers.meta);
+        assert_eq!(combo.to_string(), "Ctrl+Shift+M");
+
+        assert_eq!(KeyCombo::parse("f9").unwrap().to_string(), "F9");
+        assert_eq!(KeyCombo::parse("Alt+7").unwrap().key, Key::Num7);
+        assert_eq!(KeyCombo::parse("num7").unwrap().key, Key::Kp7);
+
+        assert!(KeyCombo::parse("Ctrl+Shift").is_err());
+        assert!(KeyCombo::parse("Ctrl+A+B").is_err());
+        assert!(KeyCombo::parse("Hyper+A").is_err());
+    }
+
+    #[test]
+    fn combos_need_exact_modifiers() {
+        let combo = KeyCombo::parse("Ctrl+M").unwrap();
+        let mut modifiers = Modifiers::default();
+        assert!(!combo.matches(Key::KeyM, &modifiers));
+
+        assert!(modifiers.update(Key::ControlRight, true));
+        assert!(combo.matches(Key::KeyM, &modifiers));
+
+        modifiers.update(Key::ShiftLeft, true);
+        assert!(!combo.matches(Key::KeyM, &modifiers));
+        assert!(!modifiers.update(Key::KeyM, true));
+    }
+}
@@ -0,0 +1,150 @@
+// Keyboard hook (rdev) and the worker that executes triggered actions.
+// Actions run on their own thread because OS keyboard hooks get dropped if their
+// callback blocks for too long, and starting/stopping engines can take a while.
+
+use rdev::{EventType, Key};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use tauri::Emitter;
+
+use super::bindings::{HotkeyAction, Modifiers};
+use super::HotkeyManager;
+use crate::audio::audio_controls::AudioControls;
+use crate::soundboard::SoundboardManager;
+
+/// Sent to the frontend so it can follow state changed by hotkeys
+#[derive(Clone, Debug, Serialize)]
+pub struct HotkeyEvent {
+    pub action: HotkeyAction,
+    pub pressed: bool,
+}
+
+pub fn start_listener(app_handle: Option<tauri::AppHandle>) {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || run_actions(receiver, app_handle));
+
+    thread::spawn(move || {
+        let mut hook = KeyboardHook::new(sender);
+        if let Err(e) = rdev::listen(move |event| hook.handle(event.event_type)) {
+            eprintln!("Global hotkeys unavailable: {:?}", e);
+        }
+    });
+}
+
+struct KeyboardHook {
+    sender: Sender<HotkeyEvent>,
+    modifiers: Modifiers,
+    held_keys: HashSet<Key>,            // filters out OS key repeat
+    held_actions: Vec<(Key, HotkeyAction)>, // hold actions waiting for their key release
+}
+
+impl KeyboardHook {
+    fn new(sender: Sender<HotkeyEvent>) -> Self {
+        KeyboardHook {
+            sender,
+            modifiers: Modifiers::default(),
+            held_keys: HashSet::new(),
+            held_actions: Vec::new(),
+        }
+    }
+
+    fn handle(&mut self, event: EventType) {
+        match event {
+            EventType::KeyPress(key) => {
+                if self.modifiers.update(key, true) || !self.held_keys.insert(key) {
+                    return;
+                }
+                let action = match HotkeyManager::get_instance().lock() {
+                    Ok(manager) => manager.find_action(key, &self.modifiers),
+                    Err(_) => None,
+                };
+                if let Some(action) = action {
+                    if action.is_hold() {
+                        self.held_actions.push((key, action.clone()));
+                    }
+                    let _ = self.sender.send(HotkeyEvent { action, pressed: true });
+                }
+            }
+            EventType::KeyRelease(key) => {
+                self.modifiers.update(key, false);
+                self.held_keys.remove(&key);
+                // Released by the main key only, so letting go of a modifier first still ends it
+                if let Some(index) = self.held_actions.iter().position(|(k, _)| *k == key) {
+                    let (_, action) = self.held_actions.remove(index);
+                    let _ = self.sender.send(HotkeyEvent { action, pressed: false });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn run_actions(receiver: Receiver<HotkeyEvent>, app_handle: Option<tauri::AppHandle>) {
+    sync_output_gate();
+
+    for event in receiver {
+        if let Err(e) = execute(&event) {
+            eprintln!("Hotkey action {:?} failed: {}", event.action, e);
+            continue;
+        }
+        if let Some(ref app_handle) = app_handle {
+            let _ = app_handle.emit("hotkey-triggered", &event);
+        }
+    }
+}
+
+fn execute(event: &HotkeyEvent) -> anyhow::Result<()> {
+    let mut controls = AudioControls::get_instance()
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to acquire audio controls lock: {}", e))?;
+
+    match (&event.action, event.pressed) {
+        (HotkeyAction::PushToTalk, pressed) => controls.set_output_gate(pressed),
+        (HotkeyAction::PushToMute, pressed) => controls.set_output_gate(!pressed),
+        (_, false) => {}
+        (HotkeyAction::ToggleModulation, true) => {
+            if controls.is_modulation_active() {
+                controls.disable_modulation()?;
+            } else {
+                controls.enable_modulation()?;
+            }
+        }
+        (HotkeyAction::NextPreset, true) => controls.next_preset()?,
+        (HotkeyAction::PreviousPreset, true) => controls.previous_preset()?,
+        (HotkeyAction::LoadPreset(name), true) => controls.load_preset(name)?,
+        (HotkeyAction::ToggleRecording, true) => {
+            if controls.is_recording() {
+                controls.stop_recording()?;
+            } else {
+                controls.start_recording()?;
+            }
+        }
+        (HotkeyAction::SaveReplay, true) => {
+            let path = controls.save_replay()?;
+            println!("Replay saved to {}", path);
+        }
+        (HotkeyAction::PlayClip(name), true) => lock_soundboard()?.trigger(name)?,
+        (HotkeyAction::StopClips, true) => lock_soundboard()?.stop_all()?,
+    }
+    Ok(())
+}
+
+fn lock_soundboard() -> anyhow::Result<std::sync::MutexGuard<'static, SoundboardManager>> {
+    SoundboardManager::get_instance()
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to acquire soundboard lock: {}", e))
+}
+
+// Puts the output gate into its idle state for the current bindings
+pub fn sync_output_gate() {
+    let idle_open = match HotkeyManager::get_instance().lock() {
+        Ok(manager) => manager.idle_gate_open(),
+        Err(_) => return,
+    };
+    if let Ok(controls) = AudioControls::get_instance().lock() {
+        controls.set_output_gate(idle_open);
+    }
+}
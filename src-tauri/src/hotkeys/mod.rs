@@ -0,0 +1,98 @@
+// Global hotkeys - work while another application (e.g. a game) has focus
+
+pub mod bindings;
+pub mod listener;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use crate::persistence;
+use bindings::{HotkeyAction, HotkeyBinding, KeyCombo, Modifiers};
+
+const HOTKEYS_FILE: &str = "hotkeys.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HotkeyConfig {
+    bindings: Vec<HotkeyBinding>,
+}
+
+pub struct HotkeyManager {
+    bindings: Vec<(KeyCombo, HotkeyBinding)>,
+}
+
+static HOTKEY_MANAGER: OnceCell<Mutex<HotkeyManager>> = OnceCell::new();
+
+impl HotkeyManager {
+    fn new() -> Self {
+        let config: HotkeyConfig = persistence::load(HOTKEYS_FILE);
+        let bindings = config
+            .bindings
+            .into_iter()
+            .filter_map(|binding| match KeyCombo::parse(&binding.keys) {
+                Ok(combo) => Some((combo, binding)),
+                Err(e) => {
+                    eprintln!("Skipping hotkey '{}': {}", binding.keys, e);
+                    None
+                }
+            })
+            .collect();
+
+        HotkeyManager { bindings }
+    }
+
+    pub fn get_instance() -> &'static Mutex<HotkeyManager> {
+        HOTKEY_MANAGER.get_or_init(|| Mutex::new(HotkeyManager::new()))
+    }
+
+    pub fn get_bindings(&self) -> Vec<HotkeyBinding> {
+        self.bindings.iter().map(|(_, binding)| binding.clone()).collect()
+    }
+
+    // Binds `keys` to `action`, replacing whatever that combination did before.
+    // Only changes the bindings in memory - `save` them once the lock is released
+    pub fn set_binding(&mut self, keys: &str, action: HotkeyAction) -> anyhow::Result<()> {
+        let combo = KeyCombo::parse(keys)?;
+        let binding = HotkeyBinding {
+            keys: combo.to_string(),
+            action,
+        };
+        match self.bindings.iter_mut().find(|(c, _)| *c == combo) {
+            Some(existing) => existing.1 = binding,
+            None => self.bindings.push((combo, binding)),
+        }
+        Ok(())
+    }
+
+    pub fn remove_binding(&mut self, keys: &str) -> anyhow::Result<()> {
+        let combo = KeyCombo::parse(keys)?;
+        let index = self
+            .bindings
+            .iter()
+            .position(|(c, _)| *c == combo)
+            .ok_or_else(|| anyhow::anyhow!("No hotkey bound to '{}'", combo))?;
+        self.bindings.remove(index);
+        Ok(())
+    }
+
+    pub fn find_action(&self, key: rdev::Key, modifiers: &Modifiers) -> Option<HotkeyAction> {
+        self.bindings
+            .iter()
+            .find(|(combo, _)| combo.matches(key, modifiers))
+            .map(|(_, binding)| binding.action.clone())
+    }
+
+    // With a push-to-talk key bound the output stays silent until the key is held
+    pub fn idle_gate_open(&self) -> bool {
+        !self
+            .bindings
+            .iter()
+            .any(|(_, binding)| binding.action == HotkeyAction::PushToTalk)
+    }
+
+    // Takes the bindings instead of the manager - the listener locks it on every key press,
+    // so the file is written without holding the lock
+    pub fn save(bindings: Vec<HotkeyBinding>) -> anyhow::Result<()> {
+        persistence::save(HOTKEYS_FILE, &HotkeyConfig { bindings })
+    }
+}
@@ -1,3 +1,9 @@
 pub mod commands;
 pub mod audio;
+pub mod control_server;
 pub mod dsp; 
+pub mod hotkeys;
+pub mod midi;
+pub mod music_bed;
+pub mod persistence;
+pub mod soundboard;
@@ -0,0 +1,154 @@
+// MIDI input ports (midir) and the worker that applies mapped CC values.
+// The midir callback only forwards control changes; locking the audio controls
+// and gliding smoothed values happens on the worker thread.
+
+use midir::{MidiInput, MidiInputConnection};
+use once_cell::sync::OnceCell;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+use super::mapping::{ControlChange, ParameterSmoother};
+use super::notes::HELD_NOTES;
+use super::{CcOutcome, MidiManager};
+use crate::audio::audio_controls::AudioControls;
+
+const CLIENT_NAME: &str = "PitchSlap";
+/// Update interval while a smoothed parameter is gliding.
+const SMOOTHING_TICK: Duration = Duration::from_millis(10);
+
+struct MidiInputs {
+    sender: Sender<ControlChange>,
+    connections: Vec<(String, MidiInputConnection<()>)>,
+}
+
+static MIDI_INPUTS: OnceCell<Mutex<MidiInputs>> = OnceCell::new();
+
+pub fn start_listener(app_handle: Option<tauri::AppHandle>) -> anyhow::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    MIDI_INPUTS
+        .set(Mutex::new(MidiInputs {
+            sender,
+            connections: Vec::new(),
+        }))
+        .map_err(|_| anyhow::anyhow!("MIDI listener is already running"))?;
+
+    thread::spawn(move || run_mappings(receiver, app_handle));
+    connect_inputs()?;
+    Ok(())
+}
+
+// (Re)connects to every MIDI input currently present, returns their names
+pub fn connect_inputs() -> anyhow::Result<Vec<String>> {
+    let mut inputs = MIDI_INPUTS
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("MIDI listener is not running"))?
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to acquire MIDI inputs lock: {}", e))?;
+    inputs.connections.clear();
+    // Note offs of closed ports never arrive
+    HELD_NOTES.release_all();
+
+    let ports = MidiInput::new(CLIENT_NAME)
+        .map_err(|e| anyhow::anyhow!("MIDI unavailable: {}", e))?
+        .ports();
+    for port in ports {
+        // `connect` consumes the client, so every port gets its own
+        let midi_in = MidiInput::new(CLIENT_NAME).map_err(|e| anyhow::anyhow!("MIDI unavailable: {}", e))?;
+        let name = midi_in.port_name(&port).unwrap_or_else(|_| "Unknown".to_string());
+        let sender = inputs.sender.clone();
+        match midi_in.connect(
+            &port,
+            "pitchslap-in",
+            move |_, message, _| {
+                if let Some(cc) = ControlChange::parse(message) {
+                    let _ = sender.send(cc);
+                } else {
+                    HELD_NOTES.handle_message(message);
+                }
+            },
+            (),
+        ) {
+            Ok(connection) => inputs.connections.push((name, connection)),
+            Err(e) => eprintln!("Failed to open MIDI input '{}': {}", name, e),
+        }
+    }
+
+    Ok(inputs.connections.iter().map(|(name, _)| name.clone()).collect())
+}
+
+pub fn get_connected_inputs() -> Vec<String> {
+    MIDI_INPUTS
+        .get()
+        .and_then(|inputs| inputs.lock().ok())
+        .map(|inputs| inputs.connections.iter().map(|(name, _)| name.clone()).collect())
+        .unwrap_or_default()
+}
+
+fn run_mappings(receiver: Receiver<ControlChange>, app_handle: Option<tauri::AppHandle>) {
+    let mut smoothers: HashMap<(String, String), ParameterSmoother> = HashMap::new();
+    let mut last_tick = Instant::now();
+
+    loop {
+        // Sleep until the next message unless something is still gliding
+        let received = if smoothers.values().all(ParameterSmoother::is_settled) {
+            receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            receiver.recv_timeout(SMOOTHING_TICK)
+        };
+
+        match received {
+            Ok(cc) => handle_cc(&cc, &mut smoothers, &app_handle),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        let elapsed_ms = last_tick.elapsed().as_secs_f32() * 1000.0;
+        last_tick = Instant::now();
+        for ((effect_name, parameter_name), smoother) in smoothers.iter_mut() {
+            if let Some(value) = smoother.tick(elapsed_ms) {
+                apply(effect_name, parameter_name, value);
+            }
+        }
+    }
+}
+
+fn handle_cc(
+    cc: &ControlChange,
+    smoothers: &mut HashMap<(String, String), ParameterSmoother>,
+    app_handle: &Option<tauri::AppHandle>,
+) {
+    let outcome = match MidiManager::get_instance().lock() {
+        Ok(mut manager) => manager.handle_cc(cc),
+        Err(_) => return,
+    };
+
+    match outcome {
+        Ok(CcOutcome::Learned(mapping)) => {
+            if let Some(app_handle) = app_handle {
+                let _ = app_handle.emit("midi-learned", &mapping);
+            }
+        }
+        Ok(CcOutcome::Mapped(mappings)) => {
+            for mapping in mappings {
+                let smoother = smoothers
+                    .entry((mapping.effect_name, mapping.parameter_name))
+                    .or_insert_with(|| ParameterSmoother::new(mapping.smoothing_ms));
+                smoother.set_time(mapping.smoothing_ms);
+                smoother.set_target(cc.normalized());
+            }
+        }
+        Ok(CcOutcome::Ignored) => {}
+        Err(e) => eprintln!("MIDI learn failed: {}", e),
+    }
+}
+
+fn apply(effect_name: &str, parameter_name: &str, normalized: f32) {
+    if let Ok(mut controls) = AudioControls::get_instance().lock() {
+        // Mapped effects are often not in the chain right now - that is not an error worth reporting
+        let _ = controls.set_effect_parameter_normalized(effect_name, parameter_name, normalized);
+    }
+}
@@ -0,0 +1,143 @@
+// MIDI control change messages, CC -> parameter mappings and value smoothing
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlChange {
+    pub channel: u8, // 0-15
+    pub controller: u8,
+    pub value: u8, // 0-127
+}
+
+impl ControlChange {
+    // Anything other than a control change (notes, clock, sysex, ...) is ignored
+    pub fn parse(message: &[u8]) -> Option<Self> {
+        match *message {
+            [status, controller, value] if status & 0xF0 == 0xB0 => Some(ControlChange {
+                channel: status & 0x0F,
+                controller: controller & 0x7F,
+                value: value & 0x7F,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn normalized(&self) -> f32 {
+        self.value as f32 / 127.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MidiMapping {
+    pub channel: u8,
+    pub controller: u8,
+    pub effect_name: String,
+    pub parameter_name: String,
+    #[serde(default)]
+    pub smoothing_ms: f32, // 0 = jump straight to the new value
+}
+
+impl MidiMapping {
+    pub fn matches(&self, cc: &ControlChange) -> bool {
+        self.channel == cc.channel && self.controller == cc.controller
+    }
+
+    pub fn targets(&self, effect_name: &str, parameter_name: &str) -> bool {
+        self.effect_name == effect_name && self.parameter_name == parameter_name
+    }
+}
+
+// One-pole glide towards the last received value - hides the 128 step resolution of CC
+#[derive(Debug, Clone)]
+pub struct ParameterSmoother {
+    current: Option<f32>,
+    target: f32,
+    time_ms: f32,
+    changed: bool, // current moved since the last tick reported it
+}
+
+impl ParameterSmoother {
+    pub fn new(time_ms: f32) -> Self {
+        ParameterSmoother {
+            current: None,
+            target: 0.0,
+            time_ms: time_ms.max(0.0),
+            changed: false,
+        }
+    }
+
+    pub fn set_time(&mut self, time_ms: f32) {
+        self.time_ms = time_ms.max(0.0);
+    }
+
+    pub fn set_target(&mut self, target: f32) {
+        self.target = target.clamp(0.0, 1.0);
+        // Nothing to glide from on the first value
+        if self.current.is_none() || self.time_ms == 0.0 {
+            self.current = Some(self.target);
+            self.changed = true;
+        }
+    }
+
+    // Advances the glide by `elapsed_ms`, returns the value to apply if it moved
+    pub fn tick(&mut self, elapsed_ms: f32) -> Option<f32> {
+        let current = self.current?;
+        if current != self.target {
+            let coefficient = 1.0 - (-elapsed_ms / self.time_ms).exp();
+            let mut next = current + (self.target - current) * coefficient;
+            // Finish the tail instead of creeping forever
+            if (self.target - next).abs() < 1e-3 {
+                next = self.target;
+            }
+            self.current = Some(next);
+            self.changed = true;
+        }
+        if !self.changed {
+            return None;
+        }
+        self.changed = false;
+        self.current
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.current == Some(self.target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_only_control_changes() {
+        let cc = ControlChange::parse(&[0xB3, 74, 127]).unwrap();
+        assert_eq!(cc, ControlChange { channel: 3, controller: 74, value: 127 });
+        assert_eq!(cc.normalized(), 1.0);
+
+        assert!(ControlChange::parse(&[0x90, 60, 100]).is_none()); // note on
+        assert!(ControlChange::parse(&[0xF8]).is_none()); // clock
+    }
+
+    #[test]
+    fn smoother_glides_to_target() {
+        let mut smoother = ParameterSmoother::new(50.0);
+        smoother.set_target(0.0);
+        assert!(smoother.is_settled());
+        assert_eq!(smoother.tick(10.0), Some(0.0));
+        assert_eq!(smoother.tick(10.0), None);
+
+        smoother.set_target(1.0);
+        let first = smoother.tick(10.0).unwrap();
+        assert!(first > 0.0 && first < 1.0);
+        for _ in 0..100 {
+            smoother.tick(10.0);
+        }
+        assert!(smoother.is_settled());
+
+        let mut instant = ParameterSmoother::new(0.0);
+        instant.set_target(0.2);
+        instant.set_target(0.7);
+        assert!(instant.is_settled());
+        assert_eq!(instant.tick(10.0), Some(0.7));
+    }
+}
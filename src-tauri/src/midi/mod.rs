@@ -0,0 +1,168 @@
+// MIDI learn - binds hardware knobs and faders (CC messages) to effect parameters.
+// Held notes are tracked too, for the vocoder's MIDI carrier.
+
+pub mod input;
+pub mod mapping;
+pub mod notes;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use crate::persistence;
+use mapping::{ControlChange, MidiMapping};
+
+const MIDI_MAPPINGS_FILE: &str = "midi_mappings.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MidiConfig {
+    mappings: Vec<MidiMapping>,
+}
+
+/// Parameter waiting for the next CC to be bound to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LearnTarget {
+    pub effect_name: String,
+    pub parameter_name: String,
+}
+
+pub enum CcOutcome {
+    Learned(MidiMapping),
+    Mapped(Vec<MidiMapping>),
+    Ignored,
+}
+
+pub struct MidiManager {
+    mappings: Vec<MidiMapping>,
+    learning: Option<LearnTarget>,
+    persist: bool, // false keeps mappings in memory only - tests never touch the config dir
+}
+
+static MIDI_MANAGER: OnceCell<Mutex<MidiManager>> = OnceCell::new();
+
+impl MidiManager {
+    fn new() -> Self {
+        let config: MidiConfig = persistence::load(MIDI_MAPPINGS_FILE);
+        MidiManager {
+            mappings: config.mappings,
+            learning: None,
+            persist: true,
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<MidiManager> {
+        MIDI_MANAGER.get_or_init(|| Mutex::new(MidiManager::new()))
+    }
+
+    pub fn get_mappings(&self) -> Vec<MidiMapping> {
+        self.mappings.clone()
+    }
+
+    pub fn start_learn(&mut self, effect_name: &str, parameter_name: &str) {
+        self.learning = Some(LearnTarget {
+            effect_name: effect_name.to_string(),
+            parameter_name: parameter_name.to_string(),
+        });
+    }
+
+    pub fn cancel_learn(&mut self) {
+        self.learning = None;
+    }
+
+    pub fn get_learn_target(&self) -> Option<LearnTarget> {
+        self.learning.clone()
+    }
+
+    // While learning, the CC gets bound (replacing its old binding) instead of applied
+    pub fn handle_cc(&mut self, cc: &ControlChange) -> anyhow::Result<CcOutcome> {
+        if let Some(target) = self.learning.take() {
+            let mapping = MidiMapping {
+                channel: cc.channel,
+                controller: cc.controller,
+                effect_name: target.effect_name,
+                parameter_name: target.parameter_name,
+                smoothing_ms: 0.0,
+            };
+            // One knob per parameter, one parameter per knob
+            self.mappings
+                .retain(|m| !(m.matches(cc) || m.targets(&mapping.effect_name, &mapping.parameter_name)));
+            self.mappings.push(mapping.clone());
+            self.save()?;
+            return Ok(CcOutcome::Learned(mapping));
+        }
+
+        let mapped: Vec<MidiMapping> = self.mappings.iter().filter(|m| m.matches(cc)).cloned().collect();
+        if mapped.is_empty() {
+            Ok(CcOutcome::Ignored)
+        } else {
+            Ok(CcOutcome::Mapped(mapped))
+        }
+    }
+
+    pub fn remove_mapping(&mut self, channel: u8, controller: u8) -> anyhow::Result<()> {
+        let index = self
+            .mappings
+            .iter()
+            .position(|m| m.channel == channel && m.controller == controller)
+            .ok_or_else(|| anyhow::anyhow!("No mapping for CC {} on channel {}", controller, channel + 1))?;
+        self.mappings.remove(index);
+        self.save()
+    }
+
+    pub fn set_smoothing(&mut self, channel: u8, controller: u8, smoothing_ms: f32) -> anyhow::Result<()> {
+        let mapping = self
+            .mappings
+            .iter_mut()
+            .find(|m| m.channel == channel && m.controller == controller)
+            .ok_or_else(|| anyhow::anyhow!("No mapping for CC {} on channel {}", controller, channel + 1))?;
+        mapping.smoothing_ms = smoothing_ms.clamp(0.0, 2000.0);
+        self.save()
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        if !self.persist {
+            return Ok(());
+        }
+        persistence::save(
+            MIDI_MAPPINGS_FILE,
+            &MidiConfig {
+                mappings: self.mappings.clone(),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learn_binds_next_cc_and_replaces_old_bindings() {
+        let mut manager = MidiManager {
+            mappings: Vec::new(),
+            learning: None,
+            persist: false,
+        };
+        let knob = ControlChange { channel: 0, controller: 21, value: 64 };
+        assert!(matches!(manager.handle_cc(&knob).unwrap(), CcOutcome::Ignored));
+
+        manager.start_learn("reverb", "mix");
+        assert!(matches!(manager.handle_cc(&knob).unwrap(), CcOutcome::Learned(_)));
+        assert!(manager.get_learn_target().is_none());
+        match manager.handle_cc(&knob).unwrap() {
+            CcOutcome::Mapped(mappings) => assert_eq!(mappings[0].parameter_name, "mix"),
+            _ => panic!("expected the learned mapping to apply"),
+        }
+
+        // Same parameter on another knob moves the binding
+        manager.start_learn("reverb", "mix");
+        let other = ControlChange { controller: 22, ..knob };
+        manager.handle_cc(&other).unwrap();
+        assert_eq!(manager.get_mappings().len(), 1);
+        assert!(matches!(manager.handle_cc(&knob).unwrap(), CcOutcome::Ignored));
+
+        manager.remove_mapping(0, 22).unwrap();
+        assert!(manager.get_mappings().is_empty());
+        assert!(manager.remove_mapping(0, 22).is_err());
+    }
+}
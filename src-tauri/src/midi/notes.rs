@@ -0,0 +1,100 @@
+// Notes held on the MIDI inputs - the vocoder's MIDI carrier plays them as a chord.
+// Kept in atomics so the audio callback can read them without taking a lock.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub struct HeldNotes {
+    bits: [AtomicU64; 2], // one bit per MIDI note
+}
+
+pub static HELD_NOTES: HeldNotes = HeldNotes::new();
+
+impl HeldNotes {
+    pub const fn new() -> Self {
+        HeldNotes {
+            bits: [AtomicU64::new(0), AtomicU64::new(0)],
+        }
+    }
+
+    // Note on / note off on any channel, returns false for other messages
+    pub fn handle_message(&self, message: &[u8]) -> bool {
+        match *message {
+            [status, note, velocity] if status & 0xF0 == 0x90 && velocity > 0 => {
+                self.set(note & 0x7F, true);
+                true
+            }
+            // Note on with zero velocity is the common running status note off
+            [status, note, _] if status & 0xF0 == 0x80 || status & 0xF0 == 0x90 => {
+                self.set(note & 0x7F, false);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn set(&self, note: u8, held: bool) {
+        let bit = 1u64 << (note % 64);
+        let word = &self.bits[(note / 64) as usize];
+        if held {
+            word.fetch_or(bit, Ordering::Relaxed);
+        } else {
+            word.fetch_and(!bit, Ordering::Relaxed);
+        }
+    }
+
+    fn is_held(&self, note: u8) -> bool {
+        self.bits[(note / 64) as usize].load(Ordering::Relaxed) & (1u64 << (note % 64)) != 0
+    }
+
+    // Held notes, lowest first
+    pub fn notes(&self) -> Vec<u8> {
+        (0..128u8).filter(|&note| self.is_held(note)).collect()
+    }
+
+    // The lowest held notes written into `out`, returns how many - allocation free for the audio callback
+    pub fn lowest(&self, out: &mut [u8]) -> usize {
+        let mut count = 0;
+        for note in (0..128u8).filter(|&note| self.is_held(note)).take(out.len()) {
+            out[count] = note;
+            count += 1;
+        }
+        count
+    }
+
+    pub fn release_all(&self) {
+        for word in &self.bits {
+            word.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Default for HeldNotes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_note_on_and_off() {
+        let notes = HeldNotes::new();
+        assert!(notes.handle_message(&[0x90, 64, 100]));
+        assert!(notes.handle_message(&[0x91, 60, 80]));
+        assert!(notes.handle_message(&[0x90, 100, 1]));
+        assert_eq!(notes.notes(), vec![60, 64, 100]);
+        let mut lowest = [0; 2];
+        assert_eq!(notes.lowest(&mut lowest), 2);
+        assert_eq!(lowest, [60, 64]);
+
+        notes.handle_message(&[0x80, 64, 0]);
+        notes.handle_message(&[0x90, 100, 0]);
+        assert_eq!(notes.notes(), vec![60]);
+
+        assert!(!notes.handle_message(&[0xB0, 21, 64]));
+        notes.release_all();
+        assert!(notes.notes().is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+// Sidechain ducking - the processed voice pushes the music bed down while it is loud
+
+use serde::{Deserialize, Serialize};
+
+use crate::dsp::modules::utils::EnvelopeFollower;
+
+// Level above the threshold over which the full depth is reached - avoids a hard on/off
+const KNEE_DB: f32 = 6.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuckingSettings {
+    pub threshold_db: f32, // voice level that starts ducking
+    pub depth_db: f32,     // attenuation while speaking, 0 = ducking off
+    pub attack_ms: f32,
+    pub release_ms: f32,
+}
+
+impl Default for DuckingSettings {
+    fn default() -> Self {
+        Self {
+            threshold_db: -40.0,
+            depth_db: 12.0,
+            attack_ms: 20.0,
+            release_ms: 400.0,
+        }
+    }
+}
+
+impl DuckingSettings {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(-80.0..=0.0).contains(&self.threshold_db) {
+            return Err(anyhow::anyhow!("Ducking threshold must be between -80 and 0 dB"));
+        }
+        if !(0.0..=60.0).contains(&self.depth_db) {
+            return Err(anyhow::anyhow!("Ducking depth must be between 0 and 60 dB"));
+        }
+        if self.attack_ms <= 0.0 || self.release_ms <= 0.0 {
+            return Err(anyhow::anyhow!("Ducking attack and release must be positive"));
+        }
+        Ok(())
+    }
+}
+
+pub struct Ducker {
+    settings: DuckingSettings,
+    envelope: EnvelopeFollower,
+}
+
+impl Ducker {
+    pub fn new(settings: DuckingSettings, sample_rate: f32) -> Self {
+        Self {
+            envelope: EnvelopeFollower::new(sample_rate, settings.attack_ms, settings.release_ms),
+            settings,
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: DuckingSettings) {
+        self.envelope.set_times(settings.attack_ms, settings.release_ms);
+        self.settings = settings;
+    }
+
+    // Follows one voice frame (loudest channel) and returns the linear gain for the bed
+    pub fn process(&mut self, voice_frame: &[f32]) -> f32 {
+        let peak = voice_frame.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        let level = self.envelope.process(peak);
+        if level <= 0.0 || self.settings.depth_db <= 0.0 {
+            return 1.0;
+        }
+
+        let over_db = 20.0 * level.log10() - self.settings.threshold_db;
+        let amount = (over_db / KNEE_DB).clamp(0.0, 1.0);
+        10.0_f32.powf(-self.settings.depth_db * amount / 20.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.envelope.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ducks_while_voice_is_loud_and_recovers() {
+        let mut ducker = Ducker::new(DuckingSettings::default(), 1_000.0);
+        assert_eq!(ducker.process(&[0.001]), 1.0);
+
+        let mut gain = 1.0;
+        for _ in 0..200 {
+            gain = ducker.process(&[0.5, -0.5]);
+        }
+        // 12 dB down
+        assert!((gain - 0.251).abs() < 0.01, "ducked gain {}", gain);
+
+        for _ in 0..3_000 {
+            gain = ducker.process(&[0.0, 0.0]);
+        }
+        assert!(gain > 0.99, "released gain {}", gain);
+    }
+}
@@ -0,0 +1,73 @@
+// Real-time side of the music bed - mixes the source under the voice, ducked by it
+
+use super::ducker::{Ducker, DuckingSettings};
+use super::source::BedSource;
+
+pub struct MusicBedMixer {
+    source: Option<Box<dyn BedSource>>,
+    ducker: Ducker,
+    volume: f32,
+    frame: Vec<f32>, // scratch for one source frame
+    sample_rate: u32,
+    channels: u16,
+}
+
+impl MusicBedMixer {
+    pub fn new(sample_rate: u32, channels: u16, volume: f32, ducking: DuckingSettings) -> Self {
+        Self {
+            source: None,
+            ducker: Ducker::new(ducking, sample_rate as f32),
+            volume,
+            frame: vec![0.0; channels.max(1) as usize],
+            sample_rate,
+            channels,
+        }
+    }
+
+    pub fn is_format(&self, sample_rate: u32, channels: u16) -> bool {
+        self.sample_rate == sample_rate && self.channels == channels
+    }
+
+    pub fn format(&self) -> (u32, u16) {
+        (self.sample_rate, self.channels)
+    }
+
+    pub fn has_source(&self) -> bool {
+        self.source.is_some()
+    }
+
+    // Returns the previous source so it is dropped outside the audio lock
+    pub fn set_source(&mut self, source: Option<Box<dyn BedSource>>) -> Option<Box<dyn BedSource>> {
+        self.ducker.reset();
+        std::mem::replace(&mut self.source, source)
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+
+    pub fn set_ducking(&mut self, ducking: DuckingSettings) {
+        self.ducker.set_settings(ducking);
+    }
+
+    pub fn skip(&mut self) {
+        if let Some(ref mut source) = self.source {
+            source.skip();
+        }
+    }
+
+    // `data` holds the processed voice (interleaved) - it is the sidechain and gets the bed added
+    pub fn mix_into(&mut self, data: &mut [f32]) {
+        let source = match self.source {
+            Some(ref mut source) => source,
+            None => return,
+        };
+        for voice_frame in data.chunks_exact_mut(self.frame.len()) {
+            let gain = self.ducker.process(voice_frame) * self.volume;
+            source.next_frame(&mut self.frame);
+            for (sample, bed) in voice_frame.iter_mut().zip(&self.frame) {
+                *sample += bed * gain;
+            }
+        }
+    }
+}
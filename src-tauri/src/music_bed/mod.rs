@@ -0,0 +1,136 @@
+// Music bed - a playlist or second input mixed into the throughput output,
+// ducked automatically while the processed voice is speaking
+
+pub mod ducker;
+pub mod mixer;
+pub mod source;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::persistence;
+use ducker::DuckingSettings;
+use mixer::MusicBedMixer;
+use source::{create_source, MusicBedSource};
+
+const MUSIC_BED_FILE: &str = "music_bed.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MusicBedSettings {
+    pub source: MusicBedSource,
+    pub volume: f32, // linear, 0.0 to 1.0
+    pub ducking: DuckingSettings,
+}
+
+impl Default for MusicBedSettings {
+    fn default() -> Self {
+        Self {
+            source: MusicBedSource::Off,
+            volume: 0.3,
+            ducking: DuckingSettings::default(),
+        }
+    }
+}
+
+impl MusicBedSettings {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err(anyhow::anyhow!("Music bed volume must be between 0 and 1"));
+        }
+        self.ducking.validate()
+    }
+}
+
+pub struct MusicBedManager {
+    settings: MusicBedSettings,
+    mixer: Arc<Mutex<MusicBedMixer>>, // shared with the throughput input callback
+    attached: bool,                   // throughput engine is running
+}
+
+static MUSIC_BED_MANAGER: OnceCell<Mutex<MusicBedManager>> = OnceCell::new();
+
+impl MusicBedManager {
+    fn new() -> Self {
+        let settings: MusicBedSettings = persistence::load(MUSIC_BED_FILE);
+        MusicBedManager {
+            mixer: Arc::new(Mutex::new(MusicBedMixer::new(
+                48_000,
+                2,
+                settings.volume,
+                settings.ducking.clone(),
+            ))),
+            settings,
+            attached: false,
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<MusicBedManager> {
+        MUSIC_BED_MANAGER.get_or_init(|| Mutex::new(MusicBedManager::new()))
+    }
+
+    pub fn get_settings(&self) -> MusicBedSettings {
+        self.settings.clone()
+    }
+
+    // Volume and ducking apply live, a new source is opened right away while throughput runs
+    pub fn set_settings(&mut self, settings: MusicBedSettings) -> anyhow::Result<()> {
+        settings.validate()?;
+        if settings.source != self.settings.source && self.attached {
+            self.open_source(&settings.source)?;
+        }
+        {
+            let mut mixer = self.lock_mixer()?;
+            mixer.set_volume(settings.volume);
+            mixer.set_ducking(settings.ducking.clone());
+        }
+        self.settings = settings;
+        persistence::save(MUSIC_BED_FILE, &self.settings)
+    }
+
+    pub fn skip_track(&self) -> anyhow::Result<()> {
+        self.lock_mixer()?.skip();
+        Ok(())
+    }
+
+    // Called when the throughput engine starts - restarts keep the bed playing
+    pub fn attach(&mut self, sample_rate: u32, channels: u16) -> Arc<Mutex<MusicBedMixer>> {
+        let reopen = match self.mixer.lock() {
+            Ok(mut mixer) if !mixer.is_format(sample_rate, channels) => {
+                *mixer = MusicBedMixer::new(sample_rate, channels, self.settings.volume, self.settings.ducking.clone());
+                true
+            }
+            Ok(mixer) => !mixer.has_source(),
+            Err(_) => false,
+        };
+        self.attached = true;
+        if reopen {
+            let source = self.settings.source.clone();
+            self.open_source(&source)
+                .unwrap_or_else(|e| eprintln!("Failed to open music bed: {}", e));
+        }
+        Arc::clone(&self.mixer)
+    }
+
+    // Called when throughput is stopped by the user, closes files and devices
+    pub fn detach(&mut self) {
+        self.attached = false;
+        let previous = self.mixer.lock().ok().and_then(|mut mixer| mixer.set_source(None));
+        drop(previous);
+    }
+
+    fn open_source(&self, source: &MusicBedSource) -> anyhow::Result<()> {
+        let (sample_rate, channels) = self.lock_mixer()?.format();
+        let source = create_source(source, sample_rate, channels)?;
+        let previous = self.lock_mixer()?.set_source(source);
+        drop(previous);
+        Ok(())
+    }
+
+    fn lock_mixer(&self) -> anyhow::Result<std::sync::MutexGuard<'_, MusicBedMixer>> {
+        self.mixer
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire music bed mixer lock: {}", e))
+    }
+}
@@ -0,0 +1,178 @@
+// Music bed sources - a looping playlist of WAV files or a second input device
+
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+
+use crate::dsp::modules::carrier::{Carrier, InputCarrier};
+use crate::soundboard::clip::load_clip;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MusicBedSource {
+    #[default]
+    Off,
+    Playlist { paths: Vec<String> },
+    Input { device_name: String },
+}
+
+// Pulled one frame at a time from the audio callback, in the throughput input's format
+pub trait BedSource: Send {
+    fn next_frame(&mut self, frame: &mut [f32]);
+
+    // Jumps to the next track, where that means anything
+    fn skip(&mut self) {}
+}
+
+// Opening files and devices is slow, so call this outside the mixer lock
+pub fn create_source(
+    source: &MusicBedSource,
+    sample_rate: u32,
+    channels: u16,
+) -> anyhow::Result<Option<Box<dyn BedSource>>> {
+    let bed_source: Box<dyn BedSource> = match source {
+        MusicBedSource::Off => return Ok(None),
+        MusicBedSource::Playlist { paths } => Box::new(PlaylistSource::open(paths, sample_rate, channels)?),
+        MusicBedSource::Input { device_name } => Box::new(InputSource {
+            input: InputCarrier::open(device_name, sample_rate as usize)?,
+        }),
+    };
+    Ok(Some(bed_source))
+}
+
+// Tracks are decoded whole on a loader thread, one ahead of the one playing.
+// Finished tracks go back to that thread to be freed - never on the audio thread
+pub struct PlaylistSource {
+    current: Vec<f32>,
+    position: usize,
+    tracks: Receiver<Vec<f32>>,
+    finished: SyncSender<Vec<f32>>,
+}
+
+impl PlaylistSource {
+    pub fn open(paths: &[String], sample_rate: u32, channels: u16) -> anyhow::Result<Self> {
+        if paths.is_empty() {
+            return Err(anyhow::anyhow!("Music bed playlist is empty"));
+        }
+
+        // Bounded to one, so the loader blocks until the track it holds is taken;
+        // it exits once the source is dropped and the send fails
+        let (sender, tracks) = mpsc::sync_channel(1);
+        // Room for a swap that lands while the loader is still decoding
+        let (finished, returned) = mpsc::sync_channel::<Vec<f32>>(2);
+        let paths = paths.to_vec();
+        thread::spawn(move || loop {
+            let mut played = false;
+            for path in &paths {
+                while returned.try_recv().is_ok() {}
+                match load_clip(path, path, sample_rate, channels) {
+                    Ok(clip) => {
+                        played = true;
+                        if sender.send(clip.samples).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => eprintln!("Skipping music bed track: {}", e),
+                }
+            }
+            if !played {
+                return;
+            }
+        });
+
+        let current = tracks
+            .recv()
+            .map_err(|_| anyhow::anyhow!("No playable track in the music bed playlist"))?;
+        Ok(Self {
+            current,
+            position: 0,
+            tracks,
+            finished,
+        })
+    }
+}
+
+impl BedSource for PlaylistSource {
+    fn next_frame(&mut self, frame: &mut [f32]) {
+        if self.position + frame.len() > self.current.len() {
+            match self.tracks.try_recv() {
+                Ok(next) => {
+                    let done = std::mem::replace(&mut self.current, next);
+                    // Only dropped here if the loader is gone or somehow two swaps behind
+                    let _ = self.finished.try_send(done);
+                    self.position = 0;
+                }
+                // Next track is still decoding
+                Err(_) => {
+                    frame.fill(0.0);
+                    return;
+                }
+            }
+        }
+        frame.copy_from_slice(&self.current[self.position..self.position + frame.len()]);
+        self.position += frame.len();
+    }
+
+    fn skip(&mut self) {
+        self.position = self.current.len();
+    }
+}
+
+// The input is read as mono (see `InputCarrier`) and spread to every channel
+pub struct InputSource {
+    input: InputCarrier,
+}
+
+impl BedSource for InputSource {
+    fn next_frame(&mut self, frame: &mut [f32]) {
+        frame.fill(self.input.next_sample());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_track(name: &str, value: f32, frames: usize) -> String {
+        let path = std::env::temp_dir().join(name);
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8_000,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..frames {
+            writer.write_sample(value).unwrap();
+        }
+        writer.finalize().unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn playlist_plays_tracks_in_order_and_loops() {
+        let paths = vec![
+            write_track("pitchslap_bed_a.wav", 0.25, 2),
+            "missing.wav".to_string(),
+            write_track("pitchslap_bed_b.wav", 0.5, 1),
+        ];
+        let mut source = PlaylistSource::open(&paths, 8_000, 2).unwrap();
+
+        let mut played = Vec::new();
+        let mut frame = [0.0; 2];
+        while played.len() < 4 {
+            source.next_frame(&mut frame);
+            // Silence only while the loader catches up
+            if frame[0] != 0.0 {
+                assert_eq!(frame[0], frame[1]);
+                played.push(frame[0]);
+            }
+        }
+        assert_eq!(played, vec![0.25, 0.25, 0.5, 0.25]);
+
+        for path in [&paths[0], &paths[2]] {
+            std::fs::remove_file(path).unwrap();
+        }
+        assert!(PlaylistSource::open(&["missing.wav".to_string()], 8_000, 2).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+// Persistence of user settings (presets, hotkeys, ...) as JSON files in the app config directory
+
+use once_cell::sync::OnceCell;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+static CONFIG_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+// Call once from Tauri setup - before that (e.g. in tests) files go to the temp directory
+pub fn init(config_dir: PathBuf) -> anyhow::Result<()> {
+    fs::create_dir_all(&config_dir)?;
+    CONFIG_DIR
+        .set(config_dir)
+        .map_err(|_| anyhow::anyhow!("Config directory is already set"))
+}
+
+pub fn config_dir() -> PathBuf {
+    CONFIG_DIR
+        .get()
+        .cloned()
+        .unwrap_or_else(|| std::env::temp_dir().join("pitchslap"))
+}
+
+// Missing or unreadable files fall back to defaults so a broken config never blocks startup
+pub fn load<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    let path = config_dir().join(file_name);
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Failed to parse {}: {}", path.display(), e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+pub fn save<T: Serialize>(file_name: &str, value: &T) -> anyhow::Result<()> {
+    let dir = config_dir();
+    fs::create_dir_all(&dir)?;
+    // Write to a temporary file first so a crash never leaves half a config behind
+    let path = dir.join(file_name);
+    let temp_path = dir.join(format!("{}.tmp", file_name));
+    fs::write(&temp_path, serde_json::to_string_pretty(value)?)?;
+    fs::rename(&temp_path, &path)?;
+    Ok(())
+}
@@ -0,0 +1,74 @@
+// Clip decoding - WAV files are converted once to the device rate and channel count
+
+use crate::dsp::modules::utils::{read_wav, resample};
+
+// Decoded clip, interleaved in the mixer's format
+#[derive(Debug)]
+pub struct ClipData {
+    pub name: String,
+    pub samples: Vec<f32>,
+}
+
+pub fn load_clip(name: &str, path: &str, sample_rate: u32, channels: u16) -> anyhow::Result<ClipData> {
+    let (interleaved, file_channels, file_rate) = read_wav(path)
+        .map_err(|e| anyhow::anyhow!("Failed to open clip '{}': {}", path, e))?;
+    if interleaved.len() < file_channels {
+        return Err(anyhow::anyhow!("Clip '{}' contains no audio", path));
+    }
+    let channels = channels.max(1) as usize;
+
+    // Matching layouts keep their channels, anything else is mixed to mono and spread out
+    let planes: Vec<Vec<f32>> = if file_channels == channels {
+        (0..channels)
+            .map(|channel| interleaved.iter().skip(channel).step_by(channels).copied().collect())
+            .collect()
+    } else {
+        vec![interleaved
+            .chunks_exact(file_channels)
+            .map(|frame| frame.iter().sum::<f32>() / file_channels as f32)
+            .collect()]
+    };
+    let planes: Vec<Vec<f32>> = planes
+        .iter()
+        .map(|plane| resample(plane, file_rate, sample_rate))
+        .collect();
+
+    let planes = &planes;
+    let samples: Vec<f32> = (0..planes[0].len())
+        .flat_map(|frame| (0..channels).map(move |channel| planes[channel % planes.len()][frame]))
+        .collect();
+
+    Ok(ClipData {
+        name: name.to_string(),
+        samples,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mono_clip_is_resampled_and_spread_to_stereo() {
+        let path = std::env::temp_dir().join("pitchslap_clip_test.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 24_000,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for sample in [0.0f32, 0.5, 1.0] {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let clip = load_clip("test", path.to_str().unwrap(), 48_000, 2).unwrap();
+        assert_eq!(clip.samples.len(), 12);
+        assert!(clip.samples.chunks(2).all(|frame| frame[0] == frame[1]));
+        assert!(clip.samples[0].abs() < 0.05);
+        assert!((clip.samples[4] - 0.5).abs() < 0.05);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
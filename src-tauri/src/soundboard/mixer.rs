@@ -0,0 +1,130 @@
+// Real-time side of the soundboard - playing voices, mixed into the throughput signal
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::clip::ClipData;
+
+// Voices beyond this steal the oldest one, so a held trigger key cannot pile up clips
+const MAX_VOICES: usize = 16;
+
+// What triggering a clip does while it is still playing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerMode {
+    #[default]
+    Overlap, // starts another voice on top
+    Stop,    // stops it instead - trigger again to replay
+    Restart, // starts it over from the beginning
+}
+
+struct Voice {
+    clip: Arc<ClipData>,
+    position: usize,
+    gain: f32,
+}
+
+pub struct SoundboardMixer {
+    voices: Vec<Voice>,
+    sample_rate: u32,
+    channels: u16,
+}
+
+impl SoundboardMixer {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            voices: Vec::with_capacity(MAX_VOICES),
+            sample_rate,
+            channels,
+        }
+    }
+
+    pub fn is_format(&self, sample_rate: u32, channels: u16) -> bool {
+        self.sample_rate == sample_rate && self.channels == channels
+    }
+
+    pub fn format(&self) -> (u32, u16) {
+        (self.sample_rate, self.channels)
+    }
+
+    pub fn trigger(&mut self, clip: Arc<ClipData>, gain: f32, mode: TriggerMode) {
+        let playing = self.is_playing(&clip.name);
+        match mode {
+            TriggerMode::Stop if playing => {
+                self.stop(&clip.name);
+                return;
+            }
+            TriggerMode::Restart => self.stop(&clip.name),
+            _ => {}
+        }
+
+        if self.voices.len() == MAX_VOICES {
+            self.voices.remove(0);
+        }
+        self.voices.push(Voice {
+            clip,
+            position: 0,
+            gain,
+        });
+    }
+
+    pub fn is_playing(&self, name: &str) -> bool {
+        self.voices.iter().any(|voice| voice.clip.name == name)
+    }
+
+    pub fn stop(&mut self, name: &str) {
+        self.voices.retain(|voice| voice.clip.name != name);
+    }
+
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    // Adds every voice to `data` (interleaved, in the mixer's format) and drops finished ones
+    pub fn mix_into(&mut self, data: &mut [f32]) {
+        for voice in &mut self.voices {
+            let remaining = &voice.clip.samples[voice.position..];
+            for (sample, clip_sample) in data.iter_mut().zip(remaining) {
+                *sample += clip_sample * voice.gain;
+            }
+            voice.position += data.len().min(remaining.len());
+        }
+        self.voices.retain(|voice| voice.position < voice.clip.samples.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(name: &str) -> Arc<ClipData> {
+        Arc::new(ClipData {
+            name: name.to_string(),
+            samples: vec![1.0; 4],
+        })
+    }
+
+    #[test]
+    fn trigger_modes() {
+        let mut mixer = SoundboardMixer::new(48_000, 1);
+        let mut block = [0.0; 2];
+
+        mixer.trigger(clip("horn"), 0.5, TriggerMode::Overlap);
+        mixer.trigger(clip("horn"), 0.5, TriggerMode::Overlap);
+        mixer.mix_into(&mut block);
+        assert_eq!(block, [1.0, 1.0]);
+
+        // Restart keeps a single voice, back at the start
+        mixer.trigger(clip("horn"), 0.25, TriggerMode::Restart);
+        let mut block = [0.0; 6];
+        mixer.mix_into(&mut block);
+        assert_eq!(block, [0.25, 0.25, 0.25, 0.25, 0.0, 0.0]);
+        assert!(!mixer.is_playing("horn"));
+
+        // Stop toggles
+        mixer.trigger(clip("horn"), 1.0, TriggerMode::Stop);
+        assert!(mixer.is_playing("horn"));
+        mixer.trigger(clip("horn"), 1.0, TriggerMode::Stop);
+        assert!(!mixer.is_playing("horn"));
+    }
+}
@@ -0,0 +1,206 @@
+// Soundboard - WAV clips played into the throughput output on top of the processed voice
+
+pub mod clip;
+pub mod mixer;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::persistence;
+use clip::{load_clip, ClipData};
+use mixer::{SoundboardMixer, TriggerMode};
+
+const SOUNDBOARD_FILE: &str = "soundboard.json";
+const MAX_CLIP_GAIN: f32 = 2.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoundboardClip {
+    pub name: String,
+    pub path: String,
+    pub gain: f32, // linear, 0.0 to 2.0
+    #[serde(default)]
+    pub mode: TriggerMode,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SoundboardConfig {
+    clips: Vec<SoundboardClip>,
+}
+
+pub struct SoundboardManager {
+    clips: Vec<SoundboardClip>,
+    decoded: HashMap<String, Arc<ClipData>>, // in the mixer's current format
+    mixer: Arc<Mutex<SoundboardMixer>>,      // shared with the throughput input callback
+    // Replaced or removed clips a voice may still play - kept so the last reference
+    // is never dropped (and the samples freed) on the audio thread
+    retired: Vec<Arc<ClipData>>,
+    attached: bool,                          // throughput engine is running
+}
+
+static SOUNDBOARD_MANAGER: OnceCell<Mutex<SoundboardManager>> = OnceCell::new();
+
+impl SoundboardManager {
+    fn new() -> Self {
+        let config: SoundboardConfig = persistence::load(SOUNDBOARD_FILE);
+        SoundboardManager {
+            clips: config.clips,
+            decoded: HashMap::new(),
+            mixer: Arc::new(Mutex::new(SoundboardMixer::new(48_000, 2))),
+            retired: Vec::new(),
+            attached: false,
+        }
+    }
+
+    pub fn get_instance() -> &'static Mutex<SoundboardManager> {
+        SOUNDBOARD_MANAGER.get_or_init(|| Mutex::new(SoundboardManager::new()))
+    }
+
+    pub fn get_clips(&self) -> Vec<SoundboardClip> {
+        self.clips.clone()
+    }
+
+    // Adds the clip or replaces the one with the same name - the file is decoded right away
+    pub fn set_clip(&mut self, clip: SoundboardClip) -> anyhow::Result<()> {
+        if clip.name.trim().is_empty() {
+            return Err(anyhow::anyhow!("Clip name must not be empty"));
+        }
+        if !(0.0..=MAX_CLIP_GAIN).contains(&clip.gain) {
+            return Err(anyhow::anyhow!("Clip gain must be between 0 and {}", MAX_CLIP_GAIN));
+        }
+        let (sample_rate, channels) = self.mixer_format()?;
+        let data = load_clip(&clip.name, &clip.path, sample_rate, channels)?;
+        let replaced = self.decoded.insert(clip.name.clone(), Arc::new(data));
+        self.retire(replaced);
+
+        match self.clips.iter_mut().find(|existing| existing.name == clip.name) {
+            Some(existing) => *existing = clip,
+            None => self.clips.push(clip),
+        }
+        self.save()
+    }
+
+    pub fn remove_clip(&mut self, name: &str) -> anyhow::Result<()> {
+        let index = self
+            .clips
+            .iter()
+            .position(|clip| clip.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Clip '{}' not found", name))?;
+        self.clips.remove(index);
+        let removed = self.decoded.remove(name);
+        self.lock_mixer()?.stop(name);
+        self.retire(removed);
+        self.save()
+    }
+
+    pub fn trigger(&mut self, name: &str) -> anyhow::Result<()> {
+        if !self.attached {
+            return Err(anyhow::anyhow!("Soundboard plays through throughput mode - start it first"));
+        }
+        let clip = self
+            .clips
+            .iter()
+            .find(|clip| clip.name == name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Clip '{}' not found", name))?;
+        self.retire(None);
+
+        let data = match self.decoded.get(name) {
+            Some(data) => Arc::clone(data),
+            None => {
+                let (sample_rate, channels) = self.mixer_format()?;
+                let data = Arc::new(load_clip(&clip.name, &clip.path, sample_rate, channels)?);
+                self.decoded.insert(clip.name.clone(), Arc::clone(&data));
+                data
+            }
+        };
+        self.lock_mixer()?.trigger(data, clip.gain, clip.mode);
+        Ok(())
+    }
+
+    pub fn stop_all(&self) -> anyhow::Result<()> {
+        self.lock_mixer()?.stop_all();
+        Ok(())
+    }
+
+    // Called when the throughput engine starts - clips are decoded again if the format changed
+    pub fn attach(&mut self, sample_rate: u32, channels: u16) -> Arc<Mutex<SoundboardMixer>> {
+        match self.mixer.lock() {
+            Ok(mut mixer) if !mixer.is_format(sample_rate, channels) => {
+                *mixer = SoundboardMixer::new(sample_rate, channels);
+                self.decoded.clear();
+                self.retired.clear();
+            }
+            _ => {}
+        }
+        self.attached = true;
+        Arc::clone(&self.mixer)
+    }
+
+    // Called when throughput is stopped by the user, not on engine restarts
+    pub fn detach(&mut self) {
+        self.attached = false;
+        if let Ok(mut mixer) = self.mixer.lock() {
+            mixer.stop_all();
+        }
+    }
+
+    // Keeps `clip` until no voice holds it and frees the ones whose voices have finished
+    fn retire(&mut self, clip: Option<Arc<ClipData>>) {
+        self.retired.extend(clip);
+        self.retired.retain(|clip| Arc::strong_count(clip) > 1);
+    }
+
+    fn mixer_format(&self) -> anyhow::Result<(u32, u16)> {
+        Ok(self.lock_mixer()?.format())
+    }
+
+    fn lock_mixer(&self) -> anyhow::Result<std::sync::MutexGuard<'_, SoundboardMixer>> {
+        self.mixer
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire soundboard mixer lock: {}", e))
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        persistence::save(
+            SOUNDBOARD_FILE,
+            &SoundboardConfig {
+                clips: self.clips.clone(),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaced_clips_outlive_their_voices() {
+        let mut manager = SoundboardManager {
+            clips: Vec::new(),
+            decoded: HashMap::new(),
+            mixer: Arc::new(Mutex::new(SoundboardMixer::new(48_000, 1))),
+            retired: Vec::new(),
+            attached: true,
+        };
+        let horn = Arc::new(ClipData {
+            name: "horn".to_string(),
+            samples: vec![1.0; 4],
+        });
+        manager.decoded.insert("horn".to_string(), Arc::clone(&horn));
+        manager.mixer.lock().unwrap().trigger(Arc::clone(&horn), 1.0, TriggerMode::Overlap);
+
+        // Replaced while playing - the manager keeps it, the voice is not the last owner
+        let replaced = manager.decoded.remove("horn");
+        drop(horn);
+        manager.retire(replaced);
+        assert_eq!(manager.retired.len(), 1);
+
+        manager.mixer.lock().unwrap().mix_into(&mut [0.0; 8]);
+        assert_eq!(Arc::strong_count(&manager.retired[0]), 1);
+        manager.retire(None);
+        assert!(manager.retired.is_empty());
+    }
+}